@@ -0,0 +1,245 @@
+//! Analysis results kept on disk, one file per track keyed by a sampled hash
+//! of its audio data, so replaying a track doesn't analyse it again and the
+//! library can look results up without decoding.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use xxhash_rust::xxh3::Xxh3;
+
+use super::{ANALYSIS_VERSION, Analysis};
+use crate::config;
+
+/// Directory (inside the cache directory) the results are kept in
+pub const ANALYSIS_CACHE_DIR: &str = "analysis";
+
+/// Chunks of the audio data hashed, spread evenly from its start to its end
+const HASH_CHUNKS: u64 = 16;
+const HASH_CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum AnalysisCacheError {
+    #[error("no cache directory available on this platform")]
+    NoCacheDir,
+    #[error("cannot access the analysis cache: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid cached analysis: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Results of one track, and the analysis version that produced them
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    version: u32,
+    analysis: Analysis,
+}
+
+/// Hash of a file's audio data: its length and chunks spread over it. Tags kept
+/// apart from the audio (ID3v2, ID3v1 and APE around MP3 and other streams,
+/// FLAC metadata blocks) are left out, so editing them, or moving the file,
+/// keeps its cached results. Tags inside other containers change the hash.
+///
+/// Only audio up to `HASH_CHUNKS * HASH_CHUNK_SIZE` (1 MiB) is hashed in full.
+/// Longer audio is sampled, so a change that keeps its length and falls
+/// between the chunks keeps the same hash. Neither the modification time nor
+/// the file size is part of the key, as both change with a retag.
+pub fn content_hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let (start, end) = audio_range(&mut file)?;
+    let length = end - start;
+
+    let mut hasher = Xxh3::new();
+    hasher.update(&length.to_le_bytes());
+    let mut chunk = Vec::new();
+    if length <= HASH_CHUNKS * HASH_CHUNK_SIZE {
+        file.seek(SeekFrom::Start(start))?;
+        file.take(length).read_to_end(&mut chunk)?;
+        hasher.update(&chunk);
+    } else {
+        for i in 0..HASH_CHUNKS {
+            let offset = start + (length - HASH_CHUNK_SIZE) * i / (HASH_CHUNKS - 1);
+            file.seek(SeekFrom::Start(offset))?;
+            chunk.clear();
+            file.by_ref()
+                .take(HASH_CHUNK_SIZE)
+                .read_to_end(&mut chunk)?;
+            hasher.update(&chunk);
+        }
+    }
+    Ok(hasher.digest())
+}
+
+/// Byte range of a file between its leading and trailing tags
+fn audio_range(file: &mut File) -> io::Result<(u64, u64)> {
+    let len = file.metadata()?.len();
+    let mut start = 0;
+    let mut end = len;
+
+    let mut header = [0u8; 10];
+    if read_at(file, 0, &mut header, len) && header.starts_with(b"ID3") {
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |size, byte| (size << 7) | u64::from(byte & 0x7f));
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        start = 10 + size + footer;
+    }
+    let mut magic = [0u8; 4];
+    if read_at(file, start, &mut magic, len) && magic == *b"fLaC" {
+        // Metadata blocks up to the one flagged last, then audio frames
+        let mut block = start + 4;
+        let mut block_header = [0u8; 4];
+        while read_at(file, block, &mut block_header, len) {
+            let size = u32::from_be_bytes([0, block_header[1], block_header[2], block_header[3]]);
+            block += 4 + u64::from(size);
+            if block_header[0] & 0x80 != 0 {
+                break;
+            }
+        }
+        start = block;
+    }
+
+    let mut id3v1 = [0u8; 3];
+    if end >= 128 && read_at(file, end - 128, &mut id3v1, len) && id3v1 == *b"TAG" {
+        end -= 128;
+    }
+    let mut ape = [0u8; 32];
+    if end >= 32 && read_at(file, end - 32, &mut ape, len) && ape.starts_with(b"APETAGEX") {
+        // The size covers the items and this footer, not the optional header
+        let size = u32::from_le_bytes([ape[12], ape[13], ape[14], ape[15]]);
+        let has_header = ape[23] & 0x80 != 0;
+        end = end.saturating_sub(u64::from(size) + if has_header { 32 } else { 0 });
+    }
+    Ok((start.min(end), end))
+}
+
+/// Fill `buf` from `offset`; false when the file is too short
+fn read_at(file: &mut File, offset: u64, buf: &mut [u8], len: u64) -> bool {
+    offset + buf.len() as u64 <= len
+        && file.seek(SeekFrom::Start(offset)).is_ok()
+        && file.read_exact(buf).is_ok()
+}
+
+/// The on-disk cache (e.g. `~/.cache/audido/analysis`)
+#[derive(Debug, Clone)]
+pub struct AnalysisCache {
+    dir: PathBuf,
+}
+
+impl AnalysisCache {
+    /// The cache in the user's cache directory
+    pub fn open() -> Result<Self, AnalysisCacheError> {
+        let dir = config::cache_dir().ok_or(AnalysisCacheError::NoCacheDir)?;
+        Ok(Self::at(&dir.join(ANALYSIS_CACHE_DIR)))
+    }
+
+    pub fn at(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Results cached for this hash; `None` when there are none, or only ones
+    /// from another analysis version
+    pub fn get(&self, hash: u64) -> Result<Option<Analysis>, AnalysisCacheError> {
+        let path = self.entry_path(hash);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let entry: CacheEntry = serde_json::from_slice(&fs::read(path)?)?;
+        Ok((entry.version == ANALYSIS_VERSION).then_some(entry.analysis))
+    }
+
+    /// Keep the results for this hash, replacing what was cached before
+    pub fn put(&self, hash: u64, analysis: &Analysis) -> Result<(), AnalysisCacheError> {
+        let entry = CacheEntry {
+            version: ANALYSIS_VERSION,
+            analysis: analysis.clone(),
+        };
+        config::write_atomic(&self.entry_path(hash), serde_json::to_vec(&entry)?)?;
+        Ok(())
+    }
+
+    fn entry_path(&self, hash: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id3v2(body: &[u8]) -> Vec<u8> {
+        let size = body.len() as u32;
+        let mut out = b"ID3\x04\x00\x00".to_vec();
+        out.extend((0..4).rev().map(|i| ((size >> (7 * i)) & 0x7f) as u8));
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn hash_of(name: &str, bytes: &[u8]) -> u64 {
+        let dir = std::env::temp_dir().join(format!("audido-hash-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        let hash = content_hash(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        hash
+    }
+
+    #[test]
+    fn mp3_tags_are_left_out() {
+        let audio: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let mut plain = audio.clone();
+        plain.extend_from_slice(b"TAG");
+        plain.resize(audio.len() + 128, b' ');
+
+        let mut retagged = id3v2(b"TIT2 a much longer title than before");
+        retagged.extend_from_slice(&audio);
+        let mut ape_footer = b"APETAGEX".to_vec();
+        ape_footer.extend_from_slice(&2000u32.to_le_bytes());
+        ape_footer.extend_from_slice(&32u32.to_le_bytes());
+        ape_footer.resize(32, 0);
+        retagged.extend(ape_footer);
+
+        let mut changed = audio.clone();
+        changed[150_000] ^= 1;
+
+        assert_eq!(
+            hash_of("plain.mp3", &plain),
+            hash_of("retagged.mp3", &retagged)
+        );
+        assert_ne!(
+            hash_of("plain.mp3", &plain),
+            hash_of("changed.mp3", &changed)
+        );
+    }
+
+    #[test]
+    fn flac_metadata_blocks_are_left_out() {
+        fn flac(comment: &[u8], frames: &[u8]) -> Vec<u8> {
+            let mut out = b"fLaC".to_vec();
+            // STREAMINFO, then the last block: a Vorbis comment
+            out.extend_from_slice(&[0x00, 0, 0, 34]);
+            out.extend_from_slice(&[9; 34]);
+            out.push(0x84);
+            out.extend_from_slice(&(comment.len() as u32).to_be_bytes()[1..]);
+            out.extend_from_slice(comment);
+            out.extend_from_slice(frames);
+            out
+        }
+        let frames: Vec<u8> = (0..5_000u32).map(|i| (i % 13) as u8).collect();
+        assert_eq!(
+            hash_of("a.flac", &flac(b"TITLE=A", &frames)),
+            hash_of("b.flac", &flac(b"TITLE=Another title", &frames))
+        );
+        assert_ne!(
+            hash_of("a.flac", &flac(b"TITLE=A", &frames)),
+            hash_of("c.flac", &flac(b"TITLE=A", &frames[1..]))
+        );
+    }
+}
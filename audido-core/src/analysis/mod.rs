@@ -0,0 +1,261 @@
+//! Background analysis of loaded tracks (key, tempo, loudness, waveform),
+//! cached on disk, and whether its results are kept in the files' tags.
+
+pub mod cache;
+
+use std::{fmt, path::Path, time::Instant};
+
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+use thiserror::Error;
+
+use crate::{
+    config,
+    dsp::{
+        loudness::measure_loudness,
+        pitch_detection::{SongKeyArgsBuilder, detect_song_key},
+        tempo::detect_tempo,
+        waveform::{WAVEFORM_BINS, waveform_peaks},
+    },
+    metadata::{AudioMetadata, ChannelLayout, MusicalSongKey},
+};
+
+use self::cache::{AnalysisCache, content_hash};
+
+/// Version of the analysis algorithms. Bump it whenever one of them changes,
+/// so results cached by the old ones are computed again.
+pub const ANALYSIS_VERSION: u32 = 1;
+
+/// File (inside the config directory) the analysis settings are kept in
+pub const ANALYSIS_CONFIG_FILE: &str = "analysis.toml";
+
+/// Loudness ReplayGain 2.0 brings tracks to, in LUFS
+pub const REPLAY_GAIN_REFERENCE: f32 = -18.0;
+
+#[derive(Error, Debug)]
+pub enum AnalysisError {
+    #[error("no config directory available on this platform")]
+    NoConfigDir,
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    #[error("cannot write analysis settings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("cannot serialize analysis settings: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// Whether analysis results are written to the analysed file's tags
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumIter, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TagWriteMode {
+    #[default]
+    Never,
+    /// Ask each time a track was analysed
+    Ask,
+    Always,
+}
+
+/// Analysis settings, from `analysis.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisConfig {
+    pub write_tags: TagWriteMode,
+}
+
+impl AnalysisConfig {
+    pub fn load() -> Result<Self, AnalysisError> {
+        Ok(config::load_toml(ANALYSIS_CONFIG_FILE)?)
+    }
+
+    pub fn save(&self) -> Result<(), AnalysisError> {
+        let path = config::config_file(ANALYSIS_CONFIG_FILE).ok_or(AnalysisError::NoConfigDir)?;
+        config::write_atomic(&path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// What analysing a track found. Only what its tags lacked is reported, so
+/// values already tagged stay `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Analysis {
+    pub key: Option<MusicalSongKey>,
+    pub bpm: Option<f32>,
+    /// Integrated loudness in LUFS
+    pub loudness: Option<f32>,
+    /// Sample peak, 1.0 is full scale
+    pub peak: Option<f32>,
+    /// Peak level of each of `WAVEFORM_BINS` slices of the track
+    pub waveform: Vec<f32>,
+}
+
+impl Analysis {
+    pub fn is_empty(&self) -> bool {
+        self.key.is_none() && self.bpm.is_none() && self.loudness.is_none()
+    }
+
+    /// Whether nothing is left to analyse
+    fn is_complete(&self) -> bool {
+        self.key.is_some()
+            && self.bpm.is_some()
+            && self.loudness.is_some()
+            && !self.waveform.is_empty()
+    }
+
+    /// ReplayGain track gain in dB
+    pub fn replay_gain(&self) -> Option<f32> {
+        self.loudness
+            .map(|loudness| REPLAY_GAIN_REFERENCE - loudness)
+    }
+
+    /// These values, with the ones this lacks taken from `other`
+    pub fn or(self, other: Analysis) -> Analysis {
+        Analysis {
+            key: self.key.or(other.key),
+            bpm: self.bpm.or(other.bpm),
+            loudness: self.loudness.or(other.loudness),
+            peak: self.peak.or(other.peak),
+            waveform: if self.waveform.is_empty() {
+                other.waveform
+            } else {
+                self.waveform
+            },
+        }
+    }
+
+    /// Only the values `known` doesn't have
+    pub fn missing_from(&self, known: &Analysis) -> Analysis {
+        Analysis {
+            key: self.key.filter(|_| known.key.is_none()),
+            bpm: self.bpm.filter(|_| known.bpm.is_none()),
+            loudness: self.loudness.filter(|_| known.loudness.is_none()),
+            peak: self.peak.filter(|_| known.loudness.is_none()),
+            waveform: self.waveform.clone(),
+        }
+    }
+
+    /// Put the results into the track's metadata
+    pub fn apply_to(&self, metadata: &mut AudioMetadata) {
+        metadata.key = self.key.or(metadata.key);
+        metadata.bpm = self.bpm.or(metadata.bpm);
+        metadata.loudness = self.loudness.or(metadata.loudness);
+        metadata.peak = self.peak.or(metadata.peak);
+    }
+}
+
+/// What the metadata holds, from the tags or an earlier analysis
+impl From<&AudioMetadata> for Analysis {
+    fn from(metadata: &AudioMetadata) -> Self {
+        Self {
+            key: metadata.key,
+            bpm: metadata.bpm,
+            loudness: metadata.loudness,
+            peak: metadata.peak,
+            waveform: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Analysis {
+    /// e.g. "Am (8A) · 124 BPM · -9.3 LUFS"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            self.key
+                .map(|key| format!("{} ({})", key, key.camelot_code())),
+            self.bpm.map(|bpm| format!("{:.0} BPM", bpm)),
+            self.loudness
+                .map(|loudness| format!("{:.1} LUFS", loudness)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "{}", parts.join(" · "))
+    }
+}
+
+/// Analyse the decoded interleaved samples of the file at `path` for what its
+/// tags don't say. Results cached for the same audio are used instead of
+/// analysing again, and new ones are added to the cache.
+pub fn analyze_cached(
+    path: &Path,
+    buffer: &[f32],
+    sample_rate: f32,
+    num_channels: u16,
+    tags: &AudioMetadata,
+) -> Analysis {
+    let tagged = Analysis::from(tags);
+    let hash = content_hash(path)
+        .inspect_err(|e| log::warn!("Cannot hash {}: {}", path.display(), e))
+        .ok();
+    let cache = AnalysisCache::open()
+        .inspect_err(|e| log::warn!("Analysis cache unavailable: {}", e))
+        .ok()
+        .zip(hash);
+    let cached = cache
+        .as_ref()
+        .and_then(|(cache, hash)| {
+            cache
+                .get(*hash)
+                .inspect_err(|e| log::warn!("Ignoring cached analysis {:016x}: {}", hash, e))
+                .ok()
+                .flatten()
+        })
+        .unwrap_or_default();
+
+    let known = cached.clone().or(tagged.clone());
+    if known.is_complete() {
+        log::info!("Analysis of {} served from the cache", path.display());
+        return cached.missing_from(&tagged);
+    }
+    let all = cached.or(analyze(buffer, sample_rate, num_channels, &known));
+    if let Some((cache, hash)) = &cache
+        && let Err(e) = cache.put(*hash, &all)
+    {
+        log::warn!("Failed to cache analysis {:016x}: {}", hash, e);
+    }
+    all.missing_from(&tagged)
+}
+
+/// Results cached for a file, found without decoding it
+pub fn cached_analysis(cache: &AnalysisCache, path: &Path) -> Option<Analysis> {
+    let hash = content_hash(path).ok()?;
+    cache.get(hash).ok().flatten()
+}
+
+/// Analyse decoded interleaved samples for what `known` doesn't have yet
+pub fn analyze(buffer: &[f32], sample_rate: f32, num_channels: u16, known: &Analysis) -> Analysis {
+    let start = Instant::now();
+    let mut analysis = Analysis::default();
+
+    if known.key.is_none() {
+        let key = SongKeyArgsBuilder::new(buffer, sample_rate)
+            .channel_layout(ChannelLayout::from_channels(num_channels))
+            .build()
+            .and_then(detect_song_key);
+        match key {
+            Ok(key) => analysis.key = Some(key),
+            Err(e) => log::warn!("Key detection failed: {}", e),
+        }
+    }
+    if known.bpm.is_none() {
+        analysis.bpm = detect_tempo(buffer, sample_rate, num_channels);
+    }
+    if known.loudness.is_none()
+        && let Some(loudness) = measure_loudness(buffer, sample_rate, num_channels)
+    {
+        analysis.loudness = Some(loudness.integrated);
+        analysis.peak = Some(loudness.peak);
+    }
+    if known.waveform.is_empty() {
+        analysis.waveform = waveform_peaks(buffer, num_channels, WAVEFORM_BINS);
+    }
+
+    log::info!(
+        "Audio analysis completed in {:?}: {}",
+        start.elapsed(),
+        analysis
+    );
+    analysis
+}
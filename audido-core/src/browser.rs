@@ -2,6 +2,8 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use crate::{formats::FormatConfig, playlist};
+
 #[derive(Debug, Clone)]
 pub struct FileEntry {
     pub name: String,
@@ -9,11 +11,9 @@ pub struct FileEntry {
     pub is_dir: bool,
 }
 
-const SUPPORTED_EXTENSIONS: &[&str] = &["mp3", "wav", "flac", "ogg", "m4a", "aac"];
-
-/// Get the available files in a directory.
+/// Get the available files in a directory, with audio files as `formats` tells.
 /// If `path` is empty, returns a list of system drives (Virtual Root).
-pub fn get_directory_content(path: &Path) -> io::Result<Vec<FileEntry>> {
+pub fn get_directory_content(path: &Path, formats: &FormatConfig) -> io::Result<Vec<FileEntry>> {
     // Handle "Virtual Root" (List System Drives)
     if path.as_os_str().is_empty() {
         return Ok(get_system_drives());
@@ -26,13 +26,9 @@ pub fn get_directory_content(path: &Path) -> io::Result<Vec<FileEntry>> {
             let entry_path = entry.path();
             let is_dir = entry_path.is_dir();
 
-            // Filter: Include directories and supported audio files
-            let should_include = is_dir
-                || entry_path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
-                    .unwrap_or(false);
+            // Filter: Include directories, playlists and audio files
+            let should_include =
+                is_dir || playlist::is_playlist(&entry_path) || formats.is_audio(&entry_path);
 
             if should_include {
                 let name = entry_path
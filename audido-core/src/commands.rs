@@ -1,7 +1,17 @@
+use std::sync::Arc;
+
 use crate::{
-    dsp::{eq::{EqPreset, FilterNode}, normalization::NormalizationMode},
+    analysis::{Analysis, TagWriteMode},
+    cover::CoverArt,
+    dsp::{
+        eq::{EqPreset, FilterNode},
+        normalization::NormalizationMode,
+    },
     metadata::AudioMetadata,
-    queue::{LoopMode, QueueItem},
+    queue::{LoopMode, QueueItem, QueueSortField},
+    session::Session,
+    stream::StreamState,
+    tags::TagChange,
 };
 
 /// Commands sent from the TUI to the audio engine
@@ -27,10 +37,53 @@ pub enum AudioCommand {
     SetSpeed(f32),
     /// Add files to the queue
     AddToQueue(Vec<String>),
-    /// Remove a file from the queue by index
+    /// Resolve a web page or media URL with yt-dlp, download its audio to the
+    /// cache and append it to the queue (every entry of a playlist page)
+    AddUrl(String),
+    /// Append an internet radio stream (an `http://` URL) to the queue
+    AddStream(String),
+    /// Append the tracks of a playlist file (M3U/M3U8, PLS or XSPF) to the queue
+    LoadPlaylist(String),
+    /// Write the queue to a playlist file, format chosen by extension
+    SavePlaylist(String),
+    /// Insert files right after the current track
+    PlayNext(Vec<String>),
+    /// Remove a file from the queue by item id
     RemoveFromQueue(usize),
+    /// Remove several queue items by id
+    RemoveMany(Vec<usize>),
+    /// Move a queue item (by id) to a new position
+    MoveQueueItem {
+        id: usize,
+        to: usize,
+    },
+    /// Remove duplicate files from the queue
+    Dedupe,
+    /// Write tag changes to files, updating their queued tracks
+    WriteTags {
+        paths: Vec<String>,
+        changes: Vec<TagChange>,
+    },
+    /// Write a track's analysis results to its tags
+    WriteAnalysis {
+        path: String,
+        analysis: Analysis,
+    },
+    /// Change whether analysis results are written to tags, and save it
+    SetAnalysisTagWrite(TagWriteMode),
+    /// Sort the queue by a metadata field
+    SortBy(QueueSortField),
+    /// Reorder the tracks from the current one on for harmonic mixing: close
+    /// Camelot keys and small tempo changes between consecutive tracks
+    SortQueueHarmonic,
     /// Clear the queue
     ClearQueue,
+    /// Replace the queue with files (playlists expand into their tracks) and
+    /// start playing the one at `start`
+    ReplaceQueue {
+        paths: Vec<String>,
+        start: usize,
+    },
     /// Set the loop mode
     SetLoopMode(LoopMode),
     /// Play a specific track from the queue
@@ -43,6 +96,20 @@ pub enum AudioCommand {
     EqSetPreset(EqPreset),
     /// Set all EQ filters
     EqSetAllFilters(Vec<FilterNode>),
+    /// Set the preset and the filters in one step, without switching to the
+    /// preset's own filters first (undo/redo, A/B)
+    EqSetState {
+        preset: EqPreset,
+        filters: Vec<FilterNode>,
+    },
+    /// Replace a single EQ filter by index
+    EqSetFilter(usize, FilterNode),
+    /// Append an EQ filter band
+    EqAddFilter(FilterNode),
+    /// Remove an EQ filter band by index
+    EqRemoveFilter(usize),
+    /// Solo an EQ filter band by index (`None` clears the solo)
+    EqSetSolo(Option<usize>),
     EqResetParameters,
     /// Reset a single EQ filter node to preset default
     EqResetFilterNode(usize),
@@ -54,6 +121,9 @@ pub enum AudioCommand {
     NormalizerSetTargetLevel(f32),
     /// Set headroom in dB (for RMS mode)
     NormalizerSetHeadroom(f32),
+    /// Replace the player state with a saved session; the current track is
+    /// reopened paused at the saved position
+    RestoreSession(Session),
     /// Shutdown the audio engine
     Quit,
 }
@@ -69,17 +139,74 @@ pub enum AudioResponse {
     Stopped,
     /// Audio file loaded successfully with metadata
     Loaded(AudioMetadata),
-    /// Current playback position in seconds and total duration
+    /// Cover art of a loaded file, looked up after `Loaded`; `None` when it
+    /// has none
+    CoverArt {
+        path: String,
+        art: Option<Arc<CoverArt>>,
+    },
+    /// Current playback position in seconds and total duration (`None` for
+    /// live streams)
     Position {
         current: f32,
-        total: f32,
+        total: Option<f32>,
+    },
+    /// Queue contents changed, with the index of the current track
+    QueueUpdated {
+        items: Vec<QueueItem>,
+        current_index: Option<usize>,
     },
-    QueueUpdated(Vec<QueueItem>),
     LoopModeChanged(LoopMode),
     TrackChanged {
         index: usize,
         metadata: AudioMetadata,
     },
+    /// A live stream announced a new title; the current track's metadata
+    StreamTitle(AudioMetadata),
+    /// A live stream started or stopped buffering, or lost its connection
+    StreamState(StreamState),
+    /// DSP settings were switched by a preset rule on track change
+    /// (`rule` is `None` when the user's own settings came back)
+    PresetRuleApplied {
+        rule: Option<String>,
+        eq_enabled: bool,
+        eq_preset: EqPreset,
+        filters: Vec<FilterNode>,
+        normalizer_enabled: bool,
+    },
+    /// Playlist tracks were appended to the queue
+    PlaylistLoaded {
+        path: String,
+        count: usize,
+    },
+    /// Tracks resolved from a URL were appended to the queue
+    UrlAdded {
+        url: String,
+        count: usize,
+    },
+    /// Queue was written to a playlist file
+    PlaylistSaved {
+        path: String,
+        count: usize,
+    },
+    /// Tags were written to `count` files; `errors` tells why the others failed
+    TagsWritten {
+        count: usize,
+        errors: Vec<String>,
+    },
+    /// The current track was analysed; `metadata` holds the results. With
+    /// `ask`, the user decides whether they go into the file's tags.
+    Analyzed {
+        metadata: AudioMetadata,
+        analysis: Analysis,
+        ask: bool,
+    },
+    /// Analysis results were written to the tags of this file
+    AnalysisWritten(String),
+    /// A saved session was restored (volume and DSP settings for the UI to mirror)
+    SessionRestored(Session),
+    /// Gain currently applied by each EQ band in dB (`None` for static bands)
+    EqDynamicGains(Vec<Option<f32>>),
     /// An error occurred
     Error(String),
     /// Engine is shutting down
@@ -97,9 +224,17 @@ pub enum RealtimeAudioCommand {
     SetEqMasterGain(f32),
     /// Set the equalizer to a specific preset
     SetEqPreset(EqPreset),
+    /// Set the preset and the filters at once
+    SetEqState(EqPreset, Vec<FilterNode>),
     ResetEq,
     /// Reset a single filter node
     ResetEqFilterNode(usize),
+    /// Append a filter band
+    AddEqFilter(FilterNode),
+    /// Remove a filter band
+    RemoveEqFilter(usize),
+    /// Solo a filter band (`None` clears the solo)
+    SetEqSolo(Option<usize>),
     SetEqEnabled(bool),
     /// Update normalizer mode
     SetNormalizerMode(NormalizationMode),
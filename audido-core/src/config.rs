@@ -0,0 +1,107 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Name of the directory audido keeps its files under
+const APP_DIR: &str = "audido";
+
+/// A settings file that can't be used
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("cannot read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("invalid {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+}
+
+/// Directory holding the user's configuration files (e.g. `~/.config/audido`)
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// Path of a file inside the configuration directory
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(name))
+}
+
+/// Directory holding data audido builds up, like the library index
+/// (e.g. `~/.local/share/audido`)
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// Path of a file inside the data directory
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(name))
+}
+
+/// Directory holding state audido keeps between runs (e.g. `~/.local/state/audido`).
+/// Platforms without a state directory use the local data directory.
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// Path of a file inside the state directory
+pub fn state_file(name: &str) -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(name))
+}
+
+/// Directory holding data audido can rebuild at any time, like analysis
+/// results (e.g. `~/.cache/audido`)
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// Expand a leading `~` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Read a TOML settings file from the configuration directory. A missing file
+/// (or no configuration directory) means defaults.
+pub fn load_toml<T: DeserializeOwned + Default>(name: &str) -> Result<T, ConfigError> {
+    match config_file(name) {
+        Some(path) if path.exists() => load_toml_from(&path),
+        _ => Ok(T::default()),
+    }
+}
+
+/// Read a TOML settings file
+pub fn load_toml_from<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let content = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    toml::from_str(&content).map_err(|source| ConfigError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Write a file through a temporary one next to it, so readers never see a
+/// partial file and an interrupted write keeps the previous one. The parent
+/// directory is created when missing.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
@@ -0,0 +1,119 @@
+//! Cover art: the picture embedded in a file's tags, or an image such as
+//! `cover.jpg` next to it, decoded for display.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use lofty::{file::TaggedFileExt, picture::PictureType, probe::Probe};
+use thiserror::Error;
+
+/// Names (without extension) of image files used as the cover of the files next to them
+pub const COVER_FILE_NAMES: &[&str] = &["cover", "folder", "front", "album"];
+
+/// Extensions of cover image files
+pub const COVER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+/// Larger pictures are scaled down to this many pixels on their longest side;
+/// no terminal shows more
+pub const MAX_COVER_SIZE: u32 = 512;
+
+#[derive(Error, Debug)]
+pub enum CoverError {
+    #[error("cannot read cover image: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("cannot decode cover image: {0}")]
+    Decode(#[from] image::ImageError),
+}
+
+/// A decoded cover, 8-bit RGB
+#[derive(Clone)]
+pub struct CoverArt {
+    pub width: u32,
+    pub height: u32,
+    /// Rows of `width` RGB triples, top to bottom
+    pub pixels: Vec<u8>,
+}
+
+impl CoverArt {
+    /// Decode an image (JPEG or PNG) and scale it down to `MAX_COVER_SIZE`
+    pub fn decode(bytes: &[u8]) -> Result<Self, CoverError> {
+        let image = image::load_from_memory(bytes)?;
+        let image = if image.width().max(image.height()) > MAX_COVER_SIZE {
+            image.thumbnail(MAX_COVER_SIZE, MAX_COVER_SIZE)
+        } else {
+            image
+        };
+        let rgb = image.to_rgb8();
+        Ok(Self {
+            width: rgb.width(),
+            height: rgb.height(),
+            pixels: rgb.into_raw(),
+        })
+    }
+
+    /// Color of the pixel at (`x`, `y`), which must be inside the image
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let i = ((y * self.width + x) * 3) as usize;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+}
+
+// The pixels would flood any log the art is printed to
+impl fmt::Debug for CoverArt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CoverArt")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The cover of an audio file: its embedded front cover (or any embedded
+/// picture), otherwise a cover image in its folder. `None` when there is none.
+pub fn load_cover(path: &Path) -> Result<Option<CoverArt>, CoverError> {
+    if let Some(bytes) = embedded_picture(path) {
+        return CoverArt::decode(&bytes).map(Some);
+    }
+    match cover_file(path) {
+        Some(file) => CoverArt::decode(&fs::read(file)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Data of the picture embedded in the tags, preferring the front cover
+fn embedded_picture(path: &Path) -> Option<Vec<u8>> {
+    let tagged_file = Probe::open(path).and_then(|p| p.read()).ok()?;
+    let pictures: Vec<_> = tagged_file
+        .tags()
+        .iter()
+        .flat_map(|tag| tag.pictures())
+        .collect();
+    pictures
+        .iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures.first())
+        .map(|picture| picture.data().to_vec())
+}
+
+/// A cover image in the file's folder, e.g. `cover.jpg` or `Folder.png`
+fn cover_file(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent()?;
+    let mut candidates: Vec<(usize, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|file| {
+            let stem = file.file_stem()?.to_str()?.to_lowercase();
+            let ext = file.extension()?.to_str()?.to_lowercase();
+            let rank = COVER_FILE_NAMES.iter().position(|name| *name == stem)?;
+            COVER_EXTENSIONS
+                .contains(&ext.as_str())
+                .then_some((rank, file))
+        })
+        .collect();
+    // Earlier names win, so `cover.jpg` beats `folder.jpg`
+    candidates.sort();
+    candidates.into_iter().next().map(|(_, file)| file)
+}
@@ -0,0 +1,396 @@
+//! CUE sheets: albums ripped to a single file, split into tracks by time range
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{formats, metadata::AudioMetadata};
+
+/// CUE times are `mm:ss:ff` with 75 frames per second
+const FRAMES_PER_SECOND: f32 = 75.0;
+
+#[derive(Error, Debug)]
+pub enum CueError {
+    #[error("cannot read CUE sheet: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("no audio tracks in CUE sheet {0}")]
+    NoTracks(String),
+}
+
+/// Part of an audio file played as a track of its own
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackRange {
+    /// Start in seconds from the beginning of the file
+    pub start: f32,
+    /// End in seconds, `None` to play to the end of the file
+    pub end: Option<f32>,
+}
+
+impl TrackRange {
+    /// Length in seconds, when the end is known
+    pub fn duration(&self) -> Option<f32> {
+        self.end.map(|end| (end - self.start).max(0.0))
+    }
+
+    /// Whether `next` starts where this range ends, so the two play back to back
+    pub fn is_followed_by(&self, next: &TrackRange) -> bool {
+        self.end
+            .is_some_and(|end| (next.start - end).abs() < 1.0 / FRAMES_PER_SECOND)
+    }
+}
+
+/// A parsed CUE sheet
+#[derive(Debug, Clone, Default)]
+pub struct CueSheet {
+    /// Album title
+    pub title: Option<String>,
+    /// Album artist
+    pub performer: Option<String>,
+    pub tracks: Vec<CueTrack>,
+}
+
+/// One `TRACK` of a CUE sheet
+#[derive(Debug, Clone)]
+pub struct CueTrack {
+    pub number: u32,
+    /// Audio file the track is part of
+    pub file: PathBuf,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub range: TrackRange,
+}
+
+impl CueSheet {
+    /// Metadata of a track, until its file is read: the track's title and
+    /// performer, the sheet's for the album
+    pub fn track_metadata(&self, track: &CueTrack) -> AudioMetadata {
+        AudioMetadata {
+            full_file_path: track.file.to_string_lossy().to_string(),
+            title: Some(
+                track
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("Track {:02}", track.number)),
+            ),
+            author: track.performer.clone().or_else(|| self.performer.clone()),
+            album: self.title.clone(),
+            album_artist: self.performer.clone(),
+            track_number: Some(track.number),
+            duration: track.range.duration().unwrap_or(0.0),
+            ..Default::default()
+        }
+    }
+}
+
+/// Whether the path looks like a CUE sheet
+pub fn is_cue(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+}
+
+/// Read a CUE sheet, resolving its files against the sheet's directory
+pub fn load(path: &Path) -> Result<CueSheet, CueError> {
+    let bytes = fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+    let sheet = parse(&content, path.parent().unwrap_or(Path::new("")));
+    if sheet.tracks.is_empty() {
+        return Err(CueError::NoTracks(path.display().to_string()));
+    }
+    Ok(sheet)
+}
+
+/// Parse the text of a CUE sheet. Data tracks are skipped; each audio track
+/// ends where the next track of the same file starts (its `INDEX 01`, so
+/// pregaps play as the end of the previous track).
+pub fn parse(content: &str, base_dir: &Path) -> CueSheet {
+    let mut sheet = CueSheet::default();
+    let mut file: Option<PathBuf> = None;
+    // The track being read and its INDEX 00, used when it has no INDEX 01
+    let mut current: Option<(CueTrack, Option<f32>, Option<f32>)> = None;
+    let mut tracks = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                tracks.extend(current.take().and_then(finish_track));
+                // `FILE "name" WAVE`, the type after the name is ignored
+                let name = if rest.starts_with('"') {
+                    unquote(rest)
+                } else {
+                    rest.rsplit_once(char::is_whitespace)
+                        .map_or(rest, |(name, _)| name)
+                        .to_string()
+                };
+                file = Some(resolve_file(&name, base_dir));
+            }
+            "TRACK" => {
+                tracks.extend(current.take().and_then(finish_track));
+                let mut words = rest.split_whitespace();
+                let number = words.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                let is_audio = words
+                    .next()
+                    .is_some_and(|kind| kind.eq_ignore_ascii_case("AUDIO"));
+                if let (true, Some(file)) = (is_audio, &file) {
+                    let track = CueTrack {
+                        number,
+                        file: file.clone(),
+                        title: None,
+                        performer: None,
+                        range: TrackRange {
+                            start: 0.0,
+                            end: None,
+                        },
+                    };
+                    current = Some((track, None, None));
+                }
+            }
+            "TITLE" | "PERFORMER" => {
+                let value = Some(unquote(rest)).filter(|value| !value.is_empty());
+                let is_title = command.eq_ignore_ascii_case("TITLE");
+                match (&mut current, is_title) {
+                    (Some((track, ..)), true) => track.title = value,
+                    (Some((track, ..)), false) => track.performer = value,
+                    (None, true) if tracks.is_empty() => sheet.title = value,
+                    (None, false) if tracks.is_empty() => sheet.performer = value,
+                    _ => {}
+                }
+            }
+            "INDEX" => {
+                let mut words = rest.split_whitespace();
+                let index = words.next().and_then(|n| n.parse::<u32>().ok());
+                let time = words.next().and_then(parse_time);
+                if let (Some((_, pregap, start)), Some(time)) = (&mut current, time) {
+                    match index {
+                        Some(0) => *pregap = Some(time),
+                        Some(1) => *start = Some(time),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    tracks.extend(current.take().and_then(finish_track));
+
+    // A track runs until the next one in the same file begins
+    let starts: Vec<(PathBuf, f32)> = tracks
+        .iter()
+        .map(|track| (track.file.clone(), track.range.start))
+        .collect();
+    for (track, next) in tracks.iter_mut().zip(starts.iter().skip(1)) {
+        if track.file == next.0 && next.1 > track.range.start {
+            track.range.end = Some(next.1);
+        }
+    }
+    sheet.tracks = tracks;
+    sheet
+}
+
+fn finish_track(
+    (mut track, pregap, start): (CueTrack, Option<f32>, Option<f32>),
+) -> Option<CueTrack> {
+    track.range.start = start.or(pregap)?;
+    Some(track)
+}
+
+/// Parse `mm:ss:ff` into seconds
+fn parse_time(time: &str) -> Option<f32> {
+    let mut parts = time.split(':').map(|part| part.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((minutes * 60 + seconds) as f32 + frames as f32 / FRAMES_PER_SECOND)
+}
+
+/// Value of a command, with or without quotes
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or_default().to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Path of the audio file named by a `FILE` line. Sheets often still name the
+/// `.wav` the album was ripped to, so when that file is missing a supported file
+/// with the same name is used instead.
+fn resolve_file(location: &str, base_dir: &Path) -> PathBuf {
+    let path = base_dir.join(location);
+    if path.exists() {
+        return path;
+    }
+    formats::DEFAULT_EXTENSIONS
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|candidate| candidate.exists())
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALBUM: &str = r#"REM GENRE Rock
+PERFORMER "The Band"
+TITLE "Live Album"
+FILE "Live Album.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Opening"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Guest Song"
+    PERFORMER "A Guest"
+    INDEX 00 03:20:00
+    INDEX 01 03:25:37
+  TRACK 03 AUDIO
+    INDEX 01 07:00:74
+"#;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("audido-cue-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn index_times_are_minutes_seconds_and_frames() {
+        assert_eq!(parse_time("00:00:00"), Some(0.0));
+        assert_close(parse_time("03:25:37").unwrap(), 205.0 + 37.0 / 75.0);
+        assert_close(parse_time("70:01:74").unwrap(), 4201.0 + 74.0 / 75.0);
+        assert_eq!(parse_time("03:25"), None);
+        assert_eq!(parse_time("03:25:00:00"), None);
+        assert_eq!(parse_time("aa:bb:cc"), None);
+    }
+
+    #[test]
+    fn tracks_end_at_the_next_index_01() {
+        let sheet = parse(ALBUM, Path::new("/music"));
+        assert_eq!(sheet.tracks.len(), 3);
+
+        let ranges: Vec<TrackRange> = sheet.tracks.iter().map(|t| t.range).collect();
+        assert_eq!(ranges[0].start, 0.0);
+        // The pregap (INDEX 00) plays as the end of the previous track
+        assert_close(ranges[0].end.unwrap(), 205.0 + 37.0 / 75.0);
+        assert_eq!(ranges[1].start, ranges[0].end.unwrap());
+        assert_close(ranges[1].end.unwrap(), 420.0 + 74.0 / 75.0);
+        assert!(ranges[0].is_followed_by(&ranges[1]));
+        // The last track runs to the end of the file
+        assert_eq!(ranges[2].end, None);
+        assert_eq!(ranges[2].duration(), None);
+    }
+
+    #[test]
+    fn tracks_inherit_performer_and_album_from_the_sheet() {
+        let sheet = parse(ALBUM, Path::new("/music"));
+        assert_eq!(sheet.title.as_deref(), Some("Live Album"));
+        assert_eq!(sheet.performer.as_deref(), Some("The Band"));
+
+        let first = sheet.track_metadata(&sheet.tracks[0]);
+        assert_eq!(first.title.as_deref(), Some("Opening"));
+        assert_eq!(first.author.as_deref(), Some("The Band"));
+        assert_eq!(first.album.as_deref(), Some("Live Album"));
+        assert_eq!(first.track_number, Some(1));
+        assert_eq!(first.full_file_path, "/music/Live Album.flac");
+
+        let guest = sheet.track_metadata(&sheet.tracks[1]);
+        assert_eq!(guest.author.as_deref(), Some("A Guest"));
+        assert_eq!(guest.album_artist.as_deref(), Some("The Band"));
+
+        let untitled = sheet.track_metadata(&sheet.tracks[2]);
+        assert_eq!(untitled.title.as_deref(), Some("Track 03"));
+        assert_eq!(untitled.duration, 0.0);
+    }
+
+    #[test]
+    fn tracks_of_different_files_do_not_end_each_other() {
+        let content = r#"FILE "side a.wav" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 04:00:00
+FILE side_b.wav WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+  TRACK 04 AUDIO
+    INDEX 01 05:00:00
+"#;
+        let sheet = parse(content, Path::new("/rips"));
+        let files: Vec<&Path> = sheet.tracks.iter().map(|t| t.file.as_path()).collect();
+        assert_eq!(
+            files,
+            [
+                Path::new("/rips/side a.wav"),
+                Path::new("/rips/side a.wav"),
+                Path::new("/rips/side_b.wav"),
+                Path::new("/rips/side_b.wav"),
+            ]
+        );
+        let ends: Vec<Option<f32>> = sheet.tracks.iter().map(|t| t.range.end).collect();
+        assert_eq!(ends, [Some(240.0), None, Some(300.0), None]);
+    }
+
+    #[test]
+    fn malformed_lines_and_data_tracks_are_skipped() {
+        let content = "\u{feff}TITLE\nPERFORMER \"Unclosed\nTRACK 01 AUDIO\n\
+                       FILE \"a.wav\" WAVE\nTRACK xx AUDIO\nINDEX 01 1:2\nINDEX\n\
+                       INDEX 01 00:10:00\nTRACK 02 MODE1/2352\nINDEX 01 01:00:00\n\
+                       TRACK 03 AUDIO\nTITLE \"No index\"\nTRACK 04 AUDIO\nINDEX 00 02:00:00\n\
+                       GARBAGE \"line\"\n\n";
+        let sheet = parse(content, Path::new(""));
+        assert_eq!(sheet.title, None);
+        assert_eq!(sheet.performer.as_deref(), Some("Unclosed"));
+
+        // The track before FILE, the data track and the one without an index are left out
+        let numbers: Vec<u32> = sheet.tracks.iter().map(|t| t.number).collect();
+        assert_eq!(numbers, [0, 4]);
+        assert_eq!(sheet.tracks[0].range.start, 10.0);
+        assert_eq!(sheet.tracks[0].range.end, Some(120.0));
+        // A track with only a pregap starts there
+        assert_eq!(sheet.tracks[1].range.start, 120.0);
+    }
+
+    #[test]
+    fn missing_wav_is_looked_up_with_supported_extensions() {
+        let dir = test_dir("resolve");
+        fs::write(dir.join("Album.flac"), b"").unwrap();
+        let sheet_path = dir.join("Album.cue");
+        fs::write(
+            &sheet_path,
+            "FILE \"Album.wav\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n",
+        )
+        .unwrap();
+
+        let sheet = load(&sheet_path).unwrap();
+        assert_eq!(sheet.tracks[0].file, dir.join("Album.flac"));
+        // Nothing on disk keeps the name as written
+        assert_eq!(resolve_file("Other.wav", &dir), dir.join("Other.wav"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sheet_without_audio_tracks_is_an_error() {
+        let dir = test_dir("empty");
+        let sheet_path = dir.join("Empty.cue");
+        fs::write(
+            &sheet_path,
+            "FILE \"a.bin\" BINARY\nTRACK 01 MODE1/2352\nINDEX 01 00:00:00\n",
+        )
+        .unwrap();
+        assert!(matches!(load(&sheet_path), Err(CueError::NoTracks(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
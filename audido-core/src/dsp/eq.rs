@@ -3,13 +3,50 @@
 
 use core::f32;
 use std::f32::consts::PI;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU32, Ordering},
+};
 
 use strum::{EnumIter, IntoEnumIterator};
 
-pub const MAX_EQ_FILTERS: usize = 8;
+pub const MAX_EQ_FILTERS: usize = 32;
+
+/// Number of frames processed between dynamic band gain updates
+const DYNAMIC_BLOCK_FRAMES: usize = 32;
+
+/// Width of the soft knee above the threshold over which a dynamic band
+/// fades in to its full gain (in dB)
+const DYNAMIC_KNEE_DB: f32 = 6.0;
+
+/// Number of samples over which coefficient changes are interpolated
+const SMOOTHING_SAMPLES: u32 = 512;
+
+/// Number of samples over which an old filter chain is crossfaded into a new
+/// one after a structural change (e.g. the order of a band changed)
+const CROSSFADE_SAMPLES: u32 = 1024;
+
+/// Time constant of the master gain smoothing (in ms)
+const MASTER_GAIN_SMOOTHING_MS: f32 = 10.0;
+
+/// Q of the two shelves forming the edges of a band shelf
+const BAND_SHELF_EDGE_Q: f32 = 0.707;
+
+/// Most biquads a single band can need: a band shelf (two sections) at order 16
+const MAX_BAND_BIQUADS: usize = 16;
 
 /// Filter type: Use Direct Form II Biquad Filter
-#[derive(Default, Debug, Clone, Copy, PartialEq, EnumIter, strum::Display)]
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    EnumIter,
+    strum::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum FilterType {
     #[default]
     Peaking,
@@ -19,6 +56,16 @@ pub enum FilterType {
     HighShelf,
     BandPass,
     Notch,
+    /// Flat magnitude, only shifts the phase around the cutoff
+    AllPass,
+    /// Pivots around the cutoff: half the gain is applied above, the inverse below
+    Tilt,
+    /// Flat boost/cut between two edges, the width is set by Q (1/Q octaves)
+    BandShelf,
+    /// 6dB/oct low shelf
+    LowShelfFirstOrder,
+    /// 6dB/oct high shelf
+    HighShelfFirstOrder,
 }
 
 impl FilterType {
@@ -48,7 +95,7 @@ impl FilterType {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FilterNode {
     pub id: i16,
     pub filter_type: FilterType,
@@ -60,6 +107,13 @@ pub struct FilterNode {
     pub q: f32,
     /// Filter order (1 = 6dB/oct, 2 = 12dB/oct, 4 = 24dB/oct, etc)
     pub order: u8,
+    /// Dynamic behaviour of the band. When set, the band only applies its gain
+    /// while the band-filtered signal is above the threshold
+    #[serde(default)]
+    pub dynamic: Option<DynamicBand>,
+    /// Whether the band is temporarily switched off
+    #[serde(default)]
+    pub bypass: bool,
 }
 
 impl FilterNode {
@@ -71,48 +125,26 @@ impl FilterNode {
             gain: 0.0,
             q: 0.707,
             order: 2,
+            dynamic: None,
+            bypass: false,
         }
     }
 
     pub fn magnitude_db(&self, frequency_hz: f32, sample_rate: f32) -> f32 {
         // ensure that frequency is not below zero or greater than nyquist frequency
-        if frequency_hz <= 0.0 || frequency_hz >= sample_rate / 2.0 {
+        if self.bypass || frequency_hz <= 0.0 || frequency_hz >= sample_rate / 2.0 {
             return 0.0;
         }
 
-        let w0 = 2.0 * PI * self.freq / sample_rate;
-        let cos_w0 = w0.cos();
-        let alpha = w0.sin() / (2.0 * self.q);
-        let a_linear = 10.0f32.powf(self.gain / 40.0);
-
-        let (b0, b1, b2, a0, a1, a2) =
-            Biquad::calculate_coefficients(cos_w0, alpha, a_linear, self.filter_type);
-
-        // Evaluate Transfer Function H(z) at z = e^(jw)
         // w (omega) for the target frequency
         let w = 2.0 * PI * frequency_hz / sample_rate;
-        let cos_w = w.cos();
-        let cos_2w = (2.0 * w).cos();
-        let sin_w = w.sin();
-        let sin_2w = (2.0 * w).sin();
-
-        // Numerator (b part) real and imag
-        let num_r = b0 + b1 * cos_w + b2 * cos_2w;
-        let num_i = b1 * sin_w + b2 * sin_2w;
-
-        // Denominator (a part) real and imag
-        let den_r = a0 + a1 * cos_w + a2 * cos_2w;
-        let den_i = a1 * sin_w + a2 * sin_2w;
-
-        let mag_sq = (num_r * num_r + num_i * num_i) / (den_r * den_r + den_i * den_i);
-
-        // Convert to dB: 10 * log10(mag_sq) which is 20 * log10(mag)
-        let single_biquad_db = 10.0 * mag_sq.log10();
 
-        // Account for cascaded biquads: order N uses ceil(N/2) identical biquad sections.
-        // In dB, cascading N sections multiplies the single-section dB by N.
-        let num_biquads = (self.order as f32 / 2.0).ceil().max(1.0);
-        single_biquad_db * num_biquads
+        // Cascaded sections add up in dB
+        BandCoefficients::for_band(self, sample_rate, true)
+            .as_slice()
+            .iter()
+            .map(|section| section.magnitude_db(w))
+            .sum()
     }
 
     pub fn set_filter_type(&mut self, filter_type: FilterType) {
@@ -137,6 +169,82 @@ impl FilterNode {
         self.q = q.clamp(0.1, 10.0);
     }
 
+    /// Number of cascaded biquads needed to realise the filter order.
+    /// A standard Biquad is 2nd order (12dB/oct); order 1 is treated as order 2.
+    fn num_biquads(&self) -> usize {
+        (self.order as usize).div_ceil(2).max(1)
+    }
+
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    /// Write the coefficients of one stage of this filter into `out` and
+    /// return how many biquads the stage has. Most types are a single biquad;
+    /// a band shelf is a pair of high shelves of opposite gain.
+    fn stage_coefficients(&self, sample_rate: f32, out: &mut [Coefficients]) -> usize {
+        match self.filter_type {
+            FilterType::BandShelf => {
+                let edge = 2.0f32.powf(0.5 / self.q);
+                let shelf = FilterNode {
+                    filter_type: FilterType::HighShelf,
+                    q: BAND_SHELF_EDGE_Q,
+                    order: 2,
+                    ..self.clone()
+                };
+                out[0] = Coefficients::for_filter(
+                    &FilterNode {
+                        freq: self.freq / edge,
+                        ..shelf.clone()
+                    },
+                    sample_rate,
+                );
+                out[1] = Coefficients::for_filter(
+                    &FilterNode {
+                        freq: self.freq * edge,
+                        gain: -self.gain,
+                        ..shelf
+                    },
+                    sample_rate,
+                );
+                2
+            }
+            _ => {
+                out[0] = Coefficients::for_filter(self, sample_rate);
+                1
+            }
+        }
+    }
+
+    /// Make the band dynamic (or static again when `None`)
+    pub fn set_dynamic(&mut self, dynamic: Option<DynamicBand>) {
+        self.dynamic = dynamic;
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.dynamic.is_some()
+    }
+
+    /// Filter used by the dynamic detector to listen to this band only
+    fn sidechain_node(&self) -> FilterNode {
+        let filter_type = match self.filter_type {
+            FilterType::LowShelf | FilterType::LowShelfFirstOrder | FilterType::LowPass => {
+                FilterType::LowPass
+            }
+            FilterType::HighShelf | FilterType::HighShelfFirstOrder | FilterType::HighPass => {
+                FilterType::HighPass
+            }
+            _ => FilterType::BandPass,
+        };
+        FilterNode {
+            filter_type,
+            order: 2,
+            dynamic: None,
+            bypass: false,
+            ..self.clone()
+        }
+    }
+
     /// Reset this filter node to default parameter values, preserving its id
     pub fn reset(&mut self) {
         let id = self.id;
@@ -154,66 +262,330 @@ impl Default for FilterNode {
             gain: 0.0,
             q: 0.707,
             order: 2,
+            dynamic: None,
+            bypass: false,
         }
     }
 }
 
+/// Detector settings for a dynamic EQ band
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DynamicBand {
+    /// Level (in dBFS) the band-filtered signal has to exceed before the band engages
+    pub threshold_db: f32,
+    /// Time for the band to reach its gain once the threshold is crossed (in ms)
+    pub attack_ms: f32,
+    /// Time for the band to return to unity once the signal falls back (in ms)
+    pub release_ms: f32,
+}
+
+impl DynamicBand {
+    pub fn set_threshold(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db.clamp(-60.0, 0.0);
+    }
+
+    pub fn set_attack(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.clamp(0.1, 500.0);
+    }
+
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.clamp(1.0, 5000.0);
+    }
+}
+
+impl Default for DynamicBand {
+    fn default() -> Self {
+        Self {
+            threshold_db: -24.0,
+            attack_ms: 5.0,
+            release_ms: 120.0,
+        }
+    }
+}
+
+/// Level detector driving the gain of a dynamic band
+#[derive(Clone, Debug)]
+struct BandDetector {
+    /// Id of the filter node this detector belongs to
+    id: i16,
+    /// Sidechain filter isolating the band, one per channel
+    sidechain: Vec<Biquad>,
+    /// Envelope of the band-filtered signal (linear)
+    envelope: f32,
+    /// Coefficients of the band at its current gain, rewritten every block
+    coefficients: BandCoefficients,
+}
+
+impl BandDetector {
+    fn new(filter: &FilterNode, sample_rate: f32, num_channels: u16) -> Self {
+        let mut detector = Self {
+            id: filter.id,
+            sidechain: vec![Biquad::default(); num_channels as usize],
+            envelope: 0.0,
+            coefficients: BandCoefficients::default(),
+        };
+        detector.update(filter, sample_rate);
+        detector
+    }
+
+    fn update(&mut self, filter: &FilterNode, sample_rate: f32) {
+        let node = filter.sidechain_node();
+        for biquad in &mut self.sidechain {
+            biquad.update(&node, sample_rate);
+        }
+    }
+
+    /// Feed one interleaved block to the detector and return how much of the
+    /// band gain should be applied (0.0 = none, 1.0 = full)
+    fn detect(&mut self, block: &[f32], settings: &DynamicBand, sample_rate: f32) -> f32 {
+        let num_ch = self.sidechain.len();
+        if num_ch == 0 {
+            return 0.0;
+        }
+
+        let attack = time_coefficient(settings.attack_ms, sample_rate);
+        let release = time_coefficient(settings.release_ms, sample_rate);
+
+        for frame in block.chunks(num_ch) {
+            // Linked detection: the loudest channel drives the band
+            let mut peak = 0.0f32;
+            for (sample, biquad) in frame.iter().zip(self.sidechain.iter_mut()) {
+                peak = peak.max(biquad.process(*sample).abs());
+            }
+
+            let coeff = if peak > self.envelope {
+                attack
+            } else {
+                release
+            };
+            self.envelope = coeff * self.envelope + (1.0 - coeff) * peak;
+        }
+
+        let envelope_db = 20.0 * self.envelope.max(1e-9).log10();
+        ((envelope_db - settings.threshold_db) / DYNAMIC_KNEE_DB).clamp(0.0, 1.0)
+    }
+}
+
+/// One-pole smoothing coefficient for the given time constant
+fn time_coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    let samples = time_ms * 0.001 * sample_rate;
+    if samples <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+/// Normalized biquad coefficients (a0 = 1)
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Coefficients {
+    /// Coefficients of a filter that passes the signal through untouched
+    const IDENTITY: Coefficients = Coefficients {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: 0.0,
+    };
+
+    fn for_filter(filter: &FilterNode, sample_rate: f32) -> Self {
+        let w0 = 2.0 * PI * filter.freq / sample_rate;
+        let cos_w0 = w0.cos();
+        let alpha = w0.sin() / (2.0 * filter.q);
+        // Bilinear transform prewarping, used by the first-order designs
+        let k = (w0 / 2.0).tan();
+
+        // amplitude in linear scale (converted from dB)
+        // A = 10^(Adb / 40.0)
+        let a = 10.0f32.powf(filter.gain / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) =
+            Biquad::calculate_coefficients(cos_w0, alpha, k, a, filter.filter_type);
+
+        // Normalize coefficients by a0
+        let inv_a0 = 1.0 / a0;
+        Self {
+            b0: b0 * inv_a0,
+            b1: b1 * inv_a0,
+            b2: b2 * inv_a0,
+            a1: a1 * inv_a0,
+            a2: a2 * inv_a0,
+        }
+    }
+
+    /// Per-sample increment to move from `self` to `target` in `steps` samples
+    fn step_towards(&self, target: &Coefficients, steps: u32) -> Self {
+        let n = steps.max(1) as f32;
+        Self {
+            b0: (target.b0 - self.b0) / n,
+            b1: (target.b1 - self.b1) / n,
+            b2: (target.b2 - self.b2) / n,
+            a1: (target.a1 - self.a1) / n,
+            a2: (target.a2 - self.a2) / n,
+        }
+    }
+
+    /// Magnitude response in dB at angular frequency `w` (rad/sample)
+    fn magnitude_db(&self, w: f32) -> f32 {
+        // Evaluate Transfer Function H(z) at z = e^(jw)
+        let cos_w = w.cos();
+        let cos_2w = (2.0 * w).cos();
+        let sin_w = w.sin();
+        let sin_2w = (2.0 * w).sin();
+
+        // Numerator (b part) real and imag
+        let num_r = self.b0 + self.b1 * cos_w + self.b2 * cos_2w;
+        let num_i = self.b1 * sin_w + self.b2 * sin_2w;
+
+        // Denominator (a part) real and imag
+        let den_r = 1.0 + self.a1 * cos_w + self.a2 * cos_2w;
+        let den_i = self.a1 * sin_w + self.a2 * sin_2w;
+
+        let mag_sq = (num_r * num_r + num_i * num_i) / (den_r * den_r + den_i * den_i);
+
+        // Convert to dB: 10 * log10(mag_sq) which is 20 * log10(mag)
+        10.0 * mag_sq.log10()
+    }
+
+    fn add(&mut self, step: &Coefficients) {
+        self.b0 += step.b0;
+        self.b1 += step.b1;
+        self.b2 += step.b2;
+        self.a1 += step.a1;
+        self.a2 += step.a2;
+    }
+}
+
+/// Coefficients of every biquad in the cascade realising one band. Stored
+/// inline so dynamic bands can be recomputed on the audio thread without allocating.
+#[derive(Clone, Copy, Default, Debug)]
+struct BandCoefficients {
+    biquads: [Coefficients; MAX_BAND_BIQUADS],
+    len: usize,
+}
+
+impl BandCoefficients {
+    fn for_band(filter: &FilterNode, sample_rate: f32, active: bool) -> Self {
+        let mut coefficients = Self::default();
+        coefficients.update(filter, sample_rate, active);
+        coefficients
+    }
+
+    /// Recompute the coefficients in place. Inactive bands keep the same chain
+    /// length but become pass-throughs, so toggling bypass or solo ramps instead of clicking.
+    fn update(&mut self, filter: &FilterNode, sample_rate: f32, active: bool) {
+        let stage_len = filter.stage_coefficients(sample_rate, &mut self.biquads);
+        self.len = (stage_len * filter.num_biquads()).min(MAX_BAND_BIQUADS);
+        for i in stage_len..self.len {
+            self.biquads[i] = self.biquads[i % stage_len];
+        }
+        if !active {
+            self.biquads[..self.len].fill(Coefficients::IDENTITY);
+        }
+    }
+
+    fn as_slice(&self) -> &[Coefficients] {
+        &self.biquads[..self.len]
+    }
+}
+
 /// implement Biquad Filter (Direct Form II Transposed)
 /// $$ y[n] = frac{b0/a0}x[n] + frac{b1/a0}x[n-1] + frac{b2/a0}x[n-2] - frac{a1/a0}y[n-1] - frac{a2/a0}y[n-2] $$
+///
+/// Coefficient changes are interpolated over a number of samples instead of
+/// being swapped instantly, which avoids zipper noise while a band is edited.
 #[derive(Clone, Default, Debug)]
 struct Biquad {
-    // Coefficients
-    a1: f32,
-    a2: f32,
-    b0: f32,
-    b1: f32,
-    b2: f32,
+    // Coefficients currently in use
+    coeffs: Coefficients,
+    // Coefficients being ramped towards
+    target: Coefficients,
+    step: Coefficients,
+    ramp_remaining: u32,
+    // Whether coefficients were ever set (fresh biquads snap instead of ramping)
+    primed: bool,
     // Previous State
     z1: f32,
     z2: f32,
 }
 
 impl Biquad {
+    /// A biquad that starts as a pass-through, so it can fade in
+    fn identity() -> Self {
+        Self {
+            coeffs: Coefficients::IDENTITY,
+            target: Coefficients::IDENTITY,
+            primed: true,
+            ..Default::default()
+        }
+    }
+
     fn process(&mut self, sample: f32) -> f32 {
+        if self.ramp_remaining > 0 {
+            self.ramp_remaining -= 1;
+            if self.ramp_remaining == 0 {
+                self.coeffs = self.target;
+            } else {
+                self.coeffs.add(&self.step);
+            }
+        }
+
         // Direct Form II Transposed difference equation
         // y[n] = b0*x[n] + z1[n-1]
         // z1[n] = b1*x[n] - a1*y[n] + z2[n-1]
         // z2[n] = b2*x[n] - a2*y[n]
-
-        let out = self.b0 * sample + self.z1;
-        self.z1 = self.b1 * sample - self.a1 * out + self.z2;
-        self.z2 = self.b2 * sample - self.a2 * out;
+        let c = &self.coeffs;
+        let out = c.b0 * sample + self.z1;
+        self.z1 = c.b1 * sample - c.a1 * out + self.z2;
+        self.z2 = c.b2 * sample - c.a2 * out;
 
         out
     }
 
-    /// Recalculate coefficients
+    /// Recalculate coefficients, ramping over the default smoothing time
     fn update(&mut self, filter: &FilterNode, sample_rate: f32) {
-        let w0 = 2.0 * PI * filter.freq / sample_rate;
-        let cos_w0 = w0.cos();
-        let alpha = w0.sin() / (2.0 * filter.q);
+        self.set_target(
+            Coefficients::for_filter(filter, sample_rate),
+            SMOOTHING_SAMPLES,
+        );
+    }
 
-        // amplitude in linear scale (converted from dB)
-        // A = 10^(Adb / 40.0)
-        let a = 10.0f32.powf(filter.gain / 40.0);
+    fn set_target(&mut self, target: Coefficients, ramp_samples: u32) {
+        if !self.primed || ramp_samples == 0 {
+            self.coeffs = target;
+            self.target = target;
+            self.ramp_remaining = 0;
+            self.primed = true;
+            return;
+        }
+        if target == self.target && self.ramp_remaining == 0 {
+            return;
+        }
 
-        let (b0, b1, b2, a0, a1, a2) =
-            Self::calculate_coefficients(cos_w0, alpha, a, filter.filter_type);
+        self.target = target;
+        self.step = self.coeffs.step_towards(&target, ramp_samples);
+        self.ramp_remaining = ramp_samples;
+    }
 
-        // Normalize coefficients by a0
-        let inv_a0 = 1.0 / a0;
-        self.b0 = b0 * inv_a0;
-        self.b1 = b1 * inv_a0;
-        self.b2 = b2 * inv_a0;
-        self.a1 = a1 * inv_a0;
-        self.a2 = a2 * inv_a0;
+    fn is_ramping(&self) -> bool {
+        self.ramp_remaining > 0
     }
 
     /// Helper to calculate (b0, b1, b2, a0, a1, a2)
-    /// This code is adapted from RBJ Audio EQ Cookbook
+    /// This code is adapted from RBJ Audio EQ Cookbook; the first-order
+    /// designs are bilinear transforms of the analog prototypes, with `k = tan(w0/2)`
     fn calculate_coefficients(
         cos_w0: f32,
         alpha: f32,
+        k: f32,
         a: f32,
         filter_type: FilterType,
     ) -> (f32, f32, f32, f32, f32, f32) {
@@ -273,11 +645,47 @@ impl Biquad {
                 -2.0 * cos_w0,
                 1.0 - alpha,
             ),
+            FilterType::AllPass => (
+                1.0 - alpha,
+                -2.0 * cos_w0,
+                1.0 + alpha,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            // H(s) = (A*s + 1) / (s + A): 1/A at DC, A at nyquist
+            FilterType::Tilt => (a + k, k - a, 0.0, 1.0 + a * k, a * k - 1.0, 0.0),
+            // H(s) = (s + A) / (s + 1/A): A^2 at DC
+            FilterType::LowShelfFirstOrder => {
+                (1.0 + a * k, a * k - 1.0, 0.0, 1.0 + k / a, k / a - 1.0, 0.0)
+            }
+            // H(s) = A^2 (s + 1/A) / (s + A): A^2 at nyquist
+            FilterType::HighShelfFirstOrder => (
+                a * a * (1.0 + k / a),
+                a * a * (k / a - 1.0),
+                0.0,
+                1.0 + a * k,
+                a * k - 1.0,
+                0.0,
+            ),
+            // Realised as two shelving sections, see `FilterNode::sections`
+            FilterType::BandShelf => (1.0, 0.0, 0.0, 1.0, 0.0, 0.0),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Default,
+    strum::Display,
+    strum::EnumString,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum EqPreset {
     #[default]
     Flat,
@@ -290,16 +698,18 @@ pub enum EqPreset {
 }
 
 fn create_flat_filters() -> Vec<FilterNode> {
-    let mut filters = Vec::with_capacity(MAX_EQ_FILTERS);
     let freqs = [40.0, 200.0, 500.0, 1000., 2000., 5000., 10000., 15000.];
-    for i in 0..MAX_EQ_FILTERS {
+    let mut filters = Vec::with_capacity(freqs.len());
+    for (i, freq) in freqs.into_iter().enumerate() {
         filters.push(FilterNode {
             id: i as i16,
             filter_type: FilterType::Peaking,
-            freq: *freqs.get(i).unwrap_or(&1000.0),
+            freq,
             gain: 0.0,
             q: 0.707,
             order: 2,
+            dynamic: None,
+            bypass: false,
         });
     }
     filters
@@ -319,20 +729,127 @@ impl EqPreset {
                 gain: 6.0,
                 q: 0.707,
                 order: 2,
+                dynamic: None,
+                bypass: false,
             }],
             EqPreset::Custom => create_flat_filters(),
         }
     }
 }
 
+/// Chain of biquads realising one filter node on one channel
+#[derive(Clone, Debug)]
+struct BandChain {
+    /// Id of the filter node this chain belongs to
+    id: i16,
+    /// Type of the filter node the biquads realise
+    filter_type: FilterType,
+    biquads: Vec<Biquad>,
+    /// Previous chain, crossfaded out after a structural change
+    fading: Option<Vec<Biquad>>,
+    fade_position: u32,
+    /// The node was removed; the chain ramps to unity and is then dropped
+    retiring: bool,
+}
+
+impl BandChain {
+    /// Build a chain for `filter` out of `coefficients`. When `fade_in` is set the
+    /// chain starts as a pass-through and ramps to the filter response instead of snapping to it.
+    fn new(filter: &FilterNode, coefficients: &[Coefficients], fade_in: bool) -> Self {
+        let biquads = coefficients
+            .iter()
+            .map(|coeffs| {
+                let mut bq = if fade_in {
+                    Biquad::identity()
+                } else {
+                    Biquad::default()
+                };
+                bq.set_target(*coeffs, SMOOTHING_SAMPLES);
+                bq
+            })
+            .collect();
+
+        Self {
+            id: filter.id,
+            filter_type: filter.filter_type,
+            biquads,
+            fading: None,
+            fade_position: 0,
+            retiring: false,
+        }
+    }
+
+    /// Move the chain to new filter parameters without resetting its state
+    fn retune(&mut self, filter: &FilterNode, coefficients: &[Coefficients], ramp_samples: u32) {
+        // Interpolating between coefficients of different filter types can pass
+        // through unstable biquads, so only ramp within the same type
+        if self.filter_type == filter.filter_type && self.biquads.len() == coefficients.len() {
+            for (biquad, coeffs) in self.biquads.iter_mut().zip(coefficients) {
+                biquad.set_target(*coeffs, ramp_samples);
+            }
+            return;
+        }
+
+        // The type or topology changed: keep the old chain running and crossfade into a new one
+        let fresh = BandChain::new(filter, coefficients, false);
+        self.filter_type = filter.filter_type;
+        self.fading = Some(std::mem::replace(&mut self.biquads, fresh.biquads));
+        self.fade_position = 0;
+    }
+
+    /// Ramp the chain to a pass-through so it can be dropped without a click
+    fn retire(&mut self) {
+        self.retiring = true;
+        for biquad in &mut self.biquads {
+            biquad.set_target(Coefficients::IDENTITY, SMOOTHING_SAMPLES);
+        }
+    }
+
+    fn is_retired(&self) -> bool {
+        self.retiring && self.fading.is_none() && !self.biquads.iter().any(Biquad::is_ramping)
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let mut out = sample;
+        for biquad in &mut self.biquads {
+            out = biquad.process(out);
+        }
+
+        if let Some(old_chain) = &mut self.fading {
+            let mut old_out = sample;
+            for biquad in old_chain.iter_mut() {
+                old_out = biquad.process(old_out);
+            }
+
+            let mix = self.fade_position as f32 / CROSSFADE_SAMPLES as f32;
+            out = old_out * (1.0 - mix) + out * mix;
+
+            self.fade_position += 1;
+            if self.fade_position >= CROSSFADE_SAMPLES {
+                self.fading = None;
+            }
+        }
+
+        out
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Equalizer {
     pub sample_rate: u32,
     pub preset: EqPreset,
     pub filters: Vec<FilterNode>,
-    /// Internal DSP state (vector of vector because one node can have multiple biquads for high order)
-    processors: Vec<Vec<Vec<Biquad>>>, // [channel][filter][biquad]
+    /// Internal DSP state, one chain per filter node (plus chains fading out)
+    processors: Vec<Vec<BandChain>>, // [channel][band]
+    /// Level detectors of the dynamic bands (`None` for static bands)
+    detectors: Vec<Option<BandDetector>>,
+    /// Gain currently applied by each band in dB (shared with the UI side for metering)
+    dynamic_gains: Arc<Vec<AtomicU32>>,
     pub master_gain: f32,
+    /// Master gain actually applied, smoothed towards `master_gain`
+    applied_master_gain: f32,
+    /// Index of the band being soloed, all other bands are bypassed while set
+    solo: Option<usize>,
     num_channels: u16,
 }
 
@@ -344,7 +861,15 @@ impl Equalizer {
             preset,
             filters: preset.set_filters(),
             processors: Vec::new(), // Initialized in rebuild
+            detectors: Vec::new(),
+            dynamic_gains: Arc::new(
+                (0..MAX_EQ_FILTERS)
+                    .map(|_| AtomicU32::new(f32::to_bits(0.0)))
+                    .collect(),
+            ),
             master_gain: 1.0,
+            applied_master_gain: 1.0,
+            solo: None,
             num_channels,
         };
         // Initialize processors based on initial filters
@@ -353,18 +878,52 @@ impl Equalizer {
     }
 
     pub fn process_frame(&mut self, frame: &mut [f32]) {
-        if (self.master_gain - 1.0).abs() > f32::EPSILON {
-            for sample in frame.iter_mut() {
-                *sample *= self.master_gain;
-            }
-        }
-
         let num_ch = self.num_channels as usize;
         if num_ch == 0 {
             return;
         }
 
-        for (i, sample) in frame.iter_mut().enumerate() {
+        self.apply_master_gain(frame);
+
+        if !self.has_dynamic_bands() {
+            self.process_block(frame);
+            return;
+        }
+
+        // Dynamic bands need their gain re-evaluated regularly, so work in small blocks
+        for block in frame.chunks_mut(DYNAMIC_BLOCK_FRAMES * num_ch) {
+            self.update_dynamic_bands(block);
+            self.process_block(block);
+        }
+    }
+
+    /// Apply the master gain, gliding towards it when it was just changed
+    fn apply_master_gain(&mut self, frame: &mut [f32]) {
+        let target = self.master_gain;
+        if (self.applied_master_gain - target).abs() <= 1e-5 {
+            self.applied_master_gain = target;
+            if (target - 1.0).abs() > f32::EPSILON {
+                for sample in frame.iter_mut() {
+                    *sample *= target;
+                }
+            }
+            return;
+        }
+
+        let coeff = time_coefficient(MASTER_GAIN_SMOOTHING_MS, self.sample_rate as f32);
+        for samples in frame.chunks_mut(self.num_channels as usize) {
+            self.applied_master_gain = coeff * self.applied_master_gain + (1.0 - coeff) * target;
+            for sample in samples {
+                *sample *= self.applied_master_gain;
+            }
+        }
+    }
+
+    /// Run an interleaved block through the filter chains
+    fn process_block(&mut self, block: &mut [f32]) {
+        let num_ch = self.num_channels as usize;
+
+        for (i, sample) in block.iter_mut().enumerate() {
             let channel_idx = i % num_ch;
 
             // Access the processor chain for this specific channel
@@ -372,15 +931,74 @@ impl Equalizer {
                 let mut s = *sample;
 
                 // Pass the sample through every filter node in the chain
-                for filter_biquads in channel_filters {
-                    // Pass through every biquad (for high-order cascades)
-                    for biquad in filter_biquads {
-                        s = biquad.process(s);
-                    }
+                for chain in channel_filters {
+                    s = chain.process(s);
                 }
                 *sample = s;
             }
         }
+
+        // Drop chains of removed bands once they are fully faded out
+        for channel_filters in &mut self.processors {
+            channel_filters.retain(|chain| !chain.is_retired());
+        }
+    }
+
+    fn has_dynamic_bands(&self) -> bool {
+        self.detectors.iter().any(Option::is_some)
+    }
+
+    /// Run the detectors on the incoming block and retune the dynamic bands
+    fn update_dynamic_bands(&mut self, block: &[f32]) {
+        let sample_rate = self.sample_rate as f32;
+        let ramp_samples = (block.len() / self.num_channels as usize) as u32;
+
+        for (index, detector) in self.detectors.iter_mut().enumerate() {
+            let (Some(detector), Some(filter)) = (detector, self.filters.get(index)) else {
+                continue;
+            };
+            let Some(settings) = filter.dynamic else {
+                continue;
+            };
+
+            let amount = detector.detect(block, &settings, sample_rate);
+            let live_node = FilterNode {
+                gain: filter.gain * amount,
+                ..filter.clone()
+            };
+            detector.coefficients.update(
+                &live_node,
+                sample_rate,
+                is_band_active(self.solo, index, filter),
+            );
+
+            for channel_filters in &mut self.processors {
+                if let Some(chain) = channel_filters
+                    .iter_mut()
+                    .find(|chain| chain.id == filter.id && !chain.retiring)
+                {
+                    chain.retune(filter, detector.coefficients.as_slice(), ramp_samples);
+                }
+            }
+
+            if let Some(meter) = self.dynamic_gains.get(index) {
+                meter.store(f32::to_bits(live_node.gain), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Gain currently applied by each dynamic band in dB (`None` for static bands)
+    pub fn dynamic_gains_db(&self) -> Vec<Option<f32>> {
+        self.filters
+            .iter()
+            .enumerate()
+            .map(|(index, filter)| {
+                filter.dynamic?;
+                self.dynamic_gains
+                    .get(index)
+                    .map(|meter| f32::from_bits(meter.load(Ordering::Relaxed)))
+            })
+            .collect()
     }
 
     pub fn update_preset(&mut self, preset: EqPreset) {
@@ -389,37 +1007,129 @@ impl Equalizer {
             let config = self.preset.set_filters();
             self.filters = config;
 
-            // Rebuild the DSP processors to reflect the new config
-            self.rebuild_processors();
+            // Move the running chains to the new config
+            self.parameters_changed();
+        }
+    }
+
+    /// Append a band. Returns `false` when the equalizer is already full.
+    pub fn add_filter(&mut self, filter: FilterNode) -> bool {
+        if self.filters.len() >= MAX_EQ_FILTERS {
+            return false;
+        }
+        self.filters.push(filter);
+        self.parameters_changed();
+        true
+    }
+
+    /// Remove the band at `index`, keeping the solo on the same band if it survives
+    pub fn remove_filter(&mut self, index: usize) -> Option<FilterNode> {
+        if index >= self.filters.len() {
+            return None;
+        }
+        let removed = self.filters.remove(index);
+        // Meters are positional and shared with the realtime equalizer: clear
+        // the ones from the removed band on, the next block fills them in again
+        for meter in self.dynamic_gains.iter().skip(index) {
+            meter.store(f32::to_bits(0.0), Ordering::Relaxed);
+        }
+        self.solo = match self.solo {
+            Some(solo) if solo == index => None,
+            Some(solo) if solo > index => Some(solo - 1),
+            solo => solo,
+        };
+        self.parameters_changed();
+        Some(removed)
+    }
+
+    /// Solo the band at `index` (or clear the solo when `None`)
+    pub fn set_solo(&mut self, solo: Option<usize>) {
+        if self.solo != solo {
+            self.solo = solo;
+            self.parameters_changed();
         }
     }
 
-    /// Rebuild the DSP processors. called when the parameter is changed
+    pub fn solo(&self) -> Option<usize> {
+        self.solo
+    }
+
+    /// Coefficients for each band, with bypassed/non-soloed bands turned into pass-throughs
+    fn chain_coefficients(&self) -> Vec<BandCoefficients> {
+        let sample_rate = self.sample_rate as f32;
+        self.filters
+            .iter()
+            .enumerate()
+            .map(|(index, filter)| {
+                BandCoefficients::for_band(
+                    filter,
+                    sample_rate,
+                    is_band_active(self.solo, index, filter),
+                )
+            })
+            .collect()
+    }
+
+    /// Rebuild the DSP processors from scratch (drops all filter state)
     fn rebuild_processors(&mut self) {
-        self.processors.clear();
-
-        for _ in 0..self.num_channels {
-            let mut channel_chain = Vec::with_capacity(self.filters.len());
-            for filter_node in &self.filters {
-                // A standard Biquad is 2nd order (12dB/oct).
-                // For order 4 (24dB/oct), we need 2 biquads.
-                // For order 1 (6dB/oct), we technically need 0.5 biquads, but we treat it as order 2 with reduced slope logic
-
-                let num_biquads = (filter_node.order as f32 / 2.0).ceil() as usize;
-                let count = if num_biquads == 0 { 1 } else { num_biquads };
-
-                let mut biquads = Vec::with_capacity(count);
-                for _ in 0..count {
-                    let mut bq = Biquad::default();
-                    bq.update(filter_node, self.sample_rate as f32);
-                    biquads.push(bq);
+        let sample_rate = self.sample_rate as f32;
+        let coefficients = self.chain_coefficients();
+        self.processors = (0..self.num_channels)
+            .map(|_| {
+                self.filters
+                    .iter()
+                    .zip(&coefficients)
+                    .map(|(filter, coeffs)| BandChain::new(filter, coeffs.as_slice(), false))
+                    .collect()
+            })
+            .collect();
+
+        self.detectors = self
+            .filters
+            .iter()
+            .map(|filter| {
+                filter
+                    .is_dynamic()
+                    .then(|| BandDetector::new(filter, sample_rate, self.num_channels))
+            })
+            .collect();
+    }
+
+    /// Keep the dynamic band detectors in sync with the filter parameters.
+    /// Detectors are matched to filter nodes by id, so bands that stay dynamic
+    /// keep their envelope when other bands are added or removed.
+    fn update_detectors(&mut self) {
+        let sample_rate = self.sample_rate as f32;
+        let mut previous: Vec<BandDetector> = std::mem::take(&mut self.detectors)
+            .into_iter()
+            .flatten()
+            .collect();
+
+        self.detectors = self
+            .filters
+            .iter()
+            .map(|filter| {
+                if !filter.is_dynamic() {
+                    return None;
                 }
-                channel_chain.push(biquads);
-            }
-            self.processors.push(channel_chain);
-        }
+                match previous
+                    .iter()
+                    .position(|detector| detector.id == filter.id)
+                {
+                    Some(pos) => {
+                        let mut detector = previous.swap_remove(pos);
+                        detector.update(filter, sample_rate);
+                        Some(detector)
+                    }
+                    None => Some(BandDetector::new(filter, sample_rate, self.num_channels)),
+                }
+            })
+            .collect();
     }
 
+    /// Bring the processors in line with `filters`. Chains are matched to filter
+    /// nodes by id so their state survives edits, new bands fade in and removed
+    /// bands fade out; coefficient changes are interpolated.
     pub fn parameters_changed(&mut self) {
         // If the channel configuration doesn't match, we must do a full rebuild
         if self.processors.len() != self.num_channels as usize {
@@ -427,40 +1137,56 @@ impl Equalizer {
             return;
         }
 
-        // Iterate over every channel to update its specific processors
-        for channel_filters in &mut self.processors {
-            // If the number of filters changed (e.g. added a band), rebuild
-            if channel_filters.len() != self.filters.len() {
-                self.rebuild_processors();
-                return;
-            }
-
-            // Update filters inside this channel
-            for (i, filter_node) in self.filters.iter().enumerate() {
-                let biquad_chain = &mut channel_filters[i];
+        // A solo on a band that no longer exists would silence every band
+        if self.solo.is_some_and(|solo| solo >= self.filters.len()) {
+            self.solo = None;
+        }
 
-                // Handle Order Changes (resize chain while keeping state where possible)
-                let required_biquads = (filter_node.order as f32 / 2.0).ceil() as usize;
-                let count = if required_biquads == 0 {
-                    1
-                } else {
-                    required_biquads
+        let coefficients = self.chain_coefficients();
+        for channel_filters in &mut self.processors {
+            let mut previous = std::mem::take(channel_filters);
+
+            for (filter, coeffs) in self.filters.iter().zip(&coefficients) {
+                let chain = match previous
+                    .iter()
+                    .position(|chain| chain.id == filter.id && !chain.retiring)
+                {
+                    Some(pos) => {
+                        let mut chain = previous.remove(pos);
+                        chain.retune(filter, coeffs.as_slice(), SMOOTHING_SAMPLES);
+                        chain
+                    }
+                    None => BandChain::new(filter, coeffs.as_slice(), true),
                 };
+                channel_filters.push(chain);
+            }
 
-                if biquad_chain.len() < count {
-                    // Order increased: append new zero-state biquads
-                    biquad_chain.resize_with(count, Biquad::default);
-                } else if biquad_chain.len() > count {
-                    // Order decreased: truncate but keep state of remaining
-                    biquad_chain.truncate(count);
+            // Whatever is left belongs to removed bands
+            for mut chain in previous {
+                if !chain.retiring {
+                    chain.retire();
                 }
+                channel_filters.push(chain);
+            }
+        }
+
+        self.update_detectors();
+    }
 
-                // Update coefficients for all biquads (preserves z1/z2)
-                for biquad in biquad_chain.iter_mut() {
-                    biquad.update(filter_node, self.sample_rate as f32);
+    /// Finish all pending coefficient ramps and crossfades immediately.
+    /// Used when the equalizer starts on a fresh stream with nothing to smooth from.
+    pub fn settle(&mut self) {
+        for channel_filters in &mut self.processors {
+            channel_filters.retain(|chain| !chain.retiring);
+            for chain in channel_filters.iter_mut() {
+                chain.fading = None;
+                for biquad in &mut chain.biquads {
+                    biquad.coeffs = biquad.target;
+                    biquad.ramp_remaining = 0;
                 }
             }
         }
+        self.applied_master_gain = self.master_gain;
     }
 
     pub fn reset_parameters(&mut self) {
@@ -471,22 +1197,39 @@ impl Equalizer {
 
     pub fn reset_filter_node_param(&mut self, node_index: usize) -> anyhow::Result<()> {
         let preset_filters = self.preset.set_filters();
-        let default_node = preset_filters.get(node_index).cloned().unwrap_or_else(|| {
-            let mut node = FilterNode::default();
-            node.id = node_index as i16;
-            node
-        });
+        let default_node = preset_filters.get(node_index).cloned().unwrap_or_default();
 
         let filter_node = self
             .filters
             .get_mut(node_index)
             .ok_or(anyhow::anyhow!("Filter node not found"))?;
-        *filter_node = default_node;
+        // Keep the id so the running chain is retuned rather than replaced
+        *filter_node = FilterNode {
+            id: filter_node.id,
+            ..default_node
+        };
 
         self.parameters_changed();
         Ok(())
     }
 
+    /// Average gain of the filter curve in dB (master gain excluded), taken as
+    /// the mean power over log-spaced frequencies. Used to match the loudness
+    /// of two curves when comparing them.
+    pub fn average_gain_db(&self) -> f32 {
+        const POINTS: usize = 64;
+        let master_gain_db = 20.0 * self.master_gain.log10();
+
+        let mean_power = self
+            .get_response_curve(POINTS)
+            .iter()
+            .map(|(_, db)| 10.0f32.powf((db - master_gain_db) / 10.0))
+            .sum::<f32>()
+            / POINTS as f32;
+
+        10.0 * mean_power.log10()
+    }
+
     /// Get the combined frequency response curve for plotting
     /// Returns Vector of (Frequency, Gain_dB) points
     pub fn get_response_curve(&self, width: usize) -> Vec<(f32, f32)> {
@@ -505,8 +1248,10 @@ impl Equalizer {
             let log_f = log_start + step * i as f32;
             let f = log_f.exp();
             let mut total_db = master_gain_db;
-            for filter in &self.filters {
-                total_db += filter.magnitude_db(f, self.sample_rate as f32);
+            for (index, filter) in self.filters.iter().enumerate() {
+                if is_band_active(self.solo, index, filter) {
+                    total_db += filter.magnitude_db(f, self.sample_rate as f32);
+                }
             }
             points.push((f, total_db));
         }
@@ -514,3 +1259,364 @@ impl Equalizer {
         points
     }
 }
+
+/// Whether a band contributes to the output, given its bypass flag and the current solo
+fn is_band_active(solo: Option<usize>, index: usize, filter: &FilterNode) -> bool {
+    !filter.bypass && solo.is_none_or(|solo| solo == index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    fn equalizer_with(filters: Vec<FilterNode>) -> Equalizer {
+        let mut eq = Equalizer::new(SAMPLE_RATE, 2);
+        eq.filters = filters;
+        eq.parameters_changed();
+        eq.settle();
+        eq
+    }
+
+    fn dynamic_band(id: i16, freq: f32, gain: f32, dynamic: DynamicBand) -> FilterNode {
+        FilterNode {
+            gain,
+            dynamic: Some(dynamic),
+            ..FilterNode::new(id, freq)
+        }
+    }
+
+    /// Feed `ms` milliseconds of a stereo sine through the equalizer
+    fn play_sine(eq: &mut Equalizer, freq: f32, amplitude: f32, ms: f32) {
+        let frames = (ms * 0.001 * SAMPLE_RATE as f32) as usize;
+        let mut buffer: Vec<f32> = (0..frames)
+            .flat_map(|n| {
+                let s = amplitude * (2.0 * PI * freq * n as f32 / SAMPLE_RATE as f32).sin();
+                [s, s]
+            })
+            .collect();
+        for block in buffer.chunks_mut(1024) {
+            eq.process_frame(block);
+        }
+    }
+
+    fn dynamic_gain(eq: &Equalizer, index: usize) -> f32 {
+        eq.dynamic_gains_db()[index].expect("band is dynamic")
+    }
+
+    #[test]
+    fn dynamic_band_stays_at_unity_below_the_threshold() {
+        let settings = DynamicBand {
+            threshold_db: -20.0,
+            ..DynamicBand::default()
+        };
+        let mut eq = equalizer_with(vec![dynamic_band(0, 1000.0, -6.0, settings)]);
+        play_sine(&mut eq, 1000.0, 0.01, 200.0);
+        assert_eq!(dynamic_gain(&eq, 0), 0.0);
+    }
+
+    #[test]
+    fn dynamic_band_applies_its_gain_above_the_knee() {
+        let settings = DynamicBand {
+            threshold_db: -20.0,
+            ..DynamicBand::default()
+        };
+        let mut eq = equalizer_with(vec![dynamic_band(0, 1000.0, -6.0, settings)]);
+        play_sine(&mut eq, 1000.0, 0.5, 200.0);
+        assert!((dynamic_gain(&eq, 0) + 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn dynamic_band_applies_part_of_its_gain_inside_the_knee() {
+        // A 0.5 sine sits around -6 dBFS, half a knee above the threshold
+        let settings = DynamicBand {
+            threshold_db: -6.0 - DYNAMIC_KNEE_DB / 2.0,
+            ..DynamicBand::default()
+        };
+        let mut eq = equalizer_with(vec![dynamic_band(0, 1000.0, -6.0, settings)]);
+        play_sine(&mut eq, 1000.0, 0.5, 200.0);
+        let gain = dynamic_gain(&eq, 0);
+        assert!((gain + 3.0).abs() < 1.0, "gain {gain}");
+    }
+
+    #[test]
+    fn dynamic_band_ignores_signal_outside_the_band() {
+        let settings = DynamicBand {
+            threshold_db: -20.0,
+            ..DynamicBand::default()
+        };
+        let mut eq = equalizer_with(vec![dynamic_band(0, 8000.0, -6.0, settings)]);
+        play_sine(&mut eq, 100.0, 0.5, 200.0);
+        assert_eq!(dynamic_gain(&eq, 0), 0.0);
+    }
+
+    #[test]
+    fn dynamic_band_follows_attack_and_release_times() {
+        let settings = DynamicBand {
+            threshold_db: -30.0,
+            attack_ms: 50.0,
+            release_ms: 200.0,
+        };
+        let fast = DynamicBand {
+            attack_ms: 0.5,
+            ..settings
+        };
+        let mut eq = equalizer_with(vec![dynamic_band(0, 1000.0, -6.0, settings)]);
+        let mut fast_eq = equalizer_with(vec![dynamic_band(0, 1000.0, -6.0, fast)]);
+
+        // After 5ms only the fast attack has fully engaged
+        play_sine(&mut eq, 1000.0, 0.5, 5.0);
+        play_sine(&mut fast_eq, 1000.0, 0.5, 5.0);
+        assert!(dynamic_gain(&eq, 0) > -6.0 + 1.0);
+        assert!((dynamic_gain(&fast_eq, 0) + 6.0).abs() < 1e-4);
+
+        play_sine(&mut eq, 1000.0, 0.5, 300.0);
+        assert!((dynamic_gain(&eq, 0) + 6.0).abs() < 1e-4);
+
+        // Shortly after the signal stops the band is still held by the release
+        play_sine(&mut eq, 1000.0, 0.0, 20.0);
+        assert!((dynamic_gain(&eq, 0) + 6.0).abs() < 1e-4);
+
+        play_sine(&mut eq, 1000.0, 0.0, 2000.0);
+        assert_eq!(dynamic_gain(&eq, 0), 0.0);
+    }
+
+    #[test]
+    fn dynamic_gain_meter_follows_its_band_after_a_removal() {
+        let settings = DynamicBand {
+            threshold_db: -20.0,
+            ..DynamicBand::default()
+        };
+        let mut eq = equalizer_with(vec![
+            FilterNode::new(0, 100.0),
+            dynamic_band(1, 1000.0, -6.0, settings),
+        ]);
+        play_sine(&mut eq, 1000.0, 0.5, 200.0);
+        assert_eq!(eq.dynamic_gains_db()[0], None);
+        assert!((dynamic_gain(&eq, 1) + 6.0).abs() < 1e-4);
+
+        eq.remove_filter(0);
+        play_sine(&mut eq, 1000.0, 0.5, 5.0);
+        assert_eq!(eq.dynamic_gains_db().len(), 1);
+        assert!((dynamic_gain(&eq, 0) + 6.0).abs() < 1e-4);
+    }
+
+    fn first_biquad(eq: &Equalizer) -> &Biquad {
+        &eq.processors[0][0].biquads[0]
+    }
+
+    fn with_gain(eq: &mut Equalizer, gain: f32) {
+        eq.filters[0].gain = gain;
+        eq.parameters_changed();
+    }
+
+    #[test]
+    fn gain_change_ramps_to_its_target_without_a_step() {
+        let mut eq = equalizer_with(vec![FilterNode::new(0, 1000.0)]);
+        let start = first_biquad(&eq).coeffs;
+        with_gain(&mut eq, 12.0);
+        let target = first_biquad(&eq).target;
+        let max_step = (target.b0 - start.b0).abs() / SMOOTHING_SAMPLES as f32 * 1.01;
+
+        let mut previous = start;
+        for _ in 0..SMOOTHING_SAMPLES {
+            eq.process_frame(&mut [0.0, 0.0]);
+            let current = first_biquad(&eq).coeffs;
+            assert!((current.b0 - previous.b0).abs() <= max_step);
+            previous = current;
+        }
+        assert_eq!(first_biquad(&eq).coeffs, target);
+        assert!(!first_biquad(&eq).is_ramping());
+    }
+
+    #[test]
+    fn filter_type_change_crossfades_into_a_new_chain() {
+        let mut eq = equalizer_with(vec![FilterNode {
+            gain: 6.0,
+            ..FilterNode::new(0, 1000.0)
+        }]);
+        eq.filters[0].filter_type = FilterType::LowShelf;
+        eq.parameters_changed();
+
+        let chain = &eq.processors[0][0];
+        assert_eq!(chain.filter_type, FilterType::LowShelf);
+        assert!(chain.fading.is_some());
+        // The new chain starts at its target instead of interpolating across types
+        assert!(!chain.biquads[0].is_ramping());
+
+        for _ in 0..CROSSFADE_SAMPLES {
+            eq.process_frame(&mut [0.0, 0.0]);
+        }
+        assert!(eq.processors[0][0].fading.is_none());
+    }
+
+    #[test]
+    fn removed_band_fades_out_and_is_dropped() {
+        let mut eq = equalizer_with(vec![
+            FilterNode {
+                gain: 6.0,
+                ..FilterNode::new(0, 1000.0)
+            },
+            FilterNode::new(1, 5000.0),
+        ]);
+        eq.remove_filter(0);
+        assert_eq!(eq.processors[0].len(), 2);
+        assert!(eq.processors[0].iter().any(|chain| chain.retiring));
+
+        for _ in 0..SMOOTHING_SAMPLES {
+            eq.process_frame(&mut [0.0, 0.0]);
+        }
+        assert_eq!(eq.processors[0].len(), 1);
+        assert_eq!(eq.processors[0][0].id, 1);
+    }
+
+    #[test]
+    fn change_during_a_ramp_restarts_from_the_current_coefficients() {
+        let mut eq = equalizer_with(vec![FilterNode::new(0, 1000.0)]);
+        with_gain(&mut eq, 12.0);
+        for _ in 0..SMOOTHING_SAMPLES / 2 {
+            eq.process_frame(&mut [0.0, 0.0]);
+        }
+        let midway = first_biquad(&eq).coeffs;
+
+        with_gain(&mut eq, -12.0);
+        let biquad = first_biquad(&eq);
+        assert_eq!(biquad.coeffs, midway);
+        assert_eq!(biquad.ramp_remaining, SMOOTHING_SAMPLES);
+
+        let target = biquad.target;
+        eq.process_frame(&mut [0.0, 0.0]);
+        let next = first_biquad(&eq).coeffs;
+        let expected_step = (target.b0 - midway.b0) / SMOOTHING_SAMPLES as f32;
+        assert!((next.b0 - midway.b0 - expected_step).abs() < 1e-6);
+    }
+
+    fn node(filter_type: FilterType, gain: f32) -> FilterNode {
+        FilterNode {
+            filter_type,
+            gain,
+            ..FilterNode::new(0, 1000.0)
+        }
+    }
+
+    fn assert_response(filter: &FilterNode, expected: &[(f32, f32)]) {
+        for &(freq, db) in expected {
+            let actual = filter.magnitude_db(freq, SAMPLE_RATE as f32);
+            assert!(
+                (actual - db).abs() < 0.5,
+                "{} at {freq} Hz: {actual} dB, expected {db} dB",
+                filter.filter_type
+            );
+        }
+    }
+
+    #[test]
+    fn tilt_pivots_around_the_cutoff() {
+        let tilt = node(FilterType::Tilt, 6.0);
+        assert_response(&tilt, &[(30.0, -3.0), (1000.0, 0.0), (18000.0, 3.0)]);
+    }
+
+    #[test]
+    fn band_shelf_is_flat_between_its_edges() {
+        let shelf = FilterNode {
+            q: 0.25,
+            ..node(FilterType::BandShelf, 6.0)
+        };
+        assert_response(&shelf, &[(30.0, 0.0), (1000.0, 6.0), (18000.0, 0.0)]);
+    }
+
+    #[test]
+    fn first_order_shelves_reach_their_gain_at_the_edges() {
+        let low = node(FilterType::LowShelfFirstOrder, 6.0);
+        assert_response(&low, &[(30.0, 6.0), (1000.0, 3.0), (18000.0, 0.0)]);
+        let high = node(FilterType::HighShelfFirstOrder, 6.0);
+        assert_response(&high, &[(30.0, 0.0), (1000.0, 3.0), (18000.0, 6.0)]);
+    }
+
+    #[test]
+    fn all_pass_is_flat() {
+        let all_pass = node(FilterType::AllPass, 6.0);
+        assert_response(&all_pass, &[(30.0, 0.0), (1000.0, 0.0), (18000.0, 0.0)]);
+    }
+
+    #[test]
+    fn higher_orders_cascade_the_band_response() {
+        let shelf = FilterNode {
+            q: 0.25,
+            order: 4,
+            ..node(FilterType::BandShelf, 6.0)
+        };
+        assert_response(&shelf, &[(30.0, 0.0), (1000.0, 12.0)]);
+    }
+
+    #[test]
+    fn bypassed_band_is_flat() {
+        let mut eq = equalizer_with(vec![node(FilterType::Peaking, 6.0)]);
+        eq.filters[0].set_bypass(true);
+        eq.parameters_changed();
+        assert_response(&eq.filters[0], &[(1000.0, 0.0)]);
+        assert!(
+            eq.get_response_curve(16)
+                .iter()
+                .all(|(_, db)| db.abs() < 1e-4)
+        );
+    }
+
+    #[test]
+    fn add_filter_stops_at_the_band_limit() {
+        let mut eq = equalizer_with(Vec::new());
+        for id in 0..MAX_EQ_FILTERS {
+            assert!(eq.add_filter(FilterNode::new(id as i16, 1000.0)));
+        }
+        assert!(!eq.add_filter(FilterNode::new(MAX_EQ_FILTERS as i16, 1000.0)));
+        assert_eq!(eq.filters.len(), MAX_EQ_FILTERS);
+        assert_eq!(eq.processors[0].len(), MAX_EQ_FILTERS);
+    }
+
+    #[test]
+    fn remove_filter_ignores_indices_out_of_range() {
+        let mut eq = equalizer_with(vec![FilterNode::new(0, 1000.0)]);
+        assert_eq!(eq.remove_filter(1), None);
+        assert_eq!(eq.filters.len(), 1);
+    }
+
+    #[test]
+    fn solo_stays_on_its_band_when_an_earlier_band_is_removed() {
+        let mut eq = equalizer_with((0..4).map(|id| FilterNode::new(id, 1000.0)).collect());
+        eq.set_solo(Some(3));
+        eq.remove_filter(1);
+        assert_eq!(eq.solo(), Some(2));
+        assert_eq!(eq.filters[2].id, 3);
+
+        eq.remove_filter(2);
+        assert_eq!(eq.solo(), None);
+    }
+
+    #[test]
+    fn solo_is_kept_when_a_later_band_is_removed() {
+        let mut eq = equalizer_with((0..3).map(|id| FilterNode::new(id, 1000.0)).collect());
+        eq.set_solo(Some(0));
+        eq.remove_filter(2);
+        assert_eq!(eq.solo(), Some(0));
+    }
+
+    #[test]
+    fn solo_mutes_the_other_bands() {
+        let mut eq = equalizer_with(vec![
+            node(FilterType::Peaking, 6.0),
+            FilterNode {
+                freq: 100.0,
+                ..node(FilterType::Peaking, -6.0)
+            },
+        ]);
+        eq.set_solo(Some(0));
+        let curve = eq.get_response_curve(64);
+        let (_, peak) = curve
+            .iter()
+            .min_by(|a, b| (a.0 - 1000.0).abs().total_cmp(&(b.0 - 1000.0).abs()))
+            .unwrap();
+        assert!((peak - 6.0).abs() < 0.5);
+        assert!(curve.iter().all(|(_, db)| *db > -0.01));
+    }
+}
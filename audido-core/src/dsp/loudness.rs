@@ -0,0 +1,147 @@
+//! Integrated loudness after ITU-R BS.1770 (the measure ReplayGain 2.0 and
+//! EBU R128 use): K-weighted mean power over 400 ms blocks, gated.
+
+/// Length of a measurement block in seconds
+const BLOCK_SECONDS: f64 = 0.4;
+
+/// Blocks overlap by 75%
+const BLOCK_STEPS: usize = 4;
+
+/// Blocks quieter than this (LUFS) are silence and never count
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// Blocks more than this many LU below the ungated loudness don't count
+const RELATIVE_GATE: f64 = 10.0;
+
+/// Loudness of a whole track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS
+    pub integrated: f32,
+    /// Largest absolute sample value, 1.0 is full scale
+    pub peak: f32,
+}
+
+/// Measure the loudness of interleaved samples. `None` for silence and for
+/// audio shorter than one block.
+pub fn measure_loudness(buffer: &[f32], sample_rate: f32, num_channels: u16) -> Option<Loudness> {
+    let channels = num_channels as usize;
+    if channels == 0 || sample_rate <= 0.0 {
+        return None;
+    }
+    let frames = buffer.len() / channels;
+    let step = (BLOCK_SECONDS * sample_rate as f64) as usize / BLOCK_STEPS;
+    if step == 0 || frames < step * BLOCK_STEPS {
+        return None;
+    }
+
+    // Power of each step (a quarter block), summed over channels. Every
+    // channel weighs 1.0; surround weighting is left out.
+    let mut filters: Vec<KWeighting> = (0..channels)
+        .map(|_| KWeighting::new(sample_rate as f64))
+        .collect();
+    let mut step_power = vec![0.0f64; frames / step];
+    let mut peak = 0.0f32;
+    for (i, frame) in buffer
+        .chunks_exact(channels)
+        .take(step_power.len() * step)
+        .enumerate()
+    {
+        for (sample, filter) in frame.iter().zip(&mut filters) {
+            peak = peak.max(sample.abs());
+            let weighted = filter.process(*sample as f64);
+            step_power[i / step] += weighted * weighted;
+        }
+    }
+
+    let blocks: Vec<f64> = step_power
+        .windows(BLOCK_STEPS)
+        .map(|steps| steps.iter().sum::<f64>() / (step * BLOCK_STEPS) as f64)
+        .filter(|power| lufs(*power) > ABSOLUTE_GATE)
+        .collect();
+    if blocks.is_empty() {
+        return None;
+    }
+    let threshold = lufs(mean(&blocks)) - RELATIVE_GATE;
+    let gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|power| lufs(*power) > threshold)
+        .collect();
+
+    Some(Loudness {
+        integrated: lufs(mean(&gated)) as f32,
+        peak,
+    })
+}
+
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.max(f64::MIN_POSITIVE).log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+/// The K-weighting pre-filter: a high shelf for the head's effect, then a
+/// high-pass. Coefficients are derived for any sample rate the way libebur128 does.
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(sample_rate: f64) -> Self {
+        use std::f64::consts::PI;
+
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        Self {
+            stages: [shelf, high_pass],
+        }
+    }
+
+    fn process(&mut self, sample: f64) -> f64 {
+        self.stages
+            .iter_mut()
+            .fold(sample, |sample, stage| stage.process(sample))
+    }
+}
+
+/// Second-order section, transposed direct form II
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
@@ -1,6 +1,9 @@
 pub mod dsp_graph;
 pub mod eq;
+pub mod loudness;
+pub mod normalization;
 pub mod pitch_detection;
 pub mod pitch_shifter;
 pub mod stretcher;
-pub mod normalization;
\ No newline at end of file
+pub mod tempo;
+pub mod waveform;
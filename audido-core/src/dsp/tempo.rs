@@ -0,0 +1,120 @@
+//! Tempo estimation: spectral flux as an onset envelope, whose periodicity
+//! (autocorrelation) gives the beat period.
+
+use rustfft::{FftPlanner, num_complex::Complex};
+
+const WINDOW_SIZE: usize = 1024;
+const HOP_SIZE: usize = WINDOW_SIZE / 2;
+
+/// Tempo range searched, in BPM
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+
+/// Periodicities are weighted towards this tempo, in BPM, so a track isn't
+/// read at half or double its tempo as easily
+const PREFERRED_BPM: f32 = 120.0;
+
+/// Seconds of the onset envelope over which its local mean is removed
+const MEAN_WINDOW_SECONDS: f32 = 0.5;
+
+/// Estimate the tempo of interleaved samples in BPM. `None` when the audio is
+/// too short or has no clear pulse.
+pub fn detect_tempo(buffer: &[f32], sample_rate: f32, num_channels: u16) -> Option<f32> {
+    let channels = num_channels as usize;
+    if channels == 0 || sample_rate <= 0.0 {
+        return None;
+    }
+    let mono: Vec<f32> = buffer
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    let frame_rate = sample_rate / HOP_SIZE as f32;
+    let envelope = onset_envelope(&mono, frame_rate);
+    let min_lag = (60.0 * frame_rate / MAX_BPM).floor() as usize;
+    let max_lag = (60.0 * frame_rate / MIN_BPM).ceil() as usize;
+    if min_lag == 0 || envelope.len() < max_lag * 4 {
+        return None;
+    }
+
+    let correlation: Vec<f32> = (0..=max_lag + 1)
+        .map(|lag| {
+            envelope
+                .iter()
+                .zip(&envelope[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+        })
+        .collect();
+    if correlation[0] <= 0.0 {
+        return None;
+    }
+
+    // Log-Gaussian weight around the preferred tempo, one octave wide
+    let weight = |lag: usize| {
+        let octaves = (60.0 * frame_rate / lag as f32 / PREFERRED_BPM).log2();
+        (-0.5 * octaves * octaves).exp()
+    };
+    let best = (min_lag..=max_lag)
+        .max_by(|&a, &b| (correlation[a] * weight(a)).total_cmp(&(correlation[b] * weight(b))))?;
+    if correlation[best] <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation between the neighbouring lags
+    let (left, center, right) = (
+        correlation[best - 1],
+        correlation[best],
+        correlation[best + 1],
+    );
+    let curvature = left - 2.0 * center + right;
+    let offset = if curvature < 0.0 {
+        (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let bpm = 60.0 * frame_rate / (best as f32 + offset);
+    Some((bpm * 10.0).round() / 10.0)
+}
+
+/// Positive change of log magnitude per hop, summed over frequencies, with
+/// its local mean removed
+fn onset_envelope(mono: &[f32], frame_rate: f32) -> Vec<f32> {
+    if mono.len() < WINDOW_SIZE {
+        return Vec::new();
+    }
+    let fft = FftPlanner::new().plan_fft_forward(WINDOW_SIZE);
+    let window: Vec<f32> = (0..WINDOW_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / WINDOW_SIZE as f32).cos())
+        .collect();
+
+    let mut frame = vec![Complex::new(0.0f32, 0.0f32); WINDOW_SIZE];
+    let mut previous = vec![0.0f32; WINDOW_SIZE / 2];
+    let mut flux = Vec::with_capacity(mono.len() / HOP_SIZE);
+    for start in (0..=mono.len() - WINDOW_SIZE).step_by(HOP_SIZE) {
+        for ((bin, sample), w) in frame.iter_mut().zip(&mono[start..]).zip(&window) {
+            *bin = Complex::new(sample * w, 0.0);
+        }
+        fft.process(&mut frame);
+        let mut total = 0.0;
+        for (bin, last) in frame[..WINDOW_SIZE / 2].iter().zip(previous.iter_mut()) {
+            let magnitude = (1.0 + 100.0 * bin.norm()).ln();
+            total += (magnitude - *last).max(0.0);
+            *last = magnitude;
+        }
+        flux.push(total);
+    }
+    if let Some(first) = flux.first_mut() {
+        // Nothing came before the first frame
+        *first = 0.0;
+    }
+
+    let half = ((MEAN_WINDOW_SECONDS * frame_rate) as usize / 2).max(1);
+    (0..flux.len())
+        .map(|i| {
+            let window = &flux[i.saturating_sub(half)..(i + half + 1).min(flux.len())];
+            let mean = window.iter().sum::<f32>() / window.len() as f32;
+            (flux[i] - mean).max(0.0)
+        })
+        .collect()
+}
@@ -0,0 +1,24 @@
+//! Waveform overview: the peak level of each of a fixed number of slices of a
+//! track, enough to draw it at any width.
+
+/// Number of slices a track's waveform is split into
+pub const WAVEFORM_BINS: usize = 1024;
+
+/// Largest absolute sample value of each of `bins` equal slices of the
+/// interleaved samples, all channels together. Empty for empty audio.
+pub fn waveform_peaks(buffer: &[f32], num_channels: u16, bins: usize) -> Vec<f32> {
+    let channels = (num_channels as usize).max(1);
+    let frames = buffer.len() / channels;
+    if frames == 0 || bins == 0 {
+        return Vec::new();
+    }
+    (0..bins)
+        .map(|bin| {
+            let start = frames * bin / bins * channels;
+            let end = frames * (bin + 1) / bins * channels;
+            buffer[start..end]
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        })
+        .collect()
+}
@@ -1,5 +1,7 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use crossbeam_channel::{Receiver, Sender, unbounded};
@@ -8,11 +10,25 @@ use rodio::{
     cpal::{self, traits::HostTrait},
 };
 
-use crate::queue::{LoopMode, PlaybackQueue};
+use crate::queue::{LoopMode, PlaybackQueue, QueueItem, QueueSortField};
 use crate::source::AudioPlaybackData;
 use crate::{
+    analysis::{Analysis, AnalysisConfig, TagWriteMode},
     commands::{AudioCommand, AudioResponse, RealtimeAudioCommand},
-    dsp::{eq::Equalizer, normalization::Normalizer},
+    cover,
+    cue::TrackRange,
+    dsp::{
+        eq::{EqPreset, Equalizer, FilterNode, MAX_EQ_FILTERS},
+        normalization::Normalizer,
+    },
+    metadata::AudioMetadata,
+    playlist::{self, PlaylistEntry},
+    preset_rules::{PresetRule, PresetRules},
+    remote::{self, RemoteConfig, RemoteError, RemoteTrack},
+    session::{EqSession, NormalizerSession, Session},
+    stats::{ListenEnd, PlayStats},
+    stream::{self, StreamEvent},
+    tags::{self, TagChange},
 };
 
 /// Handle to communicate with the audio engine from the TUI
@@ -37,6 +53,117 @@ pub struct AudioEngine {
     normalizer_shadow: Normalizer,
     normalizer_enabled: bool,
     rt_cmd_tx: Option<Sender<RealtimeAudioCommand>>,
+    /// Rules picking DSP settings per track
+    preset_rules: PresetRules,
+    /// User settings kept aside while a preset rule is in effect
+    rule_baseline: Option<DspSettings>,
+    /// Whether this run has state worth saving. Stays off until something is
+    /// queued, so launching and quitting doesn't wipe the previous session.
+    session_active: bool,
+    last_session_save: Instant,
+    /// Play statistics, updated whenever listening to a track ends
+    stats: PlayStats,
+    /// Statistics changed since they were last written
+    stats_dirty: bool,
+    /// The track being listened to, for the statistics
+    listen: Option<Listen>,
+    remote_config: RemoteConfig,
+    /// URLs resolved on background threads, picked up by the main loop
+    remote_tx: Sender<RemoteResult>,
+    remote_rx: Receiver<RemoteResult>,
+    /// Queue item (by id) being downloaded again or connected to in the
+    /// background, to play once it is ready, or cue paused at a position
+    awaiting_item: Option<(usize, Option<f32>)>,
+    /// Stream URL a `Load` command is connecting to in the background
+    awaiting_load: Option<String>,
+    /// Streams connected to on background threads
+    opened_tx: Sender<OpenedStream>,
+    opened_rx: Receiver<OpenedStream>,
+    /// Tags of queued tracks read on background threads for a pending reorder
+    tags_tx: Sender<TagsResult>,
+    tags_rx: Receiver<TagsResult>,
+    /// File whose cover art was sent last
+    cover_path: Option<String>,
+    analysis_config: AnalysisConfig,
+}
+
+/// A URL, the queue item (by id) downloaded again from it, and what resolving
+/// it gave
+type RemoteResult = (String, Option<usize>, Result<Vec<RemoteTrack>, RemoteError>);
+
+/// What a stream is connected to in the background for
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StreamFor {
+    /// A queue item, by id
+    Item(usize),
+    /// A `Load` command, by URL
+    Load(String),
+}
+
+/// A stream, once connected, and what it was for
+type OpenedStream = (StreamFor, anyhow::Result<AudioPlaybackData>);
+
+/// A track to queue: its file, metadata known before the file is read (a
+/// playlist's titles, a remote page's info), the range of CUE tracks and the
+/// page of remote tracks
+type QueuedTrack = (
+    PathBuf,
+    Option<AudioMetadata>,
+    Option<TrackRange>,
+    Option<String>,
+);
+
+/// Tags read for queue items (by id) and the reorder waiting for them
+type TagsResult = (Vec<(usize, AudioMetadata)>, QueueReorder);
+
+/// Queue reordering that needs the tags of tracks not played yet
+#[derive(Debug, Clone, Copy)]
+enum QueueReorder {
+    Sort(QueueSortField),
+    Harmonic,
+    /// Albums come from the tags
+    AlbumShuffle,
+}
+
+/// Listening time of the loaded track
+struct Listen {
+    path: PathBuf,
+    listened: Duration,
+    last_tick: Instant,
+}
+
+/// Snapshot of the DSP settings a preset rule can change
+#[derive(Clone)]
+struct DspSettings {
+    eq_preset: EqPreset,
+    eq_filters: Vec<FilterNode>,
+    eq_enabled: bool,
+    normalizer: Normalizer,
+    normalizer_enabled: bool,
+}
+
+impl DspSettings {
+    /// These settings with whatever `rule` sets applied on top
+    fn with_rule(mut self, rule: &PresetRule) -> Self {
+        if let Some(preset) = rule.eq_preset {
+            self.eq_preset = preset;
+            self.eq_filters = preset.set_filters();
+            self.eq_enabled = true;
+        }
+        if let Some(normalizer) = &rule.normalizer {
+            self.normalizer_enabled = normalizer.enabled;
+            if let Some(mode) = normalizer.mode {
+                self.normalizer.set_mode(mode);
+            }
+            if let Some(level) = normalizer.target_level {
+                self.normalizer.set_target_level(level);
+            }
+            if let Some(headroom) = normalizer.headroom {
+                self.normalizer.set_headroom(headroom);
+            }
+        }
+        self
+    }
 }
 
 // Constants for fading
@@ -44,6 +171,10 @@ const FADE_DURATION_MS: u64 = 100;
 const FADE_STEPS: u32 = 20;
 const FADE_STEP_DURATION: Duration = Duration::from_millis(FADE_DURATION_MS / FADE_STEPS as u64);
 
+/// How often the session and play statistics are saved while running,
+/// besides on quit
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
 impl AudioEngine {
     /// Create a new audio engine and return a handle for communication
     pub fn new() -> anyhow::Result<(Self, AudioEngineHandle)> {
@@ -63,8 +194,34 @@ impl AudioEngine {
 
         let sink = Sink::connect_new(stream.mixer());
 
+        let preset_rules = PresetRules::load().unwrap_or_else(|e| {
+            log::warn!("Preset rules disabled: {}", e);
+            PresetRules::default()
+        });
+        if !preset_rules.is_empty() {
+            log::info!("Loaded {} preset rules", preset_rules.rules.len());
+        }
+
+        let stats = PlayStats::load().unwrap_or_else(|e| {
+            log::warn!("Starting with empty play statistics: {}", e);
+            PlayStats::default()
+        });
+
+        let remote_config = RemoteConfig::load().unwrap_or_else(|e| {
+            log::warn!("Using default remote settings: {}", e);
+            RemoteConfig::default()
+        });
+
+        let analysis_config = AnalysisConfig::load().unwrap_or_else(|e| {
+            log::warn!("Using default analysis settings: {}", e);
+            AnalysisConfig::default()
+        });
+
         // Create crossbeam channels
         let (cmd_tx, cmd_rx) = unbounded::<AudioCommand>();
+        let (remote_tx, remote_rx) = unbounded::<RemoteResult>();
+        let (opened_tx, opened_rx) = unbounded::<OpenedStream>();
+        let (tags_tx, tags_rx) = unbounded::<TagsResult>();
         let (resp_tx, resp_rx) = unbounded::<AudioResponse>();
 
         let engine = AudioEngine {
@@ -82,6 +239,24 @@ impl AudioEngine {
             normalizer_shadow: Normalizer::new(),
             normalizer_enabled: false,
             rt_cmd_tx: None,
+            preset_rules,
+            rule_baseline: None,
+            session_active: false,
+            last_session_save: Instant::now(),
+            stats,
+            stats_dirty: false,
+            listen: None,
+            remote_config,
+            remote_tx,
+            remote_rx,
+            awaiting_item: None,
+            awaiting_load: None,
+            opened_tx,
+            opened_rx,
+            tags_tx,
+            tags_rx,
+            cover_path: None,
+            analysis_config,
         };
 
         let handle = AudioEngineHandle { cmd_tx, resp_rx };
@@ -165,8 +340,17 @@ impl AudioEngine {
                 }
             }
 
+            self.poll_remote();
+            self.poll_opened_streams();
+            self.poll_tags();
+            self.poll_stream();
+            self.poll_analysis();
+            self.tick_gapless();
+            self.tick_listen();
+
             if self.is_playing && self.sink.empty() && !self.sink.is_paused() {
                 log::info!("Track finished naturally.");
+                self.end_listen(ListenEnd::Finished);
 
                 if let Some(ref audio_data) = self.current_audio {
                     audio_data.position_tracker().reset();
@@ -181,7 +365,7 @@ impl AudioEngine {
                     let _ = self.resp_tx.send(AudioResponse::Stopped);
                     let _ = self.resp_tx.send(AudioResponse::Position {
                         current: 0.0,
-                        total: 0.0,
+                        total: None,
                     });
                     self.sink.set_volume(self.target_volume);
                 }
@@ -198,82 +382,80 @@ impl AudioEngine {
                 let _ = self
                     .resp_tx
                     .send(AudioResponse::Position { current, total });
+
+                // Live gain of dynamic EQ bands (shared with the realtime equalizer)
+                if self.eq_enabled && self.eq_shadow.filters.iter().any(|f| f.is_dynamic()) {
+                    let _ = self.resp_tx.send(AudioResponse::EqDynamicGains(
+                        self.eq_shadow.dynamic_gains_db(),
+                    ));
+                }
+            }
+
+            if self.last_session_save.elapsed() >= SESSION_SAVE_INTERVAL {
+                self.save_session();
+                self.save_stats();
             }
         }
 
+        // Covers both an explicit quit and the TUI going away
+        self.end_listen(ListenEnd::Interrupted);
+        self.save_session();
+        self.save_stats();
+
         log::info!("Audio engine stopped");
         let _ = self.resp_tx.send(AudioResponse::Shutdown);
     }
 
     /// Process a single command, returns false if engine should quit
     fn process_command(&mut self, cmd: AudioCommand) -> bool {
+        let eq_edit = matches!(
+            cmd,
+            AudioCommand::EqSetEnabled(_)
+                | AudioCommand::EqSetPreset(_)
+                | AudioCommand::EqSetAllFilters(_)
+                | AudioCommand::EqSetState { .. }
+                | AudioCommand::EqSetFilter(..)
+                | AudioCommand::EqAddFilter(_)
+                | AudioCommand::EqRemoveFilter(_)
+                | AudioCommand::EqResetParameters
+                | AudioCommand::EqResetFilterNode(_)
+        );
+        let normalizer_edit = matches!(
+            cmd,
+            AudioCommand::NormalizerSetEnabled(_)
+                | AudioCommand::NormalizerSetMode(_)
+                | AudioCommand::NormalizerSetTargetLevel(_)
+                | AudioCommand::NormalizerSetHeadroom(_)
+        );
+
         match cmd {
             AudioCommand::Load(path) => {
                 log::info!("Loading audio: {}", path);
-
-                if self.is_playing {
-                    self.perform_fade_out();
-                }
-
-                self.sink.stop();
-                self.is_playing = false;
-
-                match AudioPlaybackData::load_local_audio(&path) {
-                    Ok(audio_data) => {
-                        let metadata = audio_data.metadata().clone();
-
-                        let previous_filters = self.eq_shadow.filters.clone();
-                        let previous_gain = self.eq_shadow.master_gain;
-                        let previous_preset = self.eq_shadow.preset;
-
-                        let mut new_eq =
-                            Equalizer::new(metadata.sample_rate, metadata.num_channels);
-
-                        new_eq.filters = previous_filters;
-                        new_eq.master_gain = previous_gain;
-                        new_eq.preset = previous_preset;
-
-                        new_eq.parameters_changed();
-                        self.eq_shadow = new_eq;
-
-                        self.current_audio = Some(audio_data);
-                        let _ = self.resp_tx.send(AudioResponse::Loaded(metadata.clone()));
-
-                        if let Some(ref data) = self.current_audio {
-                            // Create realtime audio command channel
-                            let (rt_tx, rt_rx) = unbounded::<RealtimeAudioCommand>();
-                            self.rt_cmd_tx = Some(rt_tx);
-
-                            self.sink.append(data.create_source(
-                                self.eq_shadow.clone(),
-                                self.eq_enabled,
-                                rt_rx,
-                            ));
-                            self.sink.set_volume(0.0);
-                            self.sink.play();
-                            self.is_playing = true;
-                            let _ = self.resp_tx.send(AudioResponse::Playing);
-                            self.perform_fade_in();
+                self.awaiting_item = None;
+                if stream::is_stream(Path::new(&path)) {
+                    self.load_stream_in_background(path);
+                } else {
+                    self.awaiting_load = None;
+                    self.stop_for_next_track();
+                    match AudioPlaybackData::load(&path) {
+                        Ok(audio_data) => self.play_unqueued(audio_data),
+                        Err(e) => {
+                            let _ = self
+                                .resp_tx
+                                .send(AudioResponse::Error(format!("Failed to load audio: {}", e)));
                         }
                     }
-                    Err(e) => {
-                        let _ = self
-                            .resp_tx
-                            .send(AudioResponse::Error(format!("Failed to load audio: {}", e)));
-                    }
                 }
             }
             AudioCommand::Play => {
-                if let Some(ref audio_data) = self.current_audio {
+                if self.current_audio.is_some() {
                     // Append audio source to sink if not already playing
                     if self.sink.empty() {
-                        let (rt_tx, rt_rx) = unbounded::<RealtimeAudioCommand>();
-                        self.rt_cmd_tx = Some(rt_tx);
-                        self.sink.append(audio_data.create_source(
-                            self.eq_shadow.clone(),
-                            self.eq_enabled,
-                            rt_rx,
-                        ));
+                        self.append_current_source();
+                    }
+                    // Replaying a track that finished starts a new listen
+                    if self.listen.is_none() {
+                        self.begin_listen();
                     }
                     if !self.is_playing {
                         self.sink.set_volume(0.0);
@@ -323,7 +505,10 @@ impl AudioEngine {
                 self.sink.set_speed(speed.clamp(0.1, 4.0));
             }
             AudioCommand::Seek(pos) => {
-                if let Some(ref audio_data) = self.current_audio {
+                // Live streams only play what arrives
+                if let Some(ref audio_data) = self.current_audio
+                    && !audio_data.is_live()
+                {
                     // Check previous state logic (updated to use is_playing flag)
                     let should_play = self.is_playing;
 
@@ -334,13 +519,7 @@ impl AudioEngine {
                     audio_data.position_tracker().seek_to_seconds(pos);
 
                     // Create and append new source (starts from tracked position)
-                    let (rt_tx, rt_rx) = unbounded::<RealtimeAudioCommand>();
-                    self.rt_cmd_tx = Some(rt_tx);
-                    self.sink.append(audio_data.create_source(
-                        self.eq_shadow.clone(),
-                        self.eq_enabled,
-                        rt_rx,
-                    ));
+                    self.append_current_source();
 
                     if should_play {
                         self.sink.set_volume(self.target_volume);
@@ -355,6 +534,7 @@ impl AudioEngine {
             AudioCommand::Next => {
                 if let Some(next_idx) = self.queue.next_index() {
                     log::info!("Skipping to next track (index {})", next_idx);
+                    self.end_listen(ListenEnd::Skipped);
                     self.play_queue_track(next_idx);
                 } else {
                     log::info!("No next track available");
@@ -368,6 +548,10 @@ impl AudioEngine {
                     log::info!("No previous track available");
                 }
             }
+            AudioCommand::RestoreSession(session) => {
+                log::info!("Restoring session with {} tracks", session.queue.len());
+                self.restore_session(session);
+            }
             AudioCommand::Quit => {
                 if self.is_playing {
                     self.perform_fade_out();
@@ -378,47 +562,148 @@ impl AudioEngine {
             }
             AudioCommand::AddToQueue(paths) => {
                 log::info!("Adding {} items to queue", paths.len());
-                let was_empty = self.queue.items.is_empty();
                 let path_bufs: Vec<std::path::PathBuf> =
                     paths.into_iter().map(|s| s.into()).collect();
-                self.queue.add(path_bufs);
-
-                // Auto-play if not already playing and not paused
-                if !self.is_playing && !self.sink.is_paused() {
-                    if was_empty {
-                        self.play_queue_track(0);
-                    } else if let Some(next_idx) = self.queue.next_index() {
-                        // This handles the case where the queue had ended
-                        self.play_queue_track(next_idx);
+                self.enqueue(path_bufs);
+                self.send_queue_update();
+            }
+            AudioCommand::AddUrl(url) => {
+                log::info!("Resolving URL: {}", url);
+                self.resolve_url(url, None);
+            }
+            AudioCommand::AddStream(url) => {
+                log::info!("Adding stream: {}", url);
+                // Shown until the station sends its name
+                let metadata = AudioMetadata {
+                    title: Some(url.clone()),
+                    full_file_path: url.clone(),
+                    format: "stream".to_string(),
+                    ..Default::default()
+                };
+                self.enqueue_tracks(vec![(PathBuf::from(url), Some(metadata), None, None)]);
+                self.send_queue_update();
+            }
+            AudioCommand::LoadPlaylist(path) => {
+                log::info!("Loading playlist: {}", path);
+                if let Some(tracks) = self.playlist_tracks(&path) {
+                    let count = tracks.len();
+                    self.enqueue_tracks(tracks);
+                    self.send_queue_update();
+                    let _ = self
+                        .resp_tx
+                        .send(AudioResponse::PlaylistLoaded { path, count });
+                }
+            }
+            AudioCommand::SavePlaylist(path) => {
+                let entries: Vec<PlaylistEntry> =
+                    self.queue.items.iter().map(PlaylistEntry::from).collect();
+                match playlist::save_playlist(Path::new(&path), &entries) {
+                    Ok(()) => {
+                        log::info!("Saved {} tracks to playlist {}", entries.len(), path);
+                        let _ = self.resp_tx.send(AudioResponse::PlaylistSaved {
+                            path,
+                            count: entries.len(),
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to save playlist {}: {}", path, e);
+                        let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                            "Failed to save playlist: {}",
+                            e
+                        )));
                     }
                 }
-
+            }
+            AudioCommand::PlayNext(paths) => {
+                log::info!("Inserting {} items after the current track", paths.len());
+                let path_bufs: Vec<std::path::PathBuf> =
+                    paths.into_iter().map(|s| s.into()).collect();
+                let ids = self.queue.insert_next(path_bufs);
+                self.session_active |= !ids.is_empty();
+                if !self.is_playing
+                    && !self.sink.is_paused()
+                    && let Some(index) = ids.first().and_then(|id| self.queue.index_of(*id))
+                {
+                    self.play_queue_track(index);
+                }
                 self.send_queue_update();
             }
             AudioCommand::RemoveFromQueue(id) => {
+                let playing = self.current_item_id();
                 if self.queue.remove(id) {
                     log::info!("Removed item {} from queue", id);
+                    self.follow_current_item(playing);
+                    self.send_queue_update();
+                }
+            }
+            AudioCommand::RemoveMany(ids) => {
+                let playing = self.current_item_id();
+                let removed = self.queue.remove_many(&ids);
+                if removed > 0 {
+                    log::info!("Removed {} items from queue", removed);
+                    self.follow_current_item(playing);
+                    self.send_queue_update();
+                }
+            }
+            AudioCommand::MoveQueueItem { id, to } => {
+                if self.queue.move_item(id, to) {
+                    log::info!("Moved queue item {} to position {}", id, to);
                     self.send_queue_update();
                 }
             }
+            AudioCommand::Dedupe => {
+                let removed = self.queue.dedupe();
+                log::info!("Removed {} duplicate items from queue", removed);
+                self.send_queue_update();
+            }
+            AudioCommand::WriteTags { paths, changes } => self.write_tags(paths, changes),
+            AudioCommand::WriteAnalysis { path, analysis } => self.write_analysis(path, &analysis),
+            AudioCommand::SetAnalysisTagWrite(mode) => {
+                log::info!("Analysis tag writing: {}", mode);
+                self.analysis_config.write_tags = mode;
+                if let Err(e) = self.analysis_config.save() {
+                    log::warn!("Failed to save analysis settings: {}", e);
+                }
+            }
+            AudioCommand::SortBy(field) => {
+                log::info!("Sorting queue by {}", field);
+                self.reorder_with_tags(QueueReorder::Sort(field));
+            }
+            AudioCommand::SortQueueHarmonic => {
+                log::info!("Sorting queue for harmonic mixing");
+                self.reorder_with_tags(QueueReorder::Harmonic);
+            }
             AudioCommand::ClearQueue => {
                 log::info!("Clearing queue");
-                if self.is_playing {
-                    self.perform_fade_out();
-                    self.sink.stop();
-                    self.is_playing = false;
-                }
-                self.queue.clear();
-                self.current_audio = None;
+                self.clear_queue();
                 self.send_queue_update();
                 let _ = self.resp_tx.send(AudioResponse::Stopped);
             }
+            AudioCommand::ReplaceQueue { paths, start } => {
+                log::info!("Replacing queue with {} items", paths.len());
+                self.clear_queue();
+                let (tracks, start) = expand_playlists(paths, start, |path| {
+                    self.playlist_tracks(path).unwrap_or_default()
+                });
+                // Queued without the auto-play of `enqueue`, so only `start` is loaded
+                self.queue_tracks(tracks);
+                if let Some(start) = start.filter(|&start| start < self.queue.items.len()) {
+                    self.play_queue_track(start);
+                } else {
+                    let _ = self.resp_tx.send(AudioResponse::Stopped);
+                }
+                self.send_queue_update();
+            }
             AudioCommand::SetLoopMode(mode) => {
                 log::info!("Setting loop mode to {:?}", mode);
                 self.queue.loop_mode = mode;
-                if mode == LoopMode::Shuffle {
+                if mode.is_shuffle() {
                     self.queue.reshuffle();
                 }
+                if mode == LoopMode::AlbumShuffle {
+                    // Shuffle again by album once the tags are in
+                    self.reorder_with_tags(QueueReorder::AlbumShuffle);
+                }
                 let _ = self.resp_tx.send(AudioResponse::LoopModeChanged(mode));
             }
             AudioCommand::PlayQueueIndex(index) => {
@@ -464,6 +749,61 @@ impl AudioEngine {
                     let _ = tx.send(RealtimeAudioCommand::SetAllEqFilters(filters));
                 }
             }
+            AudioCommand::EqSetState { preset, filters } => {
+                log::info!(
+                    "Setting EQ preset {:?} with {} bands",
+                    preset,
+                    filters.len()
+                );
+                self.eq_shadow.preset = preset;
+                self.eq_shadow.filters = filters.clone();
+                self.eq_shadow.parameters_changed();
+                if let Some(ref tx) = self.rt_cmd_tx {
+                    let _ = tx.send(RealtimeAudioCommand::SetEqState(preset, filters));
+                }
+            }
+            AudioCommand::EqSetFilter(index, filter) => {
+                if index >= self.eq_shadow.filters.len() {
+                    log::warn!("Ignoring update of unknown EQ filter {}", index);
+                    return true;
+                }
+                log::info!("Updating EQ filter {}", index);
+                self.eq_shadow.filters[index] = filter.clone();
+                self.eq_shadow.parameters_changed();
+                if let Some(ref tx) = self.rt_cmd_tx {
+                    let _ = tx.send(RealtimeAudioCommand::UpdateEqFilter(index, filter));
+                }
+            }
+            AudioCommand::EqAddFilter(filter) => {
+                if !self.eq_shadow.add_filter(filter.clone()) {
+                    let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                        "The equalizer is limited to {} bands",
+                        MAX_EQ_FILTERS
+                    )));
+                    return true;
+                }
+                log::info!("Added EQ filter ({} bands)", self.eq_shadow.filters.len());
+                if let Some(ref tx) = self.rt_cmd_tx {
+                    let _ = tx.send(RealtimeAudioCommand::AddEqFilter(filter));
+                }
+            }
+            AudioCommand::EqRemoveFilter(index) => {
+                if self.eq_shadow.remove_filter(index).is_none() {
+                    log::warn!("Ignoring removal of unknown EQ filter {}", index);
+                    return true;
+                }
+                log::info!("Removed EQ filter {}", index);
+                if let Some(ref tx) = self.rt_cmd_tx {
+                    let _ = tx.send(RealtimeAudioCommand::RemoveEqFilter(index));
+                }
+            }
+            AudioCommand::EqSetSolo(solo) => {
+                log::info!("Setting EQ solo: {:?}", solo);
+                self.eq_shadow.set_solo(solo);
+                if let Some(ref tx) = self.rt_cmd_tx {
+                    let _ = tx.send(RealtimeAudioCommand::SetEqSolo(solo));
+                }
+            }
             AudioCommand::EqResetParameters => {
                 log::info!("Setting all EQ filters to their default state");
                 self.eq_shadow.reset_parameters();
@@ -510,82 +850,1148 @@ impl AudioEngine {
                 }
             }
         }
+        self.keep_user_dsp_edit(eq_edit, normalizer_edit);
         true
     }
 
     /// Helper to play a track from the queue by index
     fn play_queue_track(&mut self, index: usize) {
-        if let Some(item) = self.queue.get(index) {
-            let path = item.path.to_string_lossy().to_string();
+        if let Some(item) = self.queue.get(index).cloned() {
+            self.awaiting_load = None;
+            if self.download_again(&item, None) || self.open_stream_in_background(&item, None) {
+                return;
+            }
+            self.awaiting_item = None;
+            self.stop_for_next_track();
+
+            // Load the new track
+            match self.load_item(&item) {
+                Ok(audio_data) => self.play_loaded(index, audio_data),
+                Err(e) => {
+                    let _ = self
+                        .resp_tx
+                        .send(AudioResponse::Error(format!("Failed to load track: {}", e)));
+                }
+            }
+        }
+    }
+
+    /// End the current track before another one starts
+    fn stop_for_next_track(&mut self) {
+        self.end_listen(ListenEnd::Interrupted);
+
+        // Fade out current track if playing
+        if self.is_playing {
+            self.perform_fade_out();
+        }
+        self.sink.stop();
+        self.is_playing = false;
+    }
+
+    /// Start a track loaded with `Load`, outside the queue
+    fn play_unqueued(&mut self, audio_data: AudioPlaybackData) {
+        let metadata = audio_data.metadata().clone();
+
+        self.prepare_dsp_for_track(&metadata);
+
+        self.current_audio = Some(audio_data);
+        self.begin_listen();
+        let _ = self.resp_tx.send(AudioResponse::Loaded(metadata));
+        self.send_cover_art();
+
+        self.append_current_source();
+        self.sink.set_volume(0.0);
+        self.sink.play();
+        self.is_playing = true;
+        let _ = self.resp_tx.send(AudioResponse::Playing);
+        self.perform_fade_in();
+    }
+
+    /// Start a loaded queue track
+    fn play_loaded(&mut self, index: usize, audio_data: AudioPlaybackData) {
+        let Some(id) = self.queue.get(index).map(|item| item.id) else {
+            return;
+        };
+        let metadata = audio_data.metadata().clone();
+
+        // Update queue metadata
+        self.queue.set_metadata(id, metadata.clone());
+        self.queue.set_current(index);
+
+        self.current_audio = Some(audio_data);
+        self.begin_listen();
+
+        // Send track changed notification
+        let _ = self.resp_tx.send(AudioResponse::TrackChanged {
+            index,
+            metadata: metadata.clone(),
+        });
+
+        // Settle the DSP for the new track before its first sample plays
+        self.prepare_dsp_for_track(&metadata);
+
+        let _ = self.resp_tx.send(AudioResponse::Loaded(metadata));
+        self.send_cover_art();
+        // Start playing
+        self.append_current_source();
+        self.sink.set_volume(0.0);
+        self.sink.play();
+        self.is_playing = true;
+        let _ = self.resp_tx.send(AudioResponse::Playing);
+        self.perform_fade_in();
+    }
+
+    /// Resolve a URL with yt-dlp on a background thread, for `poll_remote`. With
+    /// an item id, the download replaces that item's missing file.
+    fn resolve_url(&self, url: String, item: Option<usize>) {
+        let config = self.remote_config.clone();
+        let tx = self.remote_tx.clone();
+        // yt-dlp can take a while, keep playback responsive meanwhile
+        thread::spawn(move || {
+            let result = remote::resolve(&url, &config);
+            let _ = tx.send((url, item, result));
+        });
+    }
+
+    /// Download a remote item again when its cached file is gone (the cache was
+    /// cleared, or it comes from a playlist or another machine's session). It is
+    /// played, or cued at `cue_at` seconds, once the download is back.
+    fn download_again(&mut self, item: &QueueItem, cue_at: Option<f32>) -> bool {
+        let Some(url) = &item.source_url else {
+            return false;
+        };
+        if item.path.is_file() {
+            return false;
+        }
+        if self.awaiting_item.is_none_or(|(id, _)| id != item.id) {
+            log::info!("Downloading {} again", url);
+            self.resolve_url(url.clone(), Some(item.id));
+        }
+        self.awaiting_item = Some((item.id, cue_at));
+        true
+    }
+
+    /// Connect to a stream on a background thread, which can take seconds. It
+    /// is played, or cued at `cue_at` seconds, once `poll_opened_streams` gets it.
+    fn open_stream_in_background(&mut self, item: &QueueItem, cue_at: Option<f32>) -> bool {
+        if item.range.is_some() || !stream::is_stream(&item.path) {
+            return false;
+        }
+        if self.awaiting_item.is_none_or(|(id, _)| id != item.id) {
+            let (id, url) = (item.id, item.path.to_string_lossy().to_string());
+            let tx = self.opened_tx.clone();
+            thread::spawn(move || {
+                let _ = tx.send((StreamFor::Item(id), AudioPlaybackData::open_stream(&url)));
+            });
+        }
+        self.awaiting_item = Some((item.id, cue_at));
+        true
+    }
+
+    /// Connect to a stream given to `Load` on a background thread, the current
+    /// track keeps playing until `poll_opened_streams` gets it
+    fn load_stream_in_background(&mut self, url: String) {
+        if self.awaiting_load.as_ref() != Some(&url) {
+            let tx = self.opened_tx.clone();
+            let url = url.clone();
+            thread::spawn(move || {
+                let result = AudioPlaybackData::open_stream(&url);
+                let _ = tx.send((StreamFor::Load(url), result));
+            });
+        }
+        self.awaiting_load = Some(url);
+    }
+
+    /// Start (or cue) streams connected in the background, unless another
+    /// track was asked for meanwhile
+    fn poll_opened_streams(&mut self) {
+        while let Ok((target, result)) = self.opened_rx.try_recv() {
+            let id = match target {
+                StreamFor::Item(id) => id,
+                StreamFor::Load(url) => {
+                    if self.awaiting_load.as_ref() == Some(&url) {
+                        self.awaiting_load = None;
+                        self.finish_load(result);
+                    }
+                    continue;
+                }
+            };
+            let Some((_, cue_at)) = self.awaiting_item.filter(|(awaited, _)| *awaited == id) else {
+                continue;
+            };
+            self.awaiting_item = None;
+            let Some(index) = self.queue.index_of(id) else {
+                continue;
+            };
+            match result {
+                Ok(audio_data) => match cue_at {
+                    Some(position) => {
+                        self.end_listen(ListenEnd::Interrupted);
+                        self.cue_loaded(index, audio_data, position);
+                    }
+                    None => {
+                        self.stop_for_next_track();
+                        self.play_loaded(index, audio_data);
+                    }
+                },
+                Err(e) => {
+                    let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                        "Failed to load track: {:#}",
+                        e
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Play a stream `Load` connected to, or report why it failed
+    fn finish_load(&mut self, result: anyhow::Result<AudioPlaybackData>) {
+        match result {
+            Ok(audio_data) => {
+                self.stop_for_next_track();
+                self.play_unqueued(audio_data);
+            }
+            Err(e) => {
+                let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                    "Failed to load audio: {:#}",
+                    e
+                )));
+            }
+        }
+    }
+
+    /// Open a queue item. A CUE track of the file already loaded reuses its
+    /// decoded samples instead of decoding the whole album again.
+    fn load_item(&self, item: &QueueItem) -> anyhow::Result<AudioPlaybackData> {
+        let path = item.path.to_string_lossy().to_string();
+        let Some(range) = item.range else {
+            let data = AudioPlaybackData::load(&path)?;
+            if let Some(known) = &item.metadata {
+                data.fill_missing_tags(known);
+            }
+            return Ok(data);
+        };
 
-            // Fade out current track if playing
-            if self.is_playing {
-                self.perform_fade_out();
+        let known = item.metadata.clone().unwrap_or_default();
+        match &self.current_audio {
+            Some(current) if !current.is_live() && current.metadata().full_file_path == path => {
+                Ok(current.track(range, &known))
             }
+            _ => Ok(AudioPlaybackData::load_local_audio(&path)?.track(range, &known)),
+        }
+    }
+
+    /// Play CUE tracks of one file back to back: while the next track starts
+    /// where the current one ends, the source plays on into it, and once it gets
+    /// there the queue moves on without touching the sink
+    fn tick_gapless(&mut self) {
+        let (Some(data), Some(current)) = (&self.current_audio, self.queue.current()) else {
+            return;
+        };
+        let Some(range) = current.range else {
+            return;
+        };
+        if data.is_live() || data.metadata().full_file_path != current.path.to_string_lossy() {
+            return;
+        }
+
+        let next = self.queue.next_index().and_then(|index| {
+            let item = self.queue.get(index)?;
+            let next_range = item.range?;
+            (item.path == current.path && range.is_followed_by(&next_range))
+                .then_some((index, next_range))
+        });
+        let tracker = data.position_tracker();
+        tracker.play_through(next.map(|(_, range)| range));
+
+        if let Some((index, range)) = next
+            && tracker.past_end()
+        {
+            self.continue_into(index, range);
+        }
+    }
+
+    /// Make the next CUE track current while the source keeps playing
+    fn continue_into(&mut self, index: usize, range: TrackRange) {
+        let (Some(data), Some(item)) = (&self.current_audio, self.queue.get(index)) else {
+            return;
+        };
+        let id = item.id;
+        let next = data.next_track(range, &item.metadata.clone().unwrap_or_default());
+        log::info!("Continuing into track {} of the same file", index);
+        self.end_listen(ListenEnd::Finished);
+
+        let metadata = next.metadata();
+        self.queue.set_metadata(id, metadata.clone());
+        self.queue.set_current(index);
+        self.current_audio = Some(next);
+        self.begin_listen();
+
+        let _ = self.resp_tx.send(AudioResponse::TrackChanged {
+            index,
+            metadata: metadata.clone(),
+        });
+        let _ = self.resp_tx.send(AudioResponse::Loaded(metadata));
+    }
+
+    /// Look up the cover art of the loaded file in the background and send it.
+    /// Skipped for streams and when the file is the one whose cover was sent
+    /// last, as with the tracks of a CUE sheet.
+    fn send_cover_art(&mut self) {
+        let Some(data) = self.current_audio.as_ref().filter(|data| !data.is_live()) else {
+            self.cover_path = None;
+            return;
+        };
+        let path = data.metadata().full_file_path;
+        if self.cover_path.as_ref() == Some(&path) {
+            return;
+        }
+        self.cover_path = Some(path.clone());
+
+        let tx = self.resp_tx.clone();
+        thread::spawn(move || {
+            let art = cover::load_cover(Path::new(&path)).unwrap_or_else(|e| {
+                log::warn!("No cover art for {}: {}", path, e);
+                None
+            });
+            let _ = tx.send(AudioResponse::CoverArt {
+                path,
+                art: art.map(Arc::new),
+            });
+        });
+    }
+
+    /// Append a source for the current audio to the sink, carrying the current
+    /// DSP settings and a fresh realtime command channel
+    fn append_current_source(&mut self) {
+        let Some(ref data) = self.current_audio else {
+            return;
+        };
+        let (rt_tx, rt_rx) = unbounded::<RealtimeAudioCommand>();
+        self.rt_cmd_tx = Some(rt_tx);
+        self.sink.append(data.create_source(
+            self.eq_shadow.clone(),
+            self.eq_enabled,
+            self.normalizer_shadow.clone(),
+            self.normalizer_enabled,
+            rt_rx,
+        ));
+    }
+
+    /// Apply the preset rules for the new track and rebuild the equalizer for its
+    /// format, preserving the EQ settings
+    fn prepare_dsp_for_track(&mut self, metadata: &AudioMetadata) {
+        self.apply_preset_rules(metadata);
+
+        let mut new_eq = Equalizer::new(metadata.sample_rate, metadata.num_channels);
+        new_eq.filters = self.eq_shadow.filters.clone();
+        new_eq.master_gain = self.eq_shadow.master_gain;
+        new_eq.preset = self.eq_shadow.preset;
+        new_eq.set_solo(self.eq_shadow.solo());
+
+        new_eq.parameters_changed();
+        self.eq_shadow = new_eq;
+    }
+
+    /// Switch the EQ/normalizer settings to the rule matching the track.
+    /// The user's own settings are kept aside while a rule is in effect and come
+    /// back once a track matches no rule.
+    fn apply_preset_rules(&mut self, metadata: &AudioMetadata) {
+        if self.preset_rules.is_empty() {
+            return;
+        }
+
+        let Some(rule) = self.preset_rules.find(metadata).cloned() else {
+            if let Some(baseline) = self.rule_baseline.take() {
+                log::info!("No preset rule matches, restoring previous DSP settings");
+                self.restore_dsp_settings(baseline);
+                self.send_preset_rule_applied(None);
+            }
+            return;
+        };
+
+        // Rules apply on top of the user's settings, not on top of the previous rule
+        let baseline = match self.rule_baseline.clone() {
+            Some(baseline) => baseline,
+            None => {
+                let baseline = self.capture_dsp_settings();
+                self.rule_baseline = Some(baseline.clone());
+                baseline
+            }
+        };
+        log::info!("Preset rule '{}' matched", rule.name);
+        self.restore_dsp_settings(baseline.with_rule(&rule));
+        self.send_preset_rule_applied(Some(rule.name));
+    }
+
+    /// While a preset rule is in effect, carry the user's own EQ or normalizer
+    /// changes into the settings restored once no rule matches, so they aren't lost
+    fn keep_user_dsp_edit(&mut self, eq_edit: bool, normalizer_edit: bool) {
+        if !(eq_edit || normalizer_edit) || self.rule_baseline.is_none() {
+            return;
+        }
+        let current = self.capture_dsp_settings();
+        let Some(baseline) = &mut self.rule_baseline else {
+            return;
+        };
+        if eq_edit {
+            baseline.eq_preset = current.eq_preset;
+            baseline.eq_filters = current.eq_filters;
+            baseline.eq_enabled = current.eq_enabled;
+        }
+        if normalizer_edit {
+            baseline.normalizer = current.normalizer;
+            baseline.normalizer_enabled = current.normalizer_enabled;
+        }
+    }
+
+    fn capture_dsp_settings(&self) -> DspSettings {
+        DspSettings {
+            eq_preset: self.eq_shadow.preset,
+            eq_filters: self.eq_shadow.filters.clone(),
+            eq_enabled: self.eq_enabled,
+            normalizer: self.normalizer_shadow.clone(),
+            normalizer_enabled: self.normalizer_enabled,
+        }
+    }
+
+    fn restore_dsp_settings(&mut self, settings: DspSettings) {
+        self.eq_shadow.preset = settings.eq_preset;
+        self.eq_shadow.filters = settings.eq_filters;
+        self.eq_enabled = settings.eq_enabled;
+        self.normalizer_shadow = settings.normalizer;
+        self.normalizer_enabled = settings.normalizer_enabled;
+    }
+
+    fn send_preset_rule_applied(&self, rule: Option<String>) {
+        let _ = self.resp_tx.send(AudioResponse::PresetRuleApplied {
+            rule,
+            eq_enabled: self.eq_enabled,
+            eq_preset: self.eq_shadow.preset,
+            filters: self.eq_shadow.filters.clone(),
+            normalizer_enabled: self.normalizer_enabled,
+        });
+    }
+
+    /// Append tracks to the queue and start playback if the player is idle
+    fn enqueue(&mut self, paths: Vec<std::path::PathBuf>) -> Vec<usize> {
+        self.enqueue_tracks(
+            paths
+                .into_iter()
+                .map(|path| (path, None, None, None))
+                .collect(),
+        )
+    }
+
+    /// Like `enqueue`, for tracks that may come with metadata or a range
+    fn enqueue_tracks(&mut self, tracks: Vec<QueuedTrack>) -> Vec<usize> {
+        let was_empty = self.queue.items.is_empty();
+        let ids = self.queue_tracks(tracks);
+
+        // Auto-play if not already playing and not paused
+        if !self.is_playing && !self.sink.is_paused() && !ids.is_empty() {
+            if was_empty {
+                self.play_queue_track(0);
+            } else if let Some(next_idx) = self.queue.next_index() {
+                // This handles the case where the queue had ended
+                self.play_queue_track(next_idx);
+            }
+        }
+
+        ids
+    }
+
+    /// Append tracks to the queue without starting playback
+    fn queue_tracks(&mut self, tracks: Vec<QueuedTrack>) -> Vec<usize> {
+        let paths = tracks.iter().map(|(path, ..)| path.clone()).collect();
+        let ids = self.queue.add(paths);
+        for (id, (_, metadata, range, source_url)) in ids.iter().zip(tracks) {
+            if let Some(metadata) = metadata {
+                self.queue.set_metadata(*id, metadata);
+            }
+            if let Some(range) = range {
+                self.queue.set_range(*id, range);
+            }
+            if let Some(url) = source_url {
+                self.queue.set_source_url(*id, url);
+            }
+        }
+        self.session_active |= !ids.is_empty();
+        ids
+    }
+
+    /// Tracks of a playlist with the titles it records, shown until each file
+    /// is read. Errors are reported and give `None`.
+    fn playlist_tracks(&self, path: &str) -> Option<Vec<QueuedTrack>> {
+        match playlist::load_playlist(Path::new(path)) {
+            Ok(entries) => Some(
+                entries
+                    .iter()
+                    .map(|entry| {
+                        // Remote tracks are exported by their page, downloaded when played
+                        let page = entry
+                            .path
+                            .to_str()
+                            .filter(|location| remote::is_page(location))
+                            .map(str::to_string);
+                        (entry.path.clone(), entry.metadata(), entry.range, page)
+                    })
+                    .collect(),
+            ),
+            Err(e) => {
+                log::error!("Failed to load playlist {}: {}", path, e);
+                let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                    "Failed to load playlist: {}",
+                    e
+                )));
+                None
+            }
+        }
+    }
+
+    /// Stop playback and empty the queue
+    fn clear_queue(&mut self) {
+        self.end_listen(ListenEnd::Interrupted);
+        if self.is_playing {
+            self.perform_fade_out();
             self.sink.stop();
             self.is_playing = false;
+        }
+        self.queue.clear();
+        self.current_audio = None;
+    }
 
-            // Load the new track
-            match AudioPlaybackData::load_local_audio(&path) {
-                Ok(audio_data) => {
-                    let metadata = audio_data.metadata().clone();
+    /// Snapshot of the player state. DSP settings are the user's own, not the ones
+    /// a preset rule put in place for the current track.
+    fn session_snapshot(&self) -> Session {
+        let dsp = self
+            .rule_baseline
+            .clone()
+            .unwrap_or_else(|| self.capture_dsp_settings());
+        let position = self
+            .current_audio
+            .as_ref()
+            .map_or(0.0, |data| data.position_tracker().position_seconds());
 
-                    // Update queue metadata
-                    self.queue.set_metadata(item.id, metadata.clone());
-                    self.queue.current_index = Some(index);
+        let mut session = Session {
+            queue: Vec::new(),
+            current_index: None,
+            position,
+            loop_mode: self.queue.loop_mode,
+            volume: self.target_volume,
+            eq: EqSession {
+                enabled: dsp.eq_enabled,
+                preset: dsp.eq_preset,
+                master_gain_db: 20.0 * self.eq_shadow.master_gain.log10(),
+                filters: dsp.eq_filters,
+            },
+            normalizer: NormalizerSession {
+                enabled: dsp.normalizer_enabled,
+                mode: dsp.normalizer.mode(),
+                target_level: dsp.normalizer.target_level(),
+                headroom: dsp.normalizer.headroom(),
+            },
+            cue_tracks: Vec::new(),
+            remote_tracks: Vec::new(),
+        };
+        session.set_queue(&self.queue);
+        session
+    }
 
-                    self.current_audio = Some(audio_data);
+    fn save_session(&mut self) {
+        self.last_session_save = Instant::now();
+        if !self.session_active {
+            return;
+        }
+        if let Err(e) = self.session_snapshot().save() {
+            log::warn!("Failed to save session: {}", e);
+        }
+    }
 
-                    // Send track changed notification
-                    let _ = self.resp_tx.send(AudioResponse::TrackChanged {
-                        index,
-                        metadata: metadata.clone(),
-                    });
+    /// Replace the queue, loop mode, volume and DSP settings with a saved session
+    /// and cue its current track, paused. Tracks whose files are gone are left out.
+    fn restore_session(&mut self, session: Session) {
+        self.end_listen(ListenEnd::Interrupted);
+        if self.is_playing {
+            self.perform_fade_out();
+        }
+        self.sink.stop();
+        self.is_playing = false;
+        self.current_audio = None;
 
-                    // Preserve existing EQ settings for the new track
-                    let previous_filters = self.eq_shadow.filters.clone();
-                    let previous_gain = self.eq_shadow.master_gain;
-                    let previous_preset = self.eq_shadow.preset;
-
-                    let mut new_eq = Equalizer::new(metadata.sample_rate, metadata.num_channels);
-
-                    new_eq.filters = previous_filters;
-                    new_eq.master_gain = previous_gain;
-                    new_eq.preset = previous_preset;
-
-                    new_eq.parameters_changed();
-                    self.eq_shadow = new_eq;
-
-                    let _ = self.resp_tx.send(AudioResponse::Loaded(metadata));
-                    // Start playing
-                    if let Some(ref data) = self.current_audio {
-                        let (rt_tx, rt_rx) = unbounded::<RealtimeAudioCommand>();
-                        self.rt_cmd_tx = Some(rt_tx);
-                        self.sink.append(data.create_source(
-                            self.eq_shadow.clone(),
-                            self.eq_enabled,
-                            rt_rx,
-                        ));
-                        self.sink.set_volume(0.0);
-                        self.sink.play();
-                        self.is_playing = true;
-                        let _ = self.resp_tx.send(AudioResponse::Playing);
-                        self.perform_fade_in();
-                    }
+        self.queue.clear();
+        let current_index = session.restore_queue(&mut self.queue);
+        self.queue.loop_mode = session.loop_mode;
+        if session.loop_mode.is_shuffle() {
+            self.queue.reshuffle();
+        }
+        self.session_active = true;
+
+        self.target_volume = session.volume.clamp(0.0, 1.0);
+        self.sink.set_volume(self.target_volume);
+
+        self.rule_baseline = None;
+        self.eq_enabled = session.eq.enabled;
+        self.eq_shadow.preset = session.eq.preset;
+        self.eq_shadow.filters = session.eq.filters.clone();
+        self.eq_shadow.master_gain = 10.0f32.powf(session.eq.master_gain_db / 20.0);
+        self.eq_shadow.set_solo(None);
+        self.eq_shadow.parameters_changed();
+        self.normalizer_enabled = session.normalizer.enabled;
+        self.normalizer_shadow.set_mode(session.normalizer.mode);
+        self.normalizer_shadow
+            .set_target_level(session.normalizer.target_level);
+        self.normalizer_shadow
+            .set_headroom(session.normalizer.headroom);
+
+        self.send_queue_update();
+        let _ = self
+            .resp_tx
+            .send(AudioResponse::LoopModeChanged(session.loop_mode));
+        let _ = self
+            .resp_tx
+            .send(AudioResponse::SessionRestored(session.clone()));
+
+        if let Some(index) = current_index {
+            self.cue_queue_track(index, session.position);
+        }
+    }
+
+    /// Load a track from the queue without starting it, positioned at `position` seconds
+    fn cue_queue_track(&mut self, index: usize, position: f32) {
+        let Some(item) = self.queue.get(index).cloned() else {
+            return;
+        };
+        self.awaiting_load = None;
+        if self.download_again(&item, Some(position))
+            || self.open_stream_in_background(&item, Some(position))
+        {
+            return;
+        }
+        self.awaiting_item = None;
+        self.end_listen(ListenEnd::Interrupted);
+
+        match self.load_item(&item) {
+            Ok(audio_data) => self.cue_loaded(index, audio_data, position),
+            Err(e) => {
+                log::warn!("Cannot reopen session track {}: {}", item.path.display(), e);
+                let _ = self
+                    .resp_tx
+                    .send(AudioResponse::Error(format!("Failed to load track: {}", e)));
+            }
+        }
+    }
+
+    /// Make a loaded queue track the current one, paused at `position` seconds
+    fn cue_loaded(&mut self, index: usize, audio_data: AudioPlaybackData, position: f32) {
+        let Some(id) = self.queue.get(index).map(|item| item.id) else {
+            return;
+        };
+        let metadata = audio_data.metadata().clone();
+        self.queue.set_metadata(id, metadata.clone());
+        self.queue.set_current(index);
+        self.current_audio = Some(audio_data);
+        self.begin_listen();
+
+        let _ = self.resp_tx.send(AudioResponse::TrackChanged {
+            index,
+            metadata: metadata.clone(),
+        });
+        self.prepare_dsp_for_track(&metadata);
+        let _ = self.resp_tx.send(AudioResponse::Loaded(metadata.clone()));
+        self.send_cover_art();
+
+        if let Some(ref data) = self.current_audio
+            && !data.is_live()
+        {
+            data.position_tracker()
+                .seek_to_seconds(position.clamp(0.0, metadata.duration));
+        }
+        self.append_current_source();
+        self.sink.pause();
+        self.sink.set_volume(self.target_volume);
+
+        let _ = self.resp_tx.send(AudioResponse::Paused);
+        let tracker = self
+            .current_audio
+            .as_ref()
+            .map(|data| data.position_tracker());
+        let _ = self.resp_tx.send(AudioResponse::Position {
+            current: tracker.map_or(0.0, |t| t.position_seconds()),
+            total: tracker.and_then(|t| t.duration_seconds()),
+        });
+        self.send_queue_update();
+    }
+
+    /// Reorder the queue once the tags of tracks that haven't been played yet
+    /// (they only have their path) are known. Reading them can take a while on a
+    /// long queue, so it happens on a background thread picked up by `poll_tags`.
+    fn reorder_with_tags(&mut self, reorder: QueueReorder) {
+        let missing: Vec<(usize, String)> = self
+            .queue
+            .items
+            .iter()
+            .filter(|item| {
+                item.metadata.is_none() && item.range.is_none() && !stream::is_stream(&item.path)
+            })
+            .map(|item| (item.id, item.path.to_string_lossy().to_string()))
+            .collect();
+        if missing.is_empty() {
+            self.apply_reorder(reorder);
+            return;
+        }
+
+        log::info!("Reading tags of {} queued tracks", missing.len());
+        let tx = self.tags_tx.clone();
+        thread::spawn(move || {
+            let tags = missing
+                .into_iter()
+                .map(|(id, path)| (id, AudioPlaybackData::read_tags(&path)))
+                .collect();
+            let _ = tx.send((tags, reorder));
+        });
+    }
+
+    /// Fill in tags read in the background and carry out the reorder waiting for them
+    fn poll_tags(&mut self) {
+        while let Ok((tags, reorder)) = self.tags_rx.try_recv() {
+            for (id, metadata) in tags {
+                // Items may have been removed or played while the tags were read
+                let unread = self
+                    .queue
+                    .index_of(id)
+                    .and_then(|index| self.queue.get(index))
+                    .is_some_and(|item| item.metadata.is_none());
+                if unread {
+                    self.queue.set_metadata(id, metadata);
+                }
+            }
+            self.apply_reorder(reorder);
+        }
+    }
+
+    fn apply_reorder(&mut self, reorder: QueueReorder) {
+        match reorder {
+            QueueReorder::Sort(field) => self.queue.sort_by(field),
+            QueueReorder::Harmonic => self.queue.sort_harmonic(),
+            QueueReorder::AlbumShuffle => {
+                // The mode may have changed again meanwhile
+                if self.queue.loop_mode != LoopMode::AlbumShuffle {
+                    return;
+                }
+                self.queue.reshuffle();
+            }
+        }
+        self.send_queue_update();
+    }
+
+    /// Write tag changes to files, then show them on the queued tracks of
+    /// those files and on the loaded track
+    fn write_tags(&mut self, paths: Vec<String>, changes: Vec<TagChange>) {
+        let mut count = 0;
+        let mut errors = Vec::new();
+        for path in paths {
+            if let Err(e) = tags::write_tags(Path::new(&path), &changes) {
+                log::error!("Failed to write tags to {}: {}", path, e);
+                errors.push(format!("{}: {}", path, e));
+                continue;
+            }
+            count += 1;
+
+            // CUE tracks keep the sheet's titles over the album file's tags
+            let written = |item: &QueueItem| item.range.is_none() && item.path == Path::new(&path);
+            for item in self.queue.items.iter_mut().filter(|item| written(item)) {
+                if let Some(metadata) = &mut item.metadata {
+                    changes.iter().for_each(|change| change.apply_to(metadata));
+                }
+            }
+            let current_is_track = self
+                .queue
+                .current_index
+                .and_then(|i| self.queue.get(i))
+                .is_some_and(|item| item.range.is_some());
+            if let Some(current) = &self.current_audio
+                && !current_is_track
+                && current.metadata().full_file_path == path
+            {
+                current.apply_tag_changes(&changes);
+                let _ = self.resp_tx.send(AudioResponse::Loaded(current.metadata()));
+            }
+        }
+        log::info!("Wrote tags to {} files", count);
+        self.send_queue_update();
+        let _ = self
+            .resp_tx
+            .send(AudioResponse::TagsWritten { count, errors });
+    }
+
+    /// Pick up the background analysis of the loaded track: show it on the
+    /// queue, then write it to the file's tags, or ask, as configured
+    fn poll_analysis(&mut self) {
+        let Some(data) = &self.current_audio else {
+            return;
+        };
+        let Some(analysis) = data.take_analysis() else {
+            return;
+        };
+        // Only whole files are analysed, CUE tracks don't get the album's results
+        let metadata = data.metadata();
+        if let Some(id) = self.queue.current().map(|item| item.id) {
+            self.queue.set_metadata(id, metadata.clone());
+            self.send_queue_update();
+        }
+
+        let writable = !analysis.is_empty();
+        let ask = match self.analysis_config.write_tags {
+            TagWriteMode::Always if writable => {
+                self.write_analysis(metadata.full_file_path.clone(), &analysis);
+                false
+            }
+            TagWriteMode::Ask => writable,
+            _ => false,
+        };
+        let _ = self.resp_tx.send(AudioResponse::Analyzed {
+            metadata,
+            analysis,
+            ask,
+        });
+    }
+
+    fn write_analysis(&self, path: String, analysis: &Analysis) {
+        match tags::write_analysis(Path::new(&path), analysis) {
+            Ok(()) => {
+                log::info!("Wrote analysis to the tags of {}", path);
+                let _ = self.resp_tx.send(AudioResponse::AnalysisWritten(path));
+            }
+            Err(e) => {
+                log::error!("Failed to write analysis to {}: {}", path, e);
+                let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                    "Failed to write analysis to {}: {}",
+                    path, e
+                )));
+            }
+        }
+    }
+
+    /// Queue the tracks of URLs resolved in the background
+    fn poll_remote(&mut self) {
+        while let Ok((url, item, result)) = self.remote_rx.try_recv() {
+            if let Some(id) = item {
+                self.finish_download(id, &url, result);
+                continue;
+            }
+            match result {
+                Ok(tracks) => {
+                    let count = tracks.len();
+                    log::info!("Resolved {} tracks from {}", count, url);
+                    self.enqueue_tracks(
+                        tracks
+                            .into_iter()
+                            .map(|track| (track.path, Some(track.metadata), None, Some(track.url)))
+                            .collect(),
+                    );
+                    self.send_queue_update();
+                    let _ = self.resp_tx.send(AudioResponse::UrlAdded { url, count });
                 }
                 Err(e) => {
+                    log::error!("Failed to resolve {}: {}", url, e);
+                    let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                        "Failed to add {}: {}",
+                        url, e
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Put a remote item's new download in place and play it if it is still the
+    /// one asked for
+    fn finish_download(
+        &mut self,
+        id: usize,
+        url: &str,
+        result: Result<Vec<RemoteTrack>, RemoteError>,
+    ) {
+        let awaited = self
+            .awaiting_item
+            .filter(|(awaited, _)| *awaited == id)
+            .map(|(_, cue_at)| cue_at);
+        if awaited.is_some() {
+            self.awaiting_item = None;
+        }
+        let track = match result.map(|tracks| tracks.into_iter().next()) {
+            Ok(Some(track)) => track,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Failed to download {} again: {}", url, e);
+                let _ = self.resp_tx.send(AudioResponse::Error(format!(
+                    "Failed to download {}: {}",
+                    url, e
+                )));
+                return;
+            }
+        };
+        self.queue.set_path(id, track.path);
+        self.queue.set_metadata(id, track.metadata);
+        self.send_queue_update();
+
+        let Some(index) = self.queue.index_of(id) else {
+            return;
+        };
+        match awaited {
+            Some(None) => self.play_queue_track(index),
+            Some(Some(position)) => self.cue_queue_track(index, position),
+            None => {}
+        }
+    }
+
+    /// Forward title and buffering news of a playing stream
+    fn poll_stream(&mut self) {
+        let Some(data) = self.current_audio.as_mut() else {
+            return;
+        };
+        let events = data.poll_stream();
+        let metadata = data.metadata();
+        for event in events {
+            match event {
+                StreamEvent::Title(_) => {
+                    if let Some(id) = self.queue.current().map(|item| item.id) {
+                        self.queue.set_metadata(id, metadata.clone());
+                    }
                     let _ = self
                         .resp_tx
-                        .send(AudioResponse::Error(format!("Failed to load track: {}", e)));
+                        .send(AudioResponse::StreamTitle(metadata.clone()));
+                    self.send_queue_update();
+                }
+                StreamEvent::State(state) => {
+                    let _ = self.resp_tx.send(AudioResponse::StreamState(state));
+                }
+            }
+        }
+    }
+
+    fn current_item_id(&self) -> Option<usize> {
+        self.queue.current().map(|item| item.id)
+    }
+
+    /// After items were removed: if the loaded track is gone, move on to the track
+    /// that took its place (keeping the play/pause state), or stop when none is left
+    fn follow_current_item(&mut self, previous: Option<usize>) {
+        if previous.is_none() || self.current_item_id() == previous {
+            return;
+        }
+        let was_playing = self.is_playing;
+        match self.queue.current_index {
+            Some(index) if was_playing => self.play_queue_track(index),
+            Some(index) => {
+                self.sink.stop();
+                self.cue_queue_track(index, 0.0);
+            }
+            None => {
+                self.end_listen(ListenEnd::Interrupted);
+                if self.is_playing {
+                    self.perform_fade_out();
+                }
+                self.sink.stop();
+                self.is_playing = false;
+                self.current_audio = None;
+                let _ = self.resp_tx.send(AudioResponse::Stopped);
+            }
+        }
+    }
+
+    /// Start counting listening time for the loaded track
+    fn begin_listen(&mut self) {
+        self.listen = self.current_audio.as_ref().map(|data| Listen {
+            path: PathBuf::from(data.metadata().full_file_path),
+            listened: Duration::ZERO,
+            last_tick: Instant::now(),
+        });
+    }
+
+    /// Add the time since the last tick while audio is actually playing
+    fn tick_listen(&mut self) {
+        let playing = self.is_playing && !self.sink.is_paused() && !self.sink.empty();
+        if let Some(listen) = &mut self.listen {
+            let now = Instant::now();
+            if playing {
+                listen.listened += now - listen.last_tick;
+            }
+            listen.last_tick = now;
+        }
+    }
+
+    /// Record the listen of the loaded track in the statistics
+    fn end_listen(&mut self, end: ListenEnd) {
+        self.tick_listen();
+        let Some(listen) = self.listen.take() else {
+            return;
+        };
+        let fraction = match (end, &self.current_audio) {
+            (ListenEnd::Finished, _) => 1.0,
+            (_, None) => 0.0,
+            (_, Some(data)) => {
+                let tracker = data.position_tracker();
+                match tracker.duration_seconds() {
+                    Some(duration) if duration > 0.0 => tracker.position_seconds() / duration,
+                    _ => 0.0,
                 }
             }
+        };
+        self.stats
+            .record_listen(&listen.path, listen.listened.as_secs_f64(), fraction, end);
+        self.stats_dirty = true;
+    }
+
+    /// Write the statistics if they changed. Batched with the session saves
+    /// rather than written at every track change.
+    fn save_stats(&mut self) {
+        if !self.stats_dirty {
+            return;
+        }
+        self.stats_dirty = false;
+        if let Err(e) = self.stats.save() {
+            log::warn!("Failed to save play statistics: {}", e);
         }
     }
 
     /// Send queue update to TUI
     fn send_queue_update(&self) {
-        let _ = self
-            .resp_tx
-            .send(AudioResponse::QueueUpdated(self.queue.items.clone()));
+        let _ = self.resp_tx.send(AudioResponse::QueueUpdated {
+            items: self.queue.items.clone(),
+            current_index: self.queue.current_index,
+        });
+    }
+}
+
+/// Tracks to queue for `paths`, with playlists expanded into their tracks by
+/// `expand`, and the queue position `paths[start]` ends up at (the first track
+/// of a playlist, or what follows an empty one)
+fn expand_playlists(
+    paths: Vec<String>,
+    start: usize,
+    mut expand: impl FnMut(&str) -> Vec<QueuedTrack>,
+) -> (Vec<QueuedTrack>, Option<usize>) {
+    let mut tracks = Vec::new();
+    let mut start_offset = None;
+    for (index, path) in paths.into_iter().enumerate() {
+        if index == start {
+            start_offset = Some(tracks.len());
+        }
+        if playlist::is_playlist(Path::new(&path)) {
+            tracks.extend(expand(&path));
+        } else {
+            tracks.push((PathBuf::from(path), None, None, None));
+        }
+    }
+    (tracks, start_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_settings() -> DspSettings {
+        let mut filters = EqPreset::Custom.set_filters();
+        filters[0].gain = 4.5;
+        DspSettings {
+            eq_preset: EqPreset::Custom,
+            eq_filters: filters,
+            eq_enabled: false,
+            normalizer: Normalizer::new(),
+            normalizer_enabled: false,
+        }
+    }
+
+    fn rule(content: &str) -> PresetRule {
+        PresetRules::parse(content).unwrap().rules.remove(0)
+    }
+
+    #[test]
+    fn rule_without_eq_preset_leaves_the_eq_unchanged() {
+        let settings = user_settings().with_rule(&rule(
+            "[[rule]]\ngenre = \"Rock\"\n[rule.normalizer]\nenabled = true",
+        ));
+        let user = user_settings();
+        assert_eq!(settings.eq_preset, user.eq_preset);
+        assert_eq!(settings.eq_filters, user.eq_filters);
+        assert_eq!(settings.eq_enabled, user.eq_enabled);
+        assert!(settings.normalizer_enabled);
+    }
+
+    #[test]
+    fn rule_with_eq_preset_switches_the_eq_on() {
+        let settings = user_settings().with_rule(&rule(
+            "[[rule]]\ngenre = \"Rock\"\neq_preset = \"BassBoosted\"",
+        ));
+        assert_eq!(settings.eq_preset, EqPreset::BassBoosted);
+        assert_eq!(settings.eq_filters, EqPreset::BassBoosted.set_filters());
+        assert!(settings.eq_enabled);
+        assert!(!settings.normalizer_enabled);
+    }
+
+    fn playlist_of(count: usize) -> impl FnMut(&str) -> Vec<QueuedTrack> {
+        move |playlist| {
+            (0..count)
+                .map(|i| {
+                    (
+                        PathBuf::from(format!("{}-{}.mp3", playlist, i)),
+                        None,
+                        None,
+                        None,
+                    )
+                })
+                .collect()
+        }
+    }
+
+    fn queued_paths(tracks: &[QueuedTrack]) -> Vec<String> {
+        tracks
+            .iter()
+            .map(|(path, ..)| path.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn replacing_the_queue_starts_after_an_expanded_playlist() {
+        let paths = vec![
+            "a.m3u".to_string(),
+            "b.mp3".to_string(),
+            "c.mp3".to_string(),
+        ];
+        let (tracks, start) = expand_playlists(paths, 2, playlist_of(3));
+        assert_eq!(tracks.len(), 5);
+        assert_eq!(start, Some(4));
+        assert_eq!(queued_paths(&tracks)[4], "c.mp3");
+    }
+
+    #[test]
+    fn replacing_the_queue_starts_at_a_playlists_first_track() {
+        let paths = vec![
+            "a.mp3".to_string(),
+            "b.pls".to_string(),
+            "c.mp3".to_string(),
+        ];
+        let (tracks, start) = expand_playlists(paths, 1, playlist_of(2));
+        assert_eq!(start, Some(1));
+        assert_eq!(queued_paths(&tracks)[1], "b.pls-0.mp3");
+    }
+
+    #[test]
+    fn replacing_the_queue_skips_over_an_empty_playlist() {
+        let paths = vec![
+            "a.mp3".to_string(),
+            "empty.m3u".to_string(),
+            "c.mp3".to_string(),
+        ];
+        let (tracks, start) = expand_playlists(paths, 2, playlist_of(0));
+        assert_eq!(queued_paths(&tracks), ["a.mp3", "c.mp3"]);
+        assert_eq!(start, Some(1));
+    }
+
+    #[test]
+    fn replacing_the_queue_with_start_out_of_range_starts_nothing() {
+        let (_, start) = expand_playlists(vec!["a.mp3".to_string()], 3, playlist_of(1));
+        assert_eq!(start, None);
     }
 }
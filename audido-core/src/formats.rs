@@ -0,0 +1,292 @@
+//! Which files are audio the player can decode: listed by their extension, or
+//! recognised by their content whatever they are named.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use lofty::{file::FileType, probe::Probe};
+use serde::Deserialize;
+use symphonia::core::{
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+use thiserror::Error;
+
+use crate::config;
+
+/// File (inside the config directory) the format settings are kept in
+pub const FORMATS_CONFIG_FILE: &str = "formats.toml";
+
+/// Extensions of the containers the decoder reads
+pub const DEFAULT_EXTENSIONS: &[&str] = &[
+    "mp3", "mp2", "wav", "flac", "ogg", "oga", "m4a", "m4b", "mp4", "aac", "aif", "aiff", "aifc",
+    "caf", "mka", "webm",
+];
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    #[error("{}: {source}", path.display())]
+    Unreadable {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: cannot decode {codec} audio", path.display())]
+    UnsupportedCodec { path: PathBuf, codec: String },
+    #[error("{}: not an audio file", path.display())]
+    NotAudio { path: PathBuf },
+}
+
+/// Which files are listed as audio, from `formats.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// Extensions listed without looking inside the files
+    pub include: Vec<String>,
+    /// Look inside files with other extensions, or none, for audio
+    pub sniff: bool,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            include: DEFAULT_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            sniff: true,
+        }
+    }
+}
+
+impl FormatConfig {
+    pub fn load() -> Result<Self, FormatError> {
+        Ok(config::load_toml(FORMATS_CONFIG_FILE)?)
+    }
+
+    /// Whether the file is listed as audio: its extension is included, or its
+    /// content looks like audio. Files recognised by content may still use a
+    /// codec that can't be decoded; `probe` tells.
+    pub fn is_audio(&self, path: &Path) -> bool {
+        let included = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                self.include
+                    .iter()
+                    .any(|included| included.eq_ignore_ascii_case(ext))
+            });
+        included || (self.sniff && path.is_file() && sniff(path).is_some())
+    }
+}
+
+/// Codec of a playable file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Codec {
+    /// Short name, e.g. "flac" or "pcm_s16le"
+    pub name: String,
+    /// Bits per sample, when the container tells
+    pub bits_per_sample: Option<u32>,
+}
+
+/// Kind of audio file the content is, from its first bytes only. `None` when
+/// it doesn't look like audio.
+pub fn sniff(path: &Path) -> Option<FileType> {
+    // Not `Probe::open`, which would take the extension's word for it
+    let reader = BufReader::new(File::open(path).ok()?);
+    Probe::new(reader).guess_file_type().ok()?.file_type()
+}
+
+/// Find the codec of the file's audio by its content, checking that it can be
+/// decoded. The extension is only a hint, so misnamed files play too.
+pub fn probe(path: &Path) -> Result<Codec, FormatError> {
+    let file = File::open(path).map_err(|source| FormatError::Unreadable {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        stream,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    );
+    let Ok(probed) = probed else {
+        return Err(match sniff(path) {
+            // Audio the demuxers don't read, like WavPack or Monkey's Audio
+            Some(file_type) => FormatError::UnsupportedCodec {
+                path: path.to_path_buf(),
+                codec: file_type_name(&file_type),
+            },
+            None => FormatError::NotAudio {
+                path: path.to_path_buf(),
+            },
+        });
+    };
+
+    let Some(params) = probed
+        .format
+        .default_track()
+        .map(|track| track.codec_params.clone())
+    else {
+        return Err(FormatError::NotAudio {
+            path: path.to_path_buf(),
+        });
+    };
+    match symphonia::default::get_codecs().get_codec(params.codec) {
+        Some(codec) => Ok(Codec {
+            name: codec.short_name.to_string(),
+            bits_per_sample: params.bits_per_sample,
+        }),
+        // The container is known but its codec isn't, like Opus in Ogg
+        None => Err(FormatError::UnsupportedCodec {
+            path: path.to_path_buf(),
+            codec: sniff(path)
+                .map(|file_type| file_type_name(&file_type))
+                .unwrap_or_else(|| "this kind of".to_string()),
+        }),
+    }
+}
+
+/// Name of a kind of audio file as people know it
+fn file_type_name(file_type: &FileType) -> String {
+    match file_type {
+        FileType::Aac => "AAC",
+        FileType::Aiff => "AIFF",
+        FileType::Ape => "Monkey's Audio",
+        FileType::Flac => "FLAC",
+        FileType::Mpeg => "MPEG",
+        FileType::Mp4 => "MP4",
+        FileType::Mpc => "Musepack",
+        FileType::Wav => "WAV",
+        other => return format!("{:?}", other),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{browser, source::AudioPlaybackData, testing::wav};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("audido-formats-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The start of a Monkey's Audio file: sniffed as audio, but no demuxer reads it
+    fn monkeys_audio() -> Vec<u8> {
+        let mut bytes = b"MAC ".to_vec();
+        bytes.extend_from_slice(&3990u16.to_le_bytes());
+        bytes.resize(512, 0);
+        bytes
+    }
+
+    fn config(toml: &str) -> FormatConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn misnamed_files_are_recognised_by_content() {
+        let dir = test_dir("misnamed");
+        let no_extension = dir.join("track");
+        let wrong_extension = dir.join("track.mp3");
+        let text = dir.join("notes.dat");
+        fs::write(&no_extension, wav(8000, 800)).unwrap();
+        fs::write(&wrong_extension, wav(8000, 800)).unwrap();
+        fs::write(&text, b"not audio at all").unwrap();
+
+        let formats = FormatConfig::default();
+        assert!(formats.is_audio(&no_extension));
+        assert_eq!(sniff(&no_extension), Some(FileType::Wav));
+        assert!(!formats.is_audio(&text));
+
+        // The extension is only a hint, the content decides the codec
+        for path in [&no_extension, &wrong_extension] {
+            let codec = probe(path).unwrap();
+            assert_eq!(codec.name, "pcm_s16le");
+            assert_eq!(codec.bits_per_sample, Some(16));
+        }
+        assert!(matches!(probe(&text), Err(FormatError::NotAudio { .. })));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn configured_extensions_are_listed_without_sniffing() {
+        let dir = test_dir("config");
+        let custom = dir.join("take.xyz");
+        let wav_file = dir.join("take.wav");
+        let misnamed = dir.join("take.bin");
+        fs::write(&custom, b"anything").unwrap();
+        fs::write(&wav_file, wav(8000, 800)).unwrap();
+        fs::write(&misnamed, wav(8000, 800)).unwrap();
+
+        let only_xyz = config("include = [\"XYZ\"]\nsniff = false");
+        assert!(only_xyz.is_audio(&custom));
+        assert!(!only_xyz.is_audio(&wav_file));
+        assert!(!only_xyz.is_audio(&misnamed));
+
+        let sniffing = config("include = [\"xyz\"]");
+        assert!(sniffing.sniff);
+        assert!(sniffing.is_audio(&misnamed));
+        assert!(sniffing.is_audio(&wav_file));
+        assert!(!sniffing.is_audio(&dir.join("missing.bin")));
+
+        assert_eq!(config("").include.len(), DEFAULT_EXTENSIONS.len());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unsupported_codecs_fail_per_file() {
+        let dir = test_dir("unsupported");
+        let ape = dir.join("album.ape");
+        let good = dir.join("good.wav");
+        fs::write(&ape, monkeys_audio()).unwrap();
+        fs::write(&good, wav(8000, 800)).unwrap();
+
+        let error = probe(&ape).unwrap_err();
+        assert!(
+            matches!(&error, FormatError::UnsupportedCodec { codec, .. } if codec == "Monkey's Audio"),
+            "{error}"
+        );
+        assert!(error.to_string().contains("album.ape"));
+
+        // Listed next to the other files, so the directory still opens
+        let formats = FormatConfig::default();
+        assert!(formats.is_audio(&ape));
+        let names: Vec<String> = browser::get_directory_content(&dir, &formats)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, ["..", "album.ape", "good.wav"]);
+
+        // Loading it fails on its own and says why; its neighbour still loads
+        let Err(load_error) = AudioPlaybackData::load(ape.to_str().unwrap()) else {
+            panic!("{} loaded", ape.display());
+        };
+        assert!(format!("{:#}", load_error).contains("cannot decode Monkey's Audio"));
+        assert!(AudioPlaybackData::load(good.to_str().unwrap()).is_ok());
+        // Reading its tags for the library or queue doesn't fail either
+        assert_eq!(
+            AudioPlaybackData::read_tags(ape.to_str().unwrap()).codec,
+            None
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
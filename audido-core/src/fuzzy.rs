@@ -0,0 +1,227 @@
+//! Fuzzy matching for search-as-you-type over track lists.
+//!
+//! A pattern matches a text when its characters appear in the text in order,
+//! case-insensitively. Matches are scored so that consecutive characters and
+//! characters at the start of words rank higher, and spread-out matches rank lower.
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_CONSECUTIVE: i32 = 12;
+const BONUS_WORD_START: i32 = 10;
+const BONUS_FIRST_CHAR: i32 = 8;
+const PENALTY_GAP: i32 = 1;
+/// Gaps longer than this don't cost more
+const MAX_GAP_PENALTY: i32 = 8;
+
+/// A parsed search pattern. Whitespace separates terms; every term has to match
+/// at least one of the searched fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyMatcher {
+    terms: Vec<Vec<char>>,
+}
+
+impl FuzzyMatcher {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            terms: pattern
+                .split_whitespace()
+                .map(|term| term.chars().map(fold_case).collect())
+                .collect(),
+        }
+    }
+
+    /// An empty pattern matches everything
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Score of a single text, `None` when it doesn't match
+    pub fn score(&self, text: &str) -> Option<i32> {
+        self.score_fields([text])
+    }
+
+    /// Score over several fields of one item (e.g. title, artist, album, file
+    /// name): each term counts with its best field. `None` when a term matches
+    /// no field.
+    pub fn score_fields<'a>(&self, fields: impl IntoIterator<Item = &'a str>) -> Option<i32> {
+        let fields: Vec<Vec<char>> = fields.into_iter().map(|f| f.chars().collect()).collect();
+        let mut total = 0;
+        for term in &self.terms {
+            total += fields
+                .iter()
+                .filter_map(|field| score_term(term, field))
+                .max()?;
+        }
+        Some(total)
+    }
+
+    /// Indices of the matching items, best match first. Equal scores keep the
+    /// items' order.
+    pub fn rank<T, F, I>(&self, items: &[T], fields: F) -> Vec<usize>
+    where
+        F: Fn(&T) -> I,
+        I: IntoIterator<Item = String>,
+    {
+        let mut scored: Vec<(usize, i32)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let fields: Vec<String> = fields(item).into_iter().collect();
+                self.score_fields(fields.iter().map(String::as_str))
+                    .map(|score| (i, score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored.into_iter().map(|(i, _)| i).collect()
+    }
+}
+
+/// Score one lowercase term against a text
+fn score_term(term: &[char], text: &[char]) -> Option<i32> {
+    if term.is_empty() {
+        return Some(0);
+    }
+
+    // Find where the first full match ends, then walk back from there to the
+    // latest possible start so the matched window is as tight as possible
+    let mut t = 0;
+    let mut end = None;
+    for (i, c) in text.iter().enumerate() {
+        if fold_case(*c) == term[t] {
+            t += 1;
+            if t == term.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut start = end;
+    let mut t = term.len();
+    for i in (0..=end).rev() {
+        if fold_case(text[i]) == term[t - 1] {
+            t -= 1;
+            if t == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    // Score the window going forward again
+    let mut score = 0;
+    let mut t = 0;
+    let mut last: Option<usize> = None;
+    for i in start..=end {
+        if t == term.len() {
+            break;
+        }
+        if fold_case(text[i]) != term[t] {
+            continue;
+        }
+        score += SCORE_MATCH;
+        if is_word_start(text, i) {
+            score += BONUS_WORD_START;
+            if i == 0 {
+                score += BONUS_FIRST_CHAR;
+            }
+        }
+        match last {
+            Some(prev) if prev + 1 == i => score += BONUS_CONSECUTIVE,
+            Some(prev) => score -= ((i - prev - 1) as i32 * PENALTY_GAP).min(MAX_GAP_PENALTY),
+            None => {}
+        }
+        last = Some(i);
+        t += 1;
+    }
+    Some(score)
+}
+
+/// Start of the text, after a separator, or a lowercase → uppercase change
+fn is_word_start(text: &[char], i: usize) -> bool {
+    let Some(prev) = i.checked_sub(1).map(|p| text[p]) else {
+        return true;
+    };
+    let c = text[i];
+    !prev.is_alphanumeric() || (prev.is_lowercase() && c.is_uppercase())
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_characters_in_order_only() {
+        let matcher = FuzzyMatcher::new("rdh");
+        assert!(matcher.score("Radiohead").is_some());
+        assert!(FuzzyMatcher::new("hdr").score("Radiohead").is_none());
+        assert!(FuzzyMatcher::new("radioheadx").score("Radiohead").is_none());
+    }
+
+    #[test]
+    fn folds_case_on_both_sides() {
+        assert_eq!(
+            FuzzyMatcher::new("BJÖRK").score("björk"),
+            FuzzyMatcher::new("björk").score("Björk")
+        );
+        assert!(FuzzyMatcher::new("ÉTÉ").score("été indien").is_some());
+    }
+
+    #[test]
+    fn consecutive_characters_beat_spread_out_ones() {
+        let matcher = FuzzyMatcher::new("love");
+        let tight = matcher.score("gloves").unwrap();
+        let spread = matcher.score("lxoxvxe").unwrap();
+        assert!(tight > spread, "{tight} <= {spread}");
+    }
+
+    #[test]
+    fn word_starts_beat_matches_inside_words() {
+        let matcher = FuzzyMatcher::new("ts");
+        let word_starts = matcher.score("the smiths").unwrap();
+        let inside = matcher.score("outsiders").unwrap();
+        assert!(word_starts > inside, "{word_starts} <= {inside}");
+
+        // camelCase boundaries count as word starts too
+        let camel = FuzzyMatcher::new("ds").score("myDarkSide").unwrap();
+        let plain = FuzzyMatcher::new("ds").score("mydarkside").unwrap();
+        assert!(camel > plain);
+    }
+
+    #[test]
+    fn tightest_window_is_scored() {
+        // Only the adjacent "ab" at the end counts, not the spread first "a"
+        let matcher = FuzzyMatcher::new("ab");
+        assert_eq!(matcher.score("a....xab"), matcher.score("xab"));
+    }
+
+    #[test]
+    fn every_term_has_to_match_some_field() {
+        let matcher = FuzzyMatcher::new("creep radio");
+        assert!(matcher.score_fields(["Creep", "Radiohead"]).is_some());
+        assert!(
+            matcher
+                .score_fields(["Creep", "Stone Temple Pilots"])
+                .is_none()
+        );
+        assert!(matcher.score("creep").is_none());
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        let matcher = FuzzyMatcher::new("   ");
+        assert!(matcher.is_empty());
+        assert_eq!(matcher.score("anything"), Some(0));
+        assert_eq!(matcher.rank(&["b", "a"], |s| [s.to_string()]), [0, 1]);
+    }
+
+    #[test]
+    fn rank_puts_best_first_and_keeps_order_on_ties() {
+        let items = ["xaxbx", "first ab", "no match", "second ab", "ab"];
+        let ranked = FuzzyMatcher::new("ab").rank(&items, |s| [s.to_string()]);
+        assert_eq!(ranked, [4, 1, 3, 0]);
+    }
+}
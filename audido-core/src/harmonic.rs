@@ -0,0 +1,259 @@
+//! Harmonic mixing: how well two tracks follow each other by key (on the
+//! Camelot wheel) and tempo, and ordering tracks for smooth transitions.
+
+use crate::metadata::{AudioMetadata, MusicalSongKey};
+
+/// Tempo changes up to this many percent sound seamless
+const BPM_SEAMLESS_PERCENT: f32 = 3.0;
+/// Tempo changes up to this many percent are still mixable
+const BPM_MIXABLE_PERCENT: f32 = 8.0;
+/// Cost of one step on the Camelot wheel, in tempo percent
+const KEY_STEP_COST: f32 = 4.0;
+/// Cost assumed when a track's key or tempo is unknown
+const UNKNOWN_KEY_COST: f32 = 2.5 * KEY_STEP_COST;
+const UNKNOWN_BPM_COST: f32 = BPM_MIXABLE_PERCENT;
+
+/// Steps between two keys on the Camelot wheel: moving one hour, or switching
+/// between a minor key and its relative major, is one step each
+pub fn camelot_distance(a: MusicalSongKey, b: MusicalSongKey) -> u8 {
+    let (hour_a, letter_a) = a.camelot();
+    let (hour_b, letter_b) = b.camelot();
+    let diff = hour_a.abs_diff(hour_b);
+    let hours = diff.min(12 - diff);
+    hours + u8::from(letter_a != letter_b)
+}
+
+/// Tempo change from `from` to `to` in percent, treating half and double time
+/// as the same tempo
+pub fn bpm_change_percent(from: f32, to: f32) -> f32 {
+    [1.0, 2.0, 0.5]
+        .into_iter()
+        .map(|factor| ((to * factor - from) / from * 100.0).abs())
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// How well a transition mixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+pub enum Compatibility {
+    /// Same or neighbouring key and a near-identical tempo
+    Harmonic,
+    /// Mixable with a small key or tempo adjustment
+    Compatible,
+    /// Keys clash or the tempo jumps
+    Clash,
+    /// Neither key nor tempo known for both tracks
+    Unknown,
+}
+
+/// Key and tempo relation between two consecutive tracks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// Camelot steps, `None` when a key is unknown
+    pub key_distance: Option<u8>,
+    /// Tempo change in percent, `None` when a tempo is unknown
+    pub bpm_change: Option<f32>,
+}
+
+impl Transition {
+    pub fn between(from: &AudioMetadata, to: &AudioMetadata) -> Self {
+        let key_distance = from.key.zip(to.key).map(|(a, b)| camelot_distance(a, b));
+        let bpm_change = from
+            .bpm
+            .zip(to.bpm)
+            .filter(|(a, _)| *a > 0.0)
+            .map(|(a, b)| bpm_change_percent(a, b));
+        Self {
+            key_distance,
+            bpm_change,
+        }
+    }
+
+    pub fn compatibility(&self) -> Compatibility {
+        let key = self.key_distance.map(|d| match d {
+            0 | 1 => Compatibility::Harmonic,
+            2 => Compatibility::Compatible,
+            _ => Compatibility::Clash,
+        });
+        let bpm = self.bpm_change.map(|change| {
+            if change <= BPM_SEAMLESS_PERCENT {
+                Compatibility::Harmonic
+            } else if change <= BPM_MIXABLE_PERCENT {
+                Compatibility::Compatible
+            } else {
+                Compatibility::Clash
+            }
+        });
+        // The weaker of the two decides
+        match (key, bpm) {
+            (Some(a), Some(b)) => worst(a, b),
+            (Some(c), None) | (None, Some(c)) => c,
+            (None, None) => Compatibility::Unknown,
+        }
+    }
+
+    /// Cost used when ordering, lower mixes better
+    fn cost(&self) -> f32 {
+        let key = self
+            .key_distance
+            .map_or(UNKNOWN_KEY_COST, |d| d as f32 * KEY_STEP_COST);
+        let bpm = self.bpm_change.unwrap_or(UNKNOWN_BPM_COST);
+        key + bpm
+    }
+}
+
+fn worst(a: Compatibility, b: Compatibility) -> Compatibility {
+    let rank = |c: Compatibility| match c {
+        Compatibility::Harmonic => 0,
+        Compatibility::Compatible => 1,
+        Compatibility::Clash => 2,
+        Compatibility::Unknown => 3,
+    };
+    if rank(a) >= rank(b) { a } else { b }
+}
+
+/// Order tracks so that each one mixes well into the next, starting with
+/// `tracks[start]`. Greedily picks the cheapest next track, which keeps long
+/// queues fast while avoiding the worst key clashes and tempo jumps.
+/// Returns indices into `tracks`.
+pub fn harmonic_order(tracks: &[&AudioMetadata], start: usize) -> Vec<usize> {
+    if tracks.is_empty() {
+        return Vec::new();
+    }
+    let mut order = Vec::with_capacity(tracks.len());
+    let mut remaining: Vec<usize> = (0..tracks.len()).filter(|&i| i != start).collect();
+    let mut last = start;
+    order.push(start);
+    while !remaining.is_empty() {
+        let (pos, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(pos, &i)| (pos, Transition::between(tracks[last], tracks[i]).cost()))
+            .fold((0, f32::INFINITY), |best, candidate| {
+                // Ties keep the earlier track, so unknown tracks stay in order
+                if candidate.1 < best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        last = remaining.remove(pos);
+        order.push(last);
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: &str) -> MusicalSongKey {
+        code.parse().unwrap()
+    }
+
+    fn track(key_code: Option<&str>, bpm: Option<f32>) -> AudioMetadata {
+        AudioMetadata {
+            key: key_code.map(key),
+            bpm,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn camelot_distance_wraps_around_the_wheel() {
+        assert_eq!(camelot_distance(key("12A"), key("1A")), 1);
+        assert_eq!(camelot_distance(key("1B"), key("11B")), 2);
+        assert_eq!(camelot_distance(key("2A"), key("8A")), 6);
+        assert_eq!(camelot_distance(key("5A"), key("5A")), 0);
+    }
+
+    #[test]
+    fn relative_major_and_minor_are_one_step_apart() {
+        assert_eq!(camelot_distance(key("8A"), key("8B")), 1);
+        assert_eq!(camelot_distance(key("Am"), key("C")), 1);
+        assert_eq!(camelot_distance(key("12A"), key("1B")), 2);
+    }
+
+    #[test]
+    fn half_and_double_tempo_count_as_the_same() {
+        assert_eq!(bpm_change_percent(70.0, 140.0), 0.0);
+        assert_eq!(bpm_change_percent(140.0, 70.0), 0.0);
+        assert!((bpm_change_percent(128.0, 65.0) - 1.5625).abs() < 1e-4);
+        assert!((bpm_change_percent(120.0, 126.0) - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn compatibility_takes_the_weaker_of_key_and_tempo() {
+        let from = track(Some("8A"), Some(124.0));
+        let compatibility = |to: AudioMetadata| Transition::between(&from, &to).compatibility();
+        assert_eq!(
+            compatibility(track(Some("9A"), Some(125.0))),
+            Compatibility::Harmonic
+        );
+        assert_eq!(
+            compatibility(track(Some("10A"), Some(124.0))),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            compatibility(track(Some("8B"), Some(132.0))),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            compatibility(track(Some("8A"), Some(140.0))),
+            Compatibility::Clash
+        );
+        assert_eq!(
+            compatibility(track(Some("3B"), Some(124.0))),
+            Compatibility::Clash
+        );
+    }
+
+    #[test]
+    fn missing_key_or_tempo_is_left_out() {
+        let from = track(Some("8A"), Some(124.0));
+
+        let no_bpm = Transition::between(&from, &track(Some("9A"), None));
+        assert_eq!(no_bpm.key_distance, Some(1));
+        assert_eq!(no_bpm.bpm_change, None);
+        assert_eq!(no_bpm.compatibility(), Compatibility::Harmonic);
+
+        let no_key = Transition::between(&from, &track(None, Some(150.0)));
+        assert_eq!(no_key.key_distance, None);
+        assert_eq!(no_key.compatibility(), Compatibility::Clash);
+
+        let unknown = Transition::between(&from, &track(None, None));
+        assert_eq!(unknown.compatibility(), Compatibility::Unknown);
+
+        // A zero tempo is treated as unknown rather than dividing by it
+        let zero = Transition::between(&track(None, Some(0.0)), &from);
+        assert_eq!(zero.bpm_change, None);
+    }
+
+    #[test]
+    fn greedy_order_follows_the_cheapest_transitions() {
+        let tracks = [
+            track(Some("8A"), Some(124.0)),
+            track(Some("3B"), Some(124.0)),
+            track(Some("9A"), Some(125.0)),
+            track(Some("8B"), Some(124.0)),
+            track(Some("10A"), Some(126.0)),
+        ];
+        let refs: Vec<&AudioMetadata> = tracks.iter().collect();
+        assert_eq!(harmonic_order(&refs, 0), [0, 3, 2, 4, 1]);
+        assert_eq!(harmonic_order(&refs, 1)[0], 1);
+    }
+
+    #[test]
+    fn unknown_tracks_keep_their_order() {
+        let tracks = [
+            track(Some("8A"), Some(124.0)),
+            track(None, None),
+            track(Some("1B"), Some(90.0)),
+            track(None, None),
+        ];
+        let refs: Vec<&AudioMetadata> = tracks.iter().collect();
+        // An unknown track costs less than the 1B clash; after it every
+        // candidate costs the same, so the rest stay in order
+        assert_eq!(harmonic_order(&refs, 0), [0, 1, 2, 3]);
+        assert!(harmonic_order(&[], 0).is_empty());
+    }
+}
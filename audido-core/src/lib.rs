@@ -1,7 +1,26 @@
+pub mod analysis;
 pub mod browser;
 pub mod commands;
+pub mod config;
+pub mod cover;
+pub mod cue;
 pub mod dsp;
 pub mod engine;
+pub mod formats;
+pub mod fuzzy;
+pub mod harmonic;
+pub mod library;
+pub mod lyrics;
 pub mod metadata;
+pub mod playlist;
+pub mod preset_rules;
 pub mod queue;
+pub mod remote;
+pub mod session;
 pub mod source;
+pub mod stats;
+pub mod stream;
+pub mod tags;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,188 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::LibraryError;
+use crate::{
+    config,
+    metadata::{AudioMetadata, ChannelLayout, MusicalSongKey},
+};
+
+/// Bumped when the stored track layout changes; older indexes are rebuilt
+const INDEX_VERSION: u32 = 1;
+
+/// One indexed audio file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryTrack {
+    pub path: PathBuf,
+    /// Modification time in seconds since the Unix epoch
+    pub mtime: u64,
+    /// File size in bytes
+    pub size: u64,
+    /// When the file was first indexed, in seconds since the Unix epoch
+    pub added: u64,
+    pub format: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    /// Duration in seconds
+    pub duration: f32,
+    pub sample_rate: u32,
+    pub num_channels: u16,
+    /// Tempo from analysis, if the track was analysed
+    pub bpm: Option<f32>,
+    /// Key from analysis, if the track was analysed
+    pub key: Option<MusicalSongKey>,
+}
+
+impl LibraryTrack {
+    pub fn from_metadata(
+        path: PathBuf,
+        mtime: u64,
+        size: u64,
+        added: u64,
+        metadata: &AudioMetadata,
+    ) -> Self {
+        Self {
+            path,
+            mtime,
+            size,
+            added,
+            format: metadata.format.clone(),
+            title: metadata.title.clone(),
+            artist: metadata.author.clone(),
+            album: metadata.album.clone(),
+            album_artist: metadata.album_artist.clone(),
+            genre: metadata.genre.clone(),
+            duration: metadata.duration,
+            sample_rate: metadata.sample_rate,
+            num_channels: metadata.num_channels,
+            bpm: metadata.bpm,
+            key: metadata.key,
+        }
+    }
+
+    /// Metadata as the player uses it, e.g. to show queued library tracks
+    pub fn to_metadata(&self) -> AudioMetadata {
+        AudioMetadata {
+            format: self.format.clone(),
+            sample_rate: self.sample_rate,
+            num_channels: self.num_channels,
+            channel_layout: ChannelLayout::from_channels(self.num_channels),
+            full_file_path: self.path.to_string_lossy().to_string(),
+            title: self.title.clone(),
+            author: self.artist.clone(),
+            genre: self.genre.clone(),
+            bpm: self.bpm,
+            key: self.key,
+            duration: self.duration,
+            album: self.album.clone(),
+            album_artist: self.album_artist.clone(),
+            file_size: Some(self.size),
+            ..Default::default()
+        }
+    }
+
+    /// Title, falling back to the file name
+    pub fn display_title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            self.path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Artist an album is filed under: the album artist, else the track artist
+    pub fn album_artist_or_artist(&self) -> Option<&str> {
+        self.album_artist.as_deref().or(self.artist.as_deref())
+    }
+}
+
+/// All indexed tracks, keyed by path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    version: u32,
+    tracks: BTreeMap<PathBuf, LibraryTrack>,
+}
+
+impl Default for LibraryIndex {
+    fn default() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            tracks: BTreeMap::new(),
+        }
+    }
+}
+
+impl LibraryIndex {
+    pub fn load(path: &Path) -> Result<Self, LibraryError> {
+        let index: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if index.version != INDEX_VERSION {
+            log::info!("Library index format changed, rebuilding on next scan");
+            return Ok(Self::default());
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LibraryError> {
+        config::write_atomic(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&LibraryTrack> {
+        self.tracks.get(path)
+    }
+
+    /// Tracks in path order
+    pub fn tracks(&self) -> impl Iterator<Item = &LibraryTrack> {
+        self.tracks.values()
+    }
+
+    pub fn insert(&mut self, track: LibraryTrack) {
+        self.tracks.insert(track.path.clone(), track);
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Option<LibraryTrack> {
+        self.tracks.remove(path)
+    }
+
+    /// Keep only the tracks for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&LibraryTrack) -> bool) {
+        self.tracks.retain(|_, track| keep(track));
+    }
+
+    /// Store analysis results for an indexed track. Returns false when the path
+    /// isn't in the library.
+    pub fn record_analysis(
+        &mut self,
+        path: &Path,
+        bpm: Option<f32>,
+        key: Option<MusicalSongKey>,
+    ) -> bool {
+        let Some(track) = self.tracks.get_mut(path) else {
+            return false;
+        };
+        if bpm.is_some() {
+            track.bpm = bpm;
+        }
+        if key.is_some() {
+            track.key = key;
+        }
+        true
+    }
+}
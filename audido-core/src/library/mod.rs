@@ -0,0 +1,112 @@
+//! Music library: an on-disk index of the audio files below the configured root
+//! folders, kept up to date by incremental rescans.
+
+mod index;
+mod query;
+mod scan;
+mod smart;
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{config, formats::FormatConfig};
+
+pub use index::{LibraryIndex, LibraryTrack};
+pub use query::{AlbumSummary, LibraryQuery, LibrarySort, UNKNOWN_ALBUM, UNKNOWN_ARTIST};
+pub use scan::ScanStats;
+pub use smart::{SMART_PLAYLISTS_FILE, SmartPlaylist, SmartPlaylistError, SmartPlaylists};
+
+/// File (inside the config directory) listing the library root folders
+pub const LIBRARY_CONFIG_FILE: &str = "library.toml";
+/// File (inside the data directory) the index is stored in
+pub const LIBRARY_INDEX_FILE: &str = "library.json";
+
+#[derive(Error, Debug)]
+pub enum LibraryError {
+    #[error("no data directory available on this platform")]
+    NoDataDir,
+    #[error("cannot access library files: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    #[error("invalid library index: {0}")]
+    Index(#[from] serde_json::Error),
+}
+
+/// Contents of the library config file
+#[derive(Debug, Default, Deserialize)]
+pub struct LibraryConfig {
+    /// Folders scanned for music, `~` is expanded
+    #[serde(default)]
+    pub roots: Vec<String>,
+}
+
+impl LibraryConfig {
+    /// Load the config from the config directory. A missing file means no roots.
+    pub fn load() -> Result<Self, LibraryError> {
+        Ok(config::load_toml(LIBRARY_CONFIG_FILE)?)
+    }
+
+    pub fn root_paths(&self) -> Vec<PathBuf> {
+        self.roots
+            .iter()
+            .map(|root| config::expand_home(root))
+            .collect()
+    }
+}
+
+/// The library: root folders plus the index of what was found in them
+#[derive(Debug, Clone)]
+pub struct Library {
+    pub roots: Vec<PathBuf>,
+    pub index: LibraryIndex,
+    /// Which files under the roots are audio
+    pub formats: FormatConfig,
+    /// Where the index is persisted
+    index_path: PathBuf,
+}
+
+impl Library {
+    /// Open the library from the user's config and data directories
+    pub fn open() -> Result<Self, LibraryError> {
+        let roots = LibraryConfig::load()?.root_paths();
+        let index_path = config::data_file(LIBRARY_INDEX_FILE).ok_or(LibraryError::NoDataDir)?;
+        // A missing index file starts an empty index
+        let index = if index_path.exists() {
+            LibraryIndex::load(&index_path)?
+        } else {
+            LibraryIndex::default()
+        };
+        let formats = FormatConfig::load().unwrap_or_else(|e| {
+            log::warn!("Using default format settings: {}", e);
+            FormatConfig::default()
+        });
+        Ok(Self {
+            roots,
+            index,
+            formats,
+            index_path,
+        })
+    }
+
+    /// Walk the roots, reading tags only for files that are new or whose
+    /// modification time or size changed, then persist the index
+    pub fn rescan(&mut self) -> Result<ScanStats, LibraryError> {
+        let stats = scan::rescan(&mut self.index, &self.roots, &self.formats);
+        log::info!(
+            "Library rescan: {} added, {} updated, {} removed, {} unchanged",
+            stats.added,
+            stats.updated,
+            stats.removed,
+            stats.unchanged
+        );
+        self.save()?;
+        Ok(stats)
+    }
+
+    pub fn save(&self) -> Result<(), LibraryError> {
+        self.index.save(&self.index_path)
+    }
+}
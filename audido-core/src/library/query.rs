@@ -0,0 +1,344 @@
+use std::cmp::Ordering;
+
+use super::{LibraryIndex, LibraryTrack};
+use crate::metadata::split_genres;
+
+/// Label for tracks without an artist tag
+pub const UNKNOWN_ARTIST: &str = "Unknown Artist";
+/// Label for tracks without an album tag
+pub const UNKNOWN_ALBUM: &str = "Unknown Album";
+
+/// Order of query results
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, strum::Display, strum::EnumIter, serde::Deserialize,
+)]
+pub enum LibrarySort {
+    /// Artist, then album, then file path (albums in track order)
+    #[default]
+    Artist,
+    Album,
+    Title,
+    Duration,
+    /// Most recently added first
+    Added,
+    Path,
+}
+
+/// Filter over the library. Unset fields match everything; artist, album and
+/// genre compare case-insensitively, `text` is a substring search over title,
+/// artist, album and path.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryQuery {
+    pub text: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub sort: LibrarySort,
+}
+
+impl LibraryQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn artist(mut self, artist: impl Into<String>) -> Self {
+        self.artist = Some(artist.into());
+        self
+    }
+
+    pub fn album(mut self, album: impl Into<String>) -> Self {
+        self.album = Some(album.into());
+        self
+    }
+
+    pub fn genre(mut self, genre: impl Into<String>) -> Self {
+        self.genre = Some(genre.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: LibrarySort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn matches(&self, track: &LibraryTrack) -> bool {
+        if let Some(artist) = &self.artist
+            && !artist_name(track).eq_ignore_ascii_case(artist)
+        {
+            return false;
+        }
+        if let Some(album) = &self.album
+            && !album_name(track).eq_ignore_ascii_case(album)
+        {
+            return false;
+        }
+        if let Some(genre) = &self.genre {
+            let matched = track
+                .genre
+                .as_deref()
+                .is_some_and(|genres| split_genres(genres).any(|g| g.eq_ignore_ascii_case(genre)));
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            let needle = text.to_lowercase();
+            let fields = [
+                track.title.as_deref(),
+                track.artist.as_deref(),
+                track.album.as_deref(),
+                track.album_artist.as_deref(),
+            ];
+            let in_tags = fields
+                .into_iter()
+                .flatten()
+                .any(|field| field.to_lowercase().contains(&needle));
+            if !in_tags
+                && !track
+                    .path
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .contains(&needle)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// An album with its totals
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlbumSummary {
+    pub album: String,
+    pub artist: String,
+    pub track_count: usize,
+    /// Total duration in seconds
+    pub duration: f32,
+}
+
+/// Artist a track is filed under, with a label for untagged tracks
+fn artist_name(track: &LibraryTrack) -> &str {
+    track.album_artist_or_artist().unwrap_or(UNKNOWN_ARTIST)
+}
+
+fn album_name(track: &LibraryTrack) -> &str {
+    track.album.as_deref().unwrap_or(UNKNOWN_ALBUM)
+}
+
+impl LibraryIndex {
+    /// Tracks matching the query, in the query's order
+    pub fn query(&self, query: &LibraryQuery) -> Vec<&LibraryTrack> {
+        let mut tracks: Vec<&LibraryTrack> = self.tracks().filter(|t| query.matches(t)).collect();
+        tracks.sort_by(|a, b| compare(a, b, query.sort));
+        tracks
+    }
+
+    /// Artist names (album artist, else track artist), sorted
+    pub fn artists(&self) -> Vec<String> {
+        let mut artists: Vec<String> = Vec::new();
+        for track in self.tracks() {
+            push_unique(&mut artists, artist_name(track));
+        }
+        artists.sort_by_key(|a| a.to_lowercase());
+        artists
+    }
+
+    /// Albums with their totals, optionally only those of one artist
+    pub fn albums(&self, artist: Option<&str>) -> Vec<AlbumSummary> {
+        let mut albums: Vec<AlbumSummary> = Vec::new();
+        for track in self.tracks() {
+            let track_artist = artist_name(track);
+            if artist.is_some_and(|a| !a.eq_ignore_ascii_case(track_artist)) {
+                continue;
+            }
+            let album = album_name(track);
+            match albums.iter_mut().find(|summary| {
+                summary.album.eq_ignore_ascii_case(album)
+                    && summary.artist.eq_ignore_ascii_case(track_artist)
+            }) {
+                Some(summary) => {
+                    summary.track_count += 1;
+                    summary.duration += track.duration;
+                }
+                None => albums.push(AlbumSummary {
+                    album: album.to_string(),
+                    artist: track_artist.to_string(),
+                    track_count: 1,
+                    duration: track.duration,
+                }),
+            }
+        }
+        albums.sort_by_key(|a| (a.artist.to_lowercase(), a.album.to_lowercase()));
+        albums
+    }
+
+    /// Individual genres across the library, sorted
+    pub fn genres(&self) -> Vec<String> {
+        let mut genres: Vec<String> = Vec::new();
+        for genres_tag in self.tracks().filter_map(|t| t.genre.as_deref()) {
+            for genre in split_genres(genres_tag) {
+                push_unique(&mut genres, genre);
+            }
+        }
+        genres.sort_by_key(|g| g.to_lowercase());
+        genres
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+        list.push(value.to_string());
+    }
+}
+
+fn compare(a: &LibraryTrack, b: &LibraryTrack, sort: LibrarySort) -> Ordering {
+    let text = |s: &str| s.to_lowercase();
+    match sort {
+        LibrarySort::Artist => text(artist_name(a))
+            .cmp(&text(artist_name(b)))
+            .then_with(|| text(album_name(a)).cmp(&text(album_name(b))))
+            .then_with(|| a.path.cmp(&b.path)),
+        LibrarySort::Album => text(album_name(a))
+            .cmp(&text(album_name(b)))
+            .then_with(|| a.path.cmp(&b.path)),
+        LibrarySort::Title => text(&a.display_title()).cmp(&text(&b.display_title())),
+        LibrarySort::Duration => a.duration.total_cmp(&b.duration),
+        LibrarySort::Added => b.added.cmp(&a.added).then_with(|| a.path.cmp(&b.path)),
+        LibrarySort::Path => a.path.cmp(&b.path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::metadata::AudioMetadata;
+
+    fn track(
+        path: &str,
+        artist: Option<&str>,
+        album: Option<&str>,
+        genre: Option<&str>,
+    ) -> LibraryTrack {
+        let metadata = AudioMetadata {
+            title: Some(path.to_string()),
+            author: artist.map(str::to_string),
+            album: album.map(str::to_string),
+            genre: genre.map(str::to_string),
+            duration: 100.0,
+            ..Default::default()
+        };
+        LibraryTrack::from_metadata(PathBuf::from(path), 0, 0, 0, &metadata)
+    }
+
+    fn library() -> LibraryIndex {
+        let mut index = LibraryIndex::default();
+        index.insert(track(
+            "/m/a1.flac",
+            Some("Alpha"),
+            Some("First"),
+            Some("Rock; Pop"),
+        ));
+        index.insert(track(
+            "/m/a2.flac",
+            Some("alpha"),
+            Some("First"),
+            Some("Rock"),
+        ));
+        index.insert(track(
+            "/m/a3.flac",
+            Some("Alpha"),
+            Some("Second"),
+            Some("Jazz"),
+        ));
+        index.insert(track(
+            "/m/b1.flac",
+            Some("Beta"),
+            Some("First"),
+            Some("pop"),
+        ));
+        index.insert(track("/m/untagged.flac", None, None, None));
+        let mut compilation = track("/m/c1.flac", Some("Gamma"), Some("Hits"), None);
+        compilation.album_artist = Some("Various".to_string());
+        index.insert(compilation);
+        index
+    }
+
+    fn paths(tracks: Vec<&LibraryTrack>) -> Vec<String> {
+        tracks
+            .into_iter()
+            .map(|t| t.path.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn query_filters_by_artist_case_insensitively() {
+        let index = library();
+        let found = index.query(&LibraryQuery::new().artist("ALPHA").sort(LibrarySort::Path));
+        assert_eq!(paths(found), ["/m/a1.flac", "/m/a2.flac", "/m/a3.flac"]);
+
+        // Compilations are filed under their album artist
+        let found = index.query(&LibraryQuery::new().artist("Various"));
+        assert_eq!(paths(found), ["/m/c1.flac"]);
+        assert!(index.query(&LibraryQuery::new().artist("Gamma")).is_empty());
+
+        let found = index.query(&LibraryQuery::new().artist(UNKNOWN_ARTIST));
+        assert_eq!(paths(found), ["/m/untagged.flac"]);
+    }
+
+    #[test]
+    fn query_filters_by_album_and_artist_together() {
+        let index = library();
+        let found = index.query(&LibraryQuery::new().album("first").sort(LibrarySort::Path));
+        assert_eq!(paths(found), ["/m/a1.flac", "/m/a2.flac", "/m/b1.flac"]);
+
+        let found = index.query(
+            &LibraryQuery::new()
+                .artist("Alpha")
+                .album("First")
+                .sort(LibrarySort::Path),
+        );
+        assert_eq!(paths(found), ["/m/a1.flac", "/m/a2.flac"]);
+    }
+
+    #[test]
+    fn query_filters_by_each_genre_of_a_track() {
+        let index = library();
+        let found = index.query(&LibraryQuery::new().genre("POP").sort(LibrarySort::Path));
+        assert_eq!(paths(found), ["/m/a1.flac", "/m/b1.flac"]);
+        assert!(index.query(&LibraryQuery::new().genre("Ro")).is_empty());
+    }
+
+    #[test]
+    fn query_text_searches_tags_and_paths() {
+        let index = library();
+        let found = index.query(&LibraryQuery::new().text("hits"));
+        assert_eq!(paths(found), ["/m/c1.flac"]);
+        let found = index.query(&LibraryQuery::new().text("UNTAGGED"));
+        assert_eq!(paths(found), ["/m/untagged.flac"]);
+    }
+
+    #[test]
+    fn artists_albums_and_genres_are_listed_once() {
+        let index = library();
+        assert_eq!(
+            index.artists(),
+            ["Alpha", "Beta", UNKNOWN_ARTIST, "Various"]
+        );
+        assert_eq!(index.genres(), ["Jazz", "Pop", "Rock"]);
+
+        let albums = index.albums(Some("alpha"));
+        assert_eq!(albums.len(), 2);
+        assert_eq!(albums[0].album, "First");
+        assert_eq!(albums[0].track_count, 2);
+        assert_eq!(albums[0].duration, 200.0);
+        assert_eq!(albums[1].album, "Second");
+    }
+}
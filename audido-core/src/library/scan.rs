@@ -0,0 +1,303 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{LibraryIndex, LibraryTrack};
+use crate::{
+    analysis::{self, cache::AnalysisCache},
+    formats::FormatConfig,
+    source::AudioPlaybackData,
+};
+
+/// What a rescan changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+pub(super) fn rescan(
+    index: &mut LibraryIndex,
+    roots: &[PathBuf],
+    formats: &FormatConfig,
+) -> ScanStats {
+    let mut stats = ScanStats::default();
+    let now = unix_seconds(SystemTime::now());
+
+    // Roots that aren't there (e.g. an unmounted drive) keep their tracks
+    let available: Vec<&PathBuf> = roots.iter().filter(|root| root.is_dir()).collect();
+    for root in roots.iter().filter(|root| !root.is_dir()) {
+        log::warn!("Library root {} is not available", root.display());
+    }
+
+    let mut files = Vec::new();
+    let mut visited_dirs = HashSet::new();
+    for root in &available {
+        collect_audio_files(root, formats, &mut files, &mut visited_dirs);
+    }
+
+    let cache = AnalysisCache::open()
+        .inspect_err(|e| log::warn!("Analysis cache unavailable: {}", e))
+        .ok();
+    let mut seen = HashSet::with_capacity(files.len());
+    for path in files {
+        let Ok(file_meta) = fs::metadata(&path) else {
+            continue;
+        };
+        let mtime = file_meta.modified().map(unix_seconds).unwrap_or(0);
+        let size = file_meta.len();
+        seen.insert(path.clone());
+
+        let previous = index.get(&path);
+        if previous.is_some_and(|track| track.mtime == mtime && track.size == size) {
+            stats.unchanged += 1;
+            continue;
+        }
+
+        let Some(path_str) = path.to_str() else {
+            log::warn!("Skipping non UTF-8 path {}", path.display());
+            continue;
+        };
+        let metadata = AudioPlaybackData::read_tags(path_str);
+        let mut track = match previous {
+            Some(previous) => {
+                stats.updated += 1;
+                let mut track = LibraryTrack::from_metadata(
+                    path.clone(),
+                    mtime,
+                    size,
+                    previous.added,
+                    &metadata,
+                );
+                // Tags don't carry analysis results, keep the ones we computed
+                track.bpm = track.bpm.or(previous.bpm);
+                track.key = track.key.or(previous.key);
+                track
+            }
+            None => {
+                stats.added += 1;
+                LibraryTrack::from_metadata(path.clone(), mtime, size, now, &metadata)
+            }
+        };
+        // Tracks analysed while playing, before they were in the library
+        if (track.bpm.is_none() || track.key.is_none())
+            && let Some(cached) = cache
+                .as_ref()
+                .and_then(|cache| analysis::cached_analysis(cache, &path))
+        {
+            track.bpm = track.bpm.or(cached.bpm);
+            track.key = track.key.or(cached.key);
+        }
+        index.insert(track);
+    }
+
+    let before = index.len();
+    index.retain(|track| {
+        let under_root = |root: &&PathBuf| track.path.starts_with(root);
+        if available.iter().any(under_root) {
+            seen.contains(&track.path)
+        } else {
+            // Keep tracks of unavailable roots, drop those of roots no longer configured
+            roots.iter().any(|root| track.path.starts_with(root))
+        }
+    });
+    stats.removed = before - index.len();
+
+    stats
+}
+
+/// Recursively collect audio files, skipping hidden entries and directories
+/// already visited through a symlink
+fn collect_audio_files(
+    dir: &Path,
+    formats: &FormatConfig,
+    files: &mut Vec<PathBuf>,
+    visited: &mut HashSet<PathBuf>,
+) {
+    let canonical = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    if !visited.insert(canonical) {
+        return;
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Cannot read {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_audio_files(&path, formats, files, visited);
+        } else if formats.is_audio(&path) {
+            files.push(path);
+        }
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, time::Duration};
+
+    use super::*;
+    use crate::testing::wav;
+
+    fn library_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("audido-scan-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("Album")).unwrap();
+        dir
+    }
+
+    /// Write a WAV file with a fixed modification time
+    fn write_track(path: &Path, sample_rate: u32, samples: usize, mtime: u64) {
+        fs::write(path, wav(sample_rate, samples)).unwrap();
+        set_mtime(path, mtime);
+    }
+
+    fn set_mtime(path: &Path, mtime: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(mtime))
+            .unwrap();
+    }
+
+    fn scan(index: &mut LibraryIndex, root: &Path) -> ScanStats {
+        rescan(index, &[root.to_path_buf()], &FormatConfig::default())
+    }
+
+    #[test]
+    fn rescan_skips_files_whose_mtime_and_size_are_unchanged() {
+        let root = library_dir("unchanged");
+        let track = root.join("Album/01.wav");
+        write_track(&track, 44100, 100, 1_000);
+        write_track(&root.join("02.wav"), 44100, 100, 1_000);
+
+        let mut index = LibraryIndex::default();
+        let stats = scan(&mut index, &root);
+        assert_eq!(stats.added, 2);
+        assert_eq!(index.get(&track).unwrap().sample_rate, 44100);
+
+        // Same size and mtime: the file isn't read again, so the new rate goes unnoticed
+        write_track(&track, 48000, 100, 1_000);
+        let stats = scan(&mut index, &root);
+        assert_eq!(
+            stats,
+            ScanStats {
+                unchanged: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(index.get(&track).unwrap().sample_rate, 44100);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rescan_rereads_files_whose_mtime_or_size_changed() {
+        let root = library_dir("changed");
+        let by_mtime = root.join("Album/01.wav");
+        let by_size = root.join("Album/02.wav");
+        write_track(&by_mtime, 44100, 100, 1_000);
+        write_track(&by_size, 44100, 100, 1_000);
+
+        let mut index = LibraryIndex::default();
+        scan(&mut index, &root);
+        let added = index.get(&by_mtime).unwrap().added;
+        index.record_analysis(&by_mtime, Some(128.0), None);
+
+        write_track(&by_mtime, 48000, 100, 2_000);
+        write_track(&by_size, 48000, 200, 1_000);
+        let stats = scan(&mut index, &root);
+        assert_eq!(stats.updated, 2);
+        assert_eq!(stats.unchanged, 0);
+
+        let track = index.get(&by_mtime).unwrap();
+        assert_eq!(track.sample_rate, 48000);
+        assert_eq!(track.mtime, 2_000);
+        assert_eq!(track.added, added);
+        // Analysis results survive a re-read of the tags
+        assert_eq!(track.bpm, Some(128.0));
+        assert_eq!(index.get(&by_size).unwrap().sample_rate, 48000);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rescan_drops_removed_files() {
+        let root = library_dir("removed");
+        let kept = root.join("Album/01.wav");
+        let removed = root.join("Album/02.wav");
+        write_track(&kept, 44100, 100, 1_000);
+        write_track(&removed, 44100, 100, 1_000);
+
+        let mut index = LibraryIndex::default();
+        scan(&mut index, &root);
+        fs::remove_file(&removed).unwrap();
+
+        let stats = scan(&mut index, &root);
+        assert_eq!(stats.removed, 1);
+        assert_eq!(stats.unchanged, 1);
+        assert!(index.get(&removed).is_none());
+        assert!(index.get(&kept).is_some());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rescan_keeps_tracks_of_an_unavailable_root() {
+        let root = library_dir("unavailable");
+        let track = root.join("Album/01.wav");
+        write_track(&track, 44100, 100, 1_000);
+
+        let mut index = LibraryIndex::default();
+        scan(&mut index, &root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let stats = scan(&mut index, &root);
+        assert_eq!(stats.removed, 0);
+        assert!(index.get(&track).is_some());
+
+        // A root that is no longer configured loses its tracks
+        let stats = rescan(&mut index, &[], &FormatConfig::default());
+        assert_eq!(stats.removed, 1);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn rescan_ignores_hidden_and_non_audio_files() {
+        let root = library_dir("hidden");
+        write_track(&root.join("Album/01.wav"), 44100, 100, 1_000);
+        write_track(&root.join(".hidden.wav"), 44100, 100, 1_000);
+        fs::write(root.join("Album/notes.txt"), "liner notes").unwrap();
+
+        let mut index = LibraryIndex::default();
+        let stats = scan(&mut index, &root);
+        assert_eq!(stats.added, 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
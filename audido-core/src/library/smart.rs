@@ -0,0 +1,327 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{LibraryIndex, LibraryQuery, LibrarySort, LibraryTrack};
+use crate::{
+    config,
+    metadata::{AudioMetadata, MusicalSongKey, split_genres},
+};
+
+/// File (inside the config directory) the smart playlists are read from
+pub const SMART_PLAYLISTS_FILE: &str = "smart_playlists.toml";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Error, Debug)]
+pub enum SmartPlaylistError {
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    #[error("invalid smart playlists: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("playlist '{playlist}': {message}")]
+    InvalidKey { playlist: String, message: String },
+}
+
+/// One entry of the smart playlists file
+#[derive(Debug, Deserialize)]
+struct PlaylistEntry {
+    name: String,
+    genre: Option<String>,
+    artist: Option<String>,
+    bpm_min: Option<f32>,
+    bpm_max: Option<f32>,
+    /// Camelot codes ("8A") or key names ("Am")
+    #[serde(default)]
+    keys: Vec<String>,
+    /// Seconds
+    duration_min: Option<f32>,
+    /// Seconds
+    duration_max: Option<f32>,
+    added_within_days: Option<u64>,
+    #[serde(default)]
+    sort: LibrarySort,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PlaylistsFile {
+    #[serde(default, rename = "playlist")]
+    playlists: Vec<PlaylistEntry>,
+}
+
+/// A saved rule-based playlist. Every condition that is set has to match; its
+/// tracks are worked out from the library each time, so they follow rescans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmartPlaylist {
+    pub name: String,
+    pub genre: Option<String>,
+    /// Album artist, else track artist
+    pub artist: Option<String>,
+    pub bpm_min: Option<f32>,
+    pub bpm_max: Option<f32>,
+    /// Accepted keys, empty for any
+    pub keys: Vec<MusicalSongKey>,
+    pub duration_min: Option<f32>,
+    pub duration_max: Option<f32>,
+    pub added_within_days: Option<u64>,
+    pub sort: LibrarySort,
+    /// Keep only the first tracks after sorting
+    pub limit: Option<usize>,
+}
+
+impl SmartPlaylist {
+    /// Check the conditions that come from the track's tags and analysis
+    pub fn matches_metadata(&self, metadata: &AudioMetadata) -> bool {
+        if let Some(genre) = &self.genre {
+            let matched = metadata
+                .genre
+                .as_deref()
+                .is_some_and(|genres| split_genres(genres).any(|g| g.eq_ignore_ascii_case(genre)));
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(artist) = &self.artist {
+            let track_artist = metadata
+                .album_artist
+                .as_deref()
+                .or(metadata.author.as_deref());
+            if !track_artist.is_some_and(|a| a.trim().eq_ignore_ascii_case(artist)) {
+                return false;
+            }
+        }
+
+        // Unanalysed tracks don't match tempo or key conditions
+        if self.bpm_min.is_some() || self.bpm_max.is_some() {
+            let Some(bpm) = metadata.bpm else {
+                return false;
+            };
+            if self.bpm_min.is_some_and(|min| bpm < min)
+                || self.bpm_max.is_some_and(|max| bpm > max)
+            {
+                return false;
+            }
+        }
+
+        if !self.keys.is_empty() && !metadata.key.is_some_and(|key| self.keys.contains(&key)) {
+            return false;
+        }
+
+        if self.duration_min.is_some_and(|min| metadata.duration < min)
+            || self.duration_max.is_some_and(|max| metadata.duration > max)
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// Check all conditions against an indexed track, `now` in seconds since the Unix epoch
+    pub fn matches(&self, track: &LibraryTrack, now: u64) -> bool {
+        if let Some(days) = self.added_within_days
+            && now.saturating_sub(track.added) > days * SECONDS_PER_DAY
+        {
+            return false;
+        }
+        self.matches_metadata(&track.to_metadata())
+    }
+
+    /// The playlist's current tracks in the library
+    pub fn tracks<'a>(&self, index: &'a LibraryIndex) -> Vec<&'a LibraryTrack> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut tracks: Vec<&LibraryTrack> = index
+            .query(&LibraryQuery::new().sort(self.sort))
+            .into_iter()
+            .filter(|track| self.matches(track, now))
+            .collect();
+        if let Some(limit) = self.limit {
+            tracks.truncate(limit);
+        }
+        tracks
+    }
+}
+
+/// All saved smart playlists, in file order
+#[derive(Debug, Clone, Default)]
+pub struct SmartPlaylists {
+    pub playlists: Vec<SmartPlaylist>,
+}
+
+impl SmartPlaylists {
+    /// Load the playlists from the config directory. A missing file means none.
+    pub fn load() -> Result<Self, SmartPlaylistError> {
+        Self::compile(config::load_toml(SMART_PLAYLISTS_FILE)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, SmartPlaylistError> {
+        Self::compile(toml::from_str(content)?)
+    }
+
+    fn compile(file: PlaylistsFile) -> Result<Self, SmartPlaylistError> {
+        let playlists = file
+            .playlists
+            .into_iter()
+            .map(compile_playlist)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { playlists })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.playlists.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SmartPlaylist> {
+        self.playlists.iter().find(|p| p.name == name)
+    }
+}
+
+fn compile_playlist(entry: PlaylistEntry) -> Result<SmartPlaylist, SmartPlaylistError> {
+    let keys = entry
+        .keys
+        .iter()
+        .map(|key| {
+            key.parse::<MusicalSongKey>()
+                .map_err(|message| SmartPlaylistError::InvalidKey {
+                    playlist: entry.name.clone(),
+                    message,
+                })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(SmartPlaylist {
+        name: entry.name,
+        genre: entry.genre,
+        artist: entry.artist,
+        bpm_min: entry.bpm_min,
+        bpm_max: entry.bpm_max,
+        keys,
+        duration_min: entry.duration_min,
+        duration_max: entry.duration_max,
+        added_within_days: entry.added_within_days,
+        sort: entry.sort,
+        limit: entry.limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn track(metadata: AudioMetadata, added: u64) -> LibraryTrack {
+        LibraryTrack::from_metadata(PathBuf::from("/music/a.flac"), 0, 0, added, &metadata)
+    }
+
+    fn analysed(genre: &str, bpm: f32, key: MusicalSongKey, duration: f32) -> AudioMetadata {
+        AudioMetadata {
+            genre: Some(genre.to_string()),
+            author: Some("Artist".to_string()),
+            bpm: Some(bpm),
+            key: Some(key),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    fn playlist(toml: &str) -> SmartPlaylist {
+        SmartPlaylists::parse(&format!("[[playlist]]\nname = \"Test\"\n{}", toml))
+            .unwrap()
+            .playlists
+            .remove(0)
+    }
+
+    #[test]
+    fn tempo_key_and_duration_rules() {
+        let rules = playlist(
+            "genre = \"electronic\"\nbpm_min = 120\nbpm_max = 130\nkeys = [\"8A\", \"C\"]\nduration_max = 360",
+        );
+        let matching = analysed("House; Electronic", 124.0, MusicalSongKey::AMin, 300.0);
+        assert!(rules.matches(&track(matching.clone(), NOW), NOW));
+
+        let cases = [
+            AudioMetadata {
+                bpm: Some(131.0),
+                ..matching.clone()
+            },
+            AudioMetadata {
+                bpm: None,
+                ..matching.clone()
+            },
+            AudioMetadata {
+                key: Some(MusicalSongKey::EMin),
+                ..matching.clone()
+            },
+            AudioMetadata {
+                key: None,
+                ..matching.clone()
+            },
+            AudioMetadata {
+                duration: 361.0,
+                ..matching.clone()
+            },
+            AudioMetadata {
+                genre: Some("Electronica".to_string()),
+                ..matching.clone()
+            },
+        ];
+        for metadata in cases {
+            assert!(
+                !rules.matches(&track(metadata.clone(), NOW), NOW),
+                "{:?}",
+                metadata
+            );
+        }
+        // Key names and Camelot codes mean the same key
+        let c_major = AudioMetadata {
+            key: Some(MusicalSongKey::CMaj),
+            ..matching
+        };
+        assert!(rules.matches(&track(c_major, NOW), NOW));
+    }
+
+    #[test]
+    fn artist_prefers_album_artist() {
+        let rules = playlist("artist = \"various artists\"");
+        let metadata = AudioMetadata {
+            author: Some("Someone".to_string()),
+            album_artist: Some("Various Artists".to_string()),
+            ..Default::default()
+        };
+        assert!(rules.matches_metadata(&metadata));
+        assert!(!rules.matches_metadata(&AudioMetadata {
+            album_artist: None,
+            ..metadata
+        }));
+    }
+
+    #[test]
+    fn added_within_days() {
+        let rules = playlist("added_within_days = 30");
+        let metadata = AudioMetadata::default();
+        assert!(rules.matches(&track(metadata.clone(), NOW - 29 * SECONDS_PER_DAY), NOW));
+        assert!(!rules.matches(&track(metadata, NOW - 31 * SECONDS_PER_DAY), NOW));
+    }
+
+    #[test]
+    fn empty_rules_match_everything() {
+        assert!(playlist("").matches(&track(AudioMetadata::default(), 0), NOW));
+    }
+
+    #[test]
+    fn invalid_keys_are_reported() {
+        let error =
+            SmartPlaylists::parse("[[playlist]]\nname = \"Bad\"\nkeys = [\"13A\"]").unwrap_err();
+        assert!(
+            matches!(error, SmartPlaylistError::InvalidKey { playlist, .. } if playlist == "Bad")
+        );
+    }
+}
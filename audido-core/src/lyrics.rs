@@ -0,0 +1,340 @@
+//! Lyrics: LRC files next to the audio file, and lyrics stored in its tags
+//! (ID3v2 `SYLT`/`USLT`, Vorbis `LYRICS`, MP4 `©lyr`).
+
+use std::{fs, path::Path};
+
+use lofty::{
+    config::ParseOptions,
+    file::{AudioFile, TaggedFileExt},
+    id3::v2::{Frame, SynchronizedTextFrame, TimestampFormat},
+    mpeg::MpegFile,
+    probe::Probe,
+    tag::ItemKey,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LyricsError {
+    #[error("cannot read lyrics: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Lyrics of a track, line by line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lyrics {
+    /// Sorted by start time when synced
+    pub lines: Vec<LyricLine>,
+    /// Whether the lines have times; plain lyrics only have text
+    pub synced: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    /// Start in seconds (0 for plain lyrics)
+    pub start: f32,
+    pub text: String,
+    /// Word timing from enhanced LRC (`<mm:ss.xx>`), empty without it
+    pub words: Vec<LyricWord>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricWord {
+    /// Start in seconds
+    pub start: f32,
+    /// The word with the spacing that follows it, as written in the line
+    pub text: String,
+}
+
+impl Lyrics {
+    /// Lyrics from text lines without times
+    pub fn plain(text: &str) -> Self {
+        Self {
+            lines: text
+                .lines()
+                .map(|line| LyricLine {
+                    start: 0.0,
+                    text: line.trim_end().to_string(),
+                    words: Vec::new(),
+                })
+                .collect(),
+            synced: false,
+        }
+    }
+
+    /// Index of the line being sung at `position` seconds: the last one that
+    /// started. `None` for plain lyrics and before the first line.
+    pub fn line_at(&self, position: f32) -> Option<usize> {
+        if !self.synced {
+            return None;
+        }
+        self.lines
+            .partition_point(|line| line.start <= position)
+            .checked_sub(1)
+    }
+}
+
+impl LyricLine {
+    /// How many of the line's timed words have started at `position` seconds
+    pub fn words_sung(&self, position: f32) -> usize {
+        self.words.partition_point(|word| word.start <= position)
+    }
+}
+
+/// The lyrics of an audio file: a `.lrc` file with the same name, else lyrics
+/// in its tags (synced `SYLT` first). `None` when there are none.
+pub fn load_lyrics(path: &Path) -> Result<Option<Lyrics>, LyricsError> {
+    let lrc = path.with_extension("lrc");
+    if lrc.is_file() {
+        let bytes = fs::read(&lrc)?;
+        return Ok(Some(parse_lrc(&String::from_utf8_lossy(&bytes))));
+    }
+    if let Some(lyrics) = synced_tag_lyrics(path) {
+        return Ok(Some(lyrics));
+    }
+
+    let tagged_file = match Probe::open(path).and_then(|p| p.read()) {
+        Ok(tagged_file) => tagged_file,
+        Err(_) => return Ok(None),
+    };
+    // Taggers often store a whole LRC file in the lyrics tag
+    Ok(tagged_file
+        .tags()
+        .iter()
+        .find_map(|tag| tag.get_string(&ItemKey::Lyrics))
+        .filter(|text| !text.trim().is_empty())
+        .map(parse_lrc))
+}
+
+/// Lyrics of an ID3v2 `SYLT` frame timed in milliseconds. Only MP3 files are
+/// searched; other formats rarely carry ID3v2.
+fn synced_tag_lyrics(path: &Path) -> Option<Lyrics> {
+    let is_mp3 = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"));
+    if !is_mp3 {
+        return None;
+    }
+    let mut file = fs::File::open(path).ok()?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).ok()?;
+    mpeg.id3v2()?.into_iter().find_map(|frame| {
+        let Frame::Binary(binary) = frame else {
+            return None;
+        };
+        if frame.id_str() != "SYLT" {
+            return None;
+        }
+        let sylt = SynchronizedTextFrame::parse(&binary.data, frame.flags()).ok()?;
+        if sylt.timestamp_format != TimestampFormat::MS || sylt.content.is_empty() {
+            return None;
+        }
+        let lines = sylt
+            .content
+            .into_iter()
+            .map(|(ms, text)| LyricLine {
+                start: ms as f32 / 1000.0,
+                // Entries often start with the line break that separates them
+                text: text.trim().to_string(),
+                words: Vec::new(),
+            })
+            .collect();
+        Some(sorted(lines))
+    })
+}
+
+/// Parse LRC text: `[mm:ss.xx]` line times (several per line for repeated
+/// lines), `<mm:ss.xx>` word times and the `[offset:ms]` tag. Text without any
+/// times gives plain lyrics, without the ID tags (`[ar:...]`, `[ti:...]`).
+pub fn parse_lrc(content: &str) -> Lyrics {
+    let mut lines = Vec::new();
+    let mut untimed = Vec::new();
+    // Positive offsets show the lyrics earlier
+    let mut offset = 0.0;
+
+    for raw in content.lines() {
+        let raw = raw.trim_start_matches('\u{feff}');
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+        let mut id_tags_only = true;
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some((inner, after)) = tag.split_once(']') else {
+                break;
+            };
+            if let Some(time) = parse_timestamp(inner) {
+                times.push(time);
+            } else if let Some((key, value)) = inner.split_once(':')
+                && is_id_tag(key)
+            {
+                if key.trim().eq_ignore_ascii_case("offset") {
+                    offset = value.trim().parse::<f32>().unwrap_or(0.0) / 1000.0;
+                }
+            } else {
+                id_tags_only = false;
+            }
+            rest = after;
+        }
+        // Kept for plain lyrics, unless it only holds ID tags like `[ar:Artist]`
+        let id_tag_line = id_tags_only && rest.trim().is_empty() && raw.contains('[');
+        if times.is_empty() && !id_tag_line {
+            untimed.push(raw);
+        }
+
+        let (text, words) = parse_words(rest);
+        for time in times {
+            lines.push(LyricLine {
+                start: time,
+                text: text.clone(),
+                words: words.clone(),
+            });
+        }
+    }
+
+    if lines.is_empty() {
+        return Lyrics::plain(&untimed.join("\n"));
+    }
+    for line in &mut lines {
+        line.start = (line.start - offset).max(0.0);
+        for word in &mut line.words {
+            word.start = (word.start - offset).max(0.0);
+        }
+    }
+    sorted(lines)
+}
+
+/// Whether a bracketed `key:value` is an ID tag like `ar`, `ti` or `offset`
+fn is_id_tag(key: &str) -> bool {
+    let key = key.trim();
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn sorted(mut lines: Vec<LyricLine>) -> Lyrics {
+    lines.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Lyrics {
+        lines,
+        synced: true,
+    }
+}
+
+/// Split a line of enhanced LRC into its text and timed words
+fn parse_words(line: &str) -> (String, Vec<LyricWord>) {
+    let mut text = String::new();
+    let mut words: Vec<LyricWord> = Vec::new();
+    let mut rest = line;
+
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|i| open + i) else {
+            break;
+        };
+        let Some(time) = parse_timestamp(&rest[open + 1..close]) else {
+            // Not a time, keep it as text
+            text.push_str(&rest[..=close]);
+            if let Some(word) = words.last_mut() {
+                word.text.push_str(&rest[..=close]);
+            }
+            rest = &rest[close + 1..];
+            continue;
+        };
+        let before = &rest[..open];
+        text.push_str(before);
+        if let Some(word) = words.last_mut() {
+            word.text.push_str(before);
+        }
+        words.push(LyricWord {
+            start: time,
+            text: String::new(),
+        });
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+    if let Some(word) = words.last_mut() {
+        word.text.push_str(rest);
+    }
+    // A trailing time only marks where the last word ends
+    words.retain(|word| !word.text.is_empty());
+    (text.trim().to_string(), words)
+}
+
+/// Parse `mm:ss`, `mm:ss.xx`, `mm:ss.xxx` or `mm:ss:xx` into seconds
+fn parse_timestamp(time: &str) -> Option<f32> {
+    let (minutes, seconds) = time.trim().split_once(':')?;
+    let minutes: u32 = minutes.parse().ok()?;
+    // Some files use a colon before the hundredths
+    let seconds = match seconds.split_once(':') {
+        Some((whole, fraction)) => format!("{}.{}", whole, fraction),
+        None => seconds.to_string(),
+    };
+    let seconds: f32 = seconds.parse().ok()?;
+    (seconds >= 0.0 && seconds.is_finite()).then_some(minutes as f32 * 60.0 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts_and_texts(lyrics: &Lyrics) -> Vec<(f32, &str)> {
+        lyrics
+            .lines
+            .iter()
+            .map(|line| (line.start, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_timestamp_formats() {
+        assert_eq!(parse_timestamp("01:02"), Some(62.0));
+        assert_eq!(parse_timestamp("01:02.50"), Some(62.5));
+        assert_eq!(parse_timestamp("1:02.250"), Some(62.25));
+        assert_eq!(parse_timestamp("01:02:50"), Some(62.5));
+        assert_eq!(parse_timestamp(" 00:00.00 "), Some(0.0));
+        for invalid in ["", "01", "ar:Artist", "-1:00", "01:-5", "01:xx.5", "01:inf"] {
+            assert_eq!(parse_timestamp(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn repeated_timestamps_give_one_line_each() {
+        let lyrics = parse_lrc("[00:20.00][00:05.00]Chorus\n[00:10.00]Verse\n");
+        assert!(lyrics.synced);
+        assert_eq!(
+            starts_and_texts(&lyrics),
+            [(5.0, "Chorus"), (10.0, "Verse"), (20.0, "Chorus")]
+        );
+        assert_eq!(lyrics.line_at(4.0), None);
+        assert_eq!(lyrics.line_at(12.0), Some(1));
+    }
+
+    #[test]
+    fn offset_moves_lines_and_words() {
+        let earlier = parse_lrc("[offset:+500]\n[00:10.00]<00:10.00>Hi\n[00:00.20]Intro");
+        assert_eq!(starts_and_texts(&earlier), [(0.0, "Intro"), (9.5, "Hi")]);
+        assert_eq!(earlier.lines[1].words[0].start, 9.5);
+
+        let later = parse_lrc("[00:10.00]Hi\n[offset:-250]");
+        assert_eq!(starts_and_texts(&later), [(10.25, "Hi")]);
+    }
+
+    #[test]
+    fn enhanced_word_times() {
+        let lyrics = parse_lrc("[00:01.00]<00:01.00>Hello <00:01:50>big <b>world<00:02.00>");
+        let line = &lyrics.lines[0];
+        assert_eq!(line.text, "Hello big <b>world");
+        let words: Vec<(f32, &str)> = line
+            .words
+            .iter()
+            .map(|word| (word.start, word.text.as_str()))
+            .collect();
+        assert_eq!(words, [(1.0, "Hello "), (1.5, "big <b>world")]);
+        assert_eq!(line.words_sung(1.2), 1);
+    }
+
+    #[test]
+    fn untimed_text_is_plain_without_id_tags() {
+        let lyrics = parse_lrc(
+            "\u{feff}[ar:Someone]\n[ti:Song][al:Album]\nFirst line\n\n[Chorus]\nSecond line\n",
+        );
+        assert!(!lyrics.synced);
+        let texts: Vec<&str> = lyrics.lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, ["First line", "", "[Chorus]", "Second line"]);
+        assert_eq!(lyrics.line_at(10.0), None);
+    }
+}
@@ -1,4 +1,6 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use crate::lyrics::Lyrics;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChannelLayout {
@@ -28,7 +30,7 @@ impl ChannelLayout {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum MusicalSongKey {
     CMaj,
     CMin,
@@ -94,6 +96,126 @@ impl MusicalSongKey {
     }
 }
 
+impl MusicalSongKey {
+    /// Pitch class of the tonic, C = 0
+    pub fn semitone(&self) -> u8 {
+        use MusicalSongKey::*;
+        match self {
+            CMaj | CMin => 0,
+            CSharpMaj | CSharpMin => 1,
+            DMaj | DMin => 2,
+            DSharpMaj | DSharpMin => 3,
+            EMaj | EMin => 4,
+            FMaj | FMin => 5,
+            FSharpMaj | FSharpMin => 6,
+            GMaj | GMin => 7,
+            GSharpMaj | GSharpMin => 8,
+            AMaj | AMin => 9,
+            ASharpMaj | ASharpMin => 10,
+            BMaj | BMin => 11,
+        }
+    }
+
+    pub fn is_minor(&self) -> bool {
+        use MusicalSongKey::*;
+        matches!(
+            self,
+            CMin | CSharpMin
+                | DMin
+                | DSharpMin
+                | EMin
+                | FMin
+                | FSharpMin
+                | GMin
+                | GSharpMin
+                | AMin
+                | ASharpMin
+                | BMin
+        )
+    }
+
+    /// Position on the Camelot wheel: hour 1-12 and A (minor) or B (major)
+    pub fn camelot(&self) -> (u8, char) {
+        // Each step of a fifth moves one hour; C major sits at 8B, A minor at 8A
+        let offset = if self.is_minor() { 5 } else { 8 };
+        let hour = (self.semitone() as u32 * 7 + offset) % 12;
+        let hour = if hour == 0 { 12 } else { hour as u8 };
+        (hour, if self.is_minor() { 'A' } else { 'B' })
+    }
+
+    /// Camelot notation, e.g. "8A"
+    pub fn camelot_code(&self) -> String {
+        let (hour, letter) = self.camelot();
+        format!("{}{}", hour, letter)
+    }
+
+    pub fn from_camelot(hour: u8, letter: char) -> Option<MusicalSongKey> {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        let minor = match letter.to_ascii_uppercase() {
+            'A' => true,
+            'B' => false,
+            _ => return None,
+        };
+        // Invert `camelot`: 7 is its own inverse modulo 12
+        let offset = if minor { 5 } else { 8 };
+        let semitone = ((hour as u32 + 12 - offset) * 7 % 12) as u8;
+        if minor {
+            Self::from_minor(semitone)
+        } else {
+            Self::from_major(semitone)
+        }
+    }
+}
+
+impl FromStr for MusicalSongKey {
+    type Err = String;
+
+    /// Parse Camelot notation ("8A") or a key name ("Am", "C#", "Bb minor")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || format!("unknown key '{}'", s);
+
+        if let Some(letter) = s.chars().last()
+            && let Ok(hour) = s[..s.len() - letter.len_utf8()].parse::<u8>()
+        {
+            return Self::from_camelot(hour, letter).ok_or_else(invalid);
+        }
+
+        let mut chars = s.chars();
+        let natural = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => 0,
+            Some('D') => 2,
+            Some('E') => 4,
+            Some('F') => 5,
+            Some('G') => 7,
+            Some('A') => 9,
+            Some('B') => 11,
+            _ => return Err(invalid()),
+        };
+        let rest = chars.as_str();
+        let (semitone, rest) = if let Some(rest) = rest.strip_prefix(['#', '♯']) {
+            ((natural + 1) % 12, rest)
+        } else if let Some(rest) = rest.strip_prefix(['b', '♭']) {
+            ((natural + 11) % 12, rest)
+        } else {
+            (natural, rest)
+        };
+        let minor = match rest.trim().to_ascii_lowercase().as_str() {
+            "" | "maj" | "major" => false,
+            "m" | "min" | "minor" => true,
+            _ => return Err(invalid()),
+        };
+        let key = if minor {
+            Self::from_minor(semitone)
+        } else {
+            Self::from_major(semitone)
+        };
+        key.ok_or_else(invalid)
+    }
+}
+
 impl Display for MusicalSongKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let label = match self {
@@ -137,6 +259,14 @@ pub struct AudioMetadata {
     pub num_channels: u16,
     /// Channel layout (Mono or Stereo)
     pub channel_layout: ChannelLayout,
+    /// Codec the audio is encoded with (e.g. "flac", "mp3", "aac")
+    pub codec: Option<String>,
+    /// Bits per sample, for PCM and lossless codecs
+    pub bit_depth: Option<u8>,
+    /// Audio bitrate in kbps
+    pub bitrate: Option<u32>,
+    /// Size of the file in bytes
+    pub file_size: Option<u64>,
     /// Path to the audio sound file
     pub full_file_path: String,
     /// Audio title (if any)
@@ -153,14 +283,36 @@ pub struct AudioMetadata {
     pub duration: f32,
     /// Album of the music (if provided any)
     pub album: Option<String>,
+    /// Album artist (if provided any), may differ from the track author on compilations
+    pub album_artist: Option<String>,
+    /// Position of the track on its disc
+    pub track_number: Option<u32>,
+    /// Disc of a multi-disc release the track is on
+    pub disc_number: Option<u32>,
+    /// Release year
+    pub year: Option<u32>,
+    pub composer: Option<String>,
+    pub comment: Option<String>,
     /// Audio danceability (computed internally)
     pub danceability: Option<f32>,
     /// Audio acousticness (computed internally)
     pub acousticness: Option<f32>,
 
     pub electronicness: Option<f32>,
-    // Add more in the future (optional)
-    // pub lyric: Option<LyricData> // LyricData store lyrics and each part's timestamp
+    /// Integrated loudness in LUFS (from ReplayGain tags or computed internally)
+    pub loudness: Option<f32>,
+    /// Sample peak, 1.0 is full scale
+    pub peak: Option<f32>,
+    /// Lyrics, synced when they come with times (read for the loaded track only)
+    pub lyric: Option<Arc<Lyrics>>,
+}
+
+/// Split a genre tag holding several genres, e.g. "Rock; Indie" or "Rock/Indie"
+pub fn split_genres(genres: &str) -> impl Iterator<Item = &str> {
+    genres
+        .split([';', '/', ','])
+        .map(str::trim)
+        .filter(|g| !g.is_empty())
 }
 
 impl Default for AudioMetadata {
@@ -170,6 +322,10 @@ impl Default for AudioMetadata {
             sample_rate: 0,
             num_channels: 0,
             channel_layout: ChannelLayout::Unsupported,
+            codec: None,
+            bit_depth: None,
+            bitrate: None,
+            file_size: None,
             full_file_path: String::new(),
             title: None,
             author: None,
@@ -178,13 +334,71 @@ impl Default for AudioMetadata {
             key: None,
             duration: 0.0,
             album: None,
+            album_artist: None,
+            track_number: None,
+            disc_number: None,
+            year: None,
+            composer: None,
+            comment: None,
             danceability: None,
             acousticness: None,
             electronicness: None,
+            loudness: None,
+            peak: None,
+            lyric: None,
         }
     }
 }
 
+impl AudioMetadata {
+    /// Take the descriptive tags this metadata lacks from `known`, e.g. a
+    /// playlist's titles or a remote page's info for a file without tags
+    pub fn fill_missing_tags(&mut self, known: &AudioMetadata) {
+        for (field, known) in [
+            (&mut self.title, &known.title),
+            (&mut self.author, &known.author),
+            (&mut self.genre, &known.genre),
+            (&mut self.album, &known.album),
+            (&mut self.album_artist, &known.album_artist),
+            (&mut self.composer, &known.composer),
+            (&mut self.comment, &known.comment),
+        ] {
+            if field.is_none() {
+                field.clone_from(known);
+            }
+        }
+        for (field, known) in [
+            (&mut self.track_number, known.track_number),
+            (&mut self.disc_number, known.disc_number),
+            (&mut self.year, known.year),
+        ] {
+            *field = field.or(known);
+        }
+    }
+
+    /// Put a CUE track's own title, performer, number and length over the
+    /// tags of the album file it is part of
+    pub fn apply_track_tags(&mut self, track: &AudioMetadata) {
+        if track.title.is_some() {
+            self.title.clone_from(&track.title);
+        }
+        if track.author.is_some() {
+            self.author.clone_from(&track.author);
+        }
+        if track.track_number.is_some() {
+            self.track_number = track.track_number;
+        }
+        self.fill_missing_tags(track);
+        self.duration = track.duration;
+        // The file's lyrics are timed for the whole album, and its key, tempo
+        // and loudness (tagged or analysed) are the album's too
+        self.lyric.clone_from(&track.lyric);
+        self.key = track.key;
+        self.bpm = track.bpm;
+        self.loudness = track.loudness;
+    }
+}
+
 impl Display for AudioMetadata {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mins = (self.duration / 60.0).floor() as u64;
@@ -219,3 +433,89 @@ impl Display for AudioMetadata {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camelot_round_trip() {
+        for semitone in 0..12 {
+            for key in [
+                MusicalSongKey::from_major(semitone).unwrap(),
+                MusicalSongKey::from_minor(semitone).unwrap(),
+            ] {
+                let (hour, letter) = key.camelot();
+                assert_eq!(MusicalSongKey::from_camelot(hour, letter), Some(key));
+                assert_eq!(key.camelot_code().parse::<MusicalSongKey>(), Ok(key));
+            }
+        }
+    }
+
+    #[test]
+    fn camelot_wheel_positions() {
+        let cases = [
+            (MusicalSongKey::AMin, "8A"),
+            (MusicalSongKey::CMaj, "8B"),
+            (MusicalSongKey::EMin, "9A"),
+            (MusicalSongKey::GMaj, "9B"),
+            (MusicalSongKey::DMin, "7A"),
+            (MusicalSongKey::FMaj, "7B"),
+            (MusicalSongKey::ASharpMin, "3A"),
+            (MusicalSongKey::GSharpMin, "1A"),
+            (MusicalSongKey::BMaj, "1B"),
+            (MusicalSongKey::CSharpMin, "12A"),
+            (MusicalSongKey::EMaj, "12B"),
+        ];
+        for (key, code) in cases {
+            assert_eq!(key.camelot_code(), code, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn parse_key_names() {
+        let cases = [
+            ("Am", MusicalSongKey::AMin),
+            ("8a", MusicalSongKey::AMin),
+            (" 12B ", MusicalSongKey::EMaj),
+            ("C", MusicalSongKey::CMaj),
+            ("C#", MusicalSongKey::CSharpMaj),
+            ("Db", MusicalSongKey::CSharpMaj),
+            ("Bb minor", MusicalSongKey::ASharpMin),
+            ("B♭m", MusicalSongKey::ASharpMin),
+            ("F# major", MusicalSongKey::FSharpMaj),
+            ("Cb", MusicalSongKey::BMaj),
+            ("e min", MusicalSongKey::EMin),
+        ];
+        for (name, key) in cases {
+            assert_eq!(name.parse::<MusicalSongKey>(), Ok(key), "{}", name);
+        }
+        for invalid in ["", "H", "13A", "0B", "8C", "Am7", "C dorian"] {
+            assert!(invalid.parse::<MusicalSongKey>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn track_tags_drop_the_album_analysis() {
+        let mut album = AudioMetadata {
+            title: Some("Live Album".to_string()),
+            album: Some("Live Album".to_string()),
+            key: Some(MusicalSongKey::AMin),
+            bpm: Some(124.0),
+            loudness: Some(-9.0),
+            duration: 3600.0,
+            ..Default::default()
+        };
+        let track = AudioMetadata {
+            title: Some("Opener".to_string()),
+            duration: 240.0,
+            ..Default::default()
+        };
+        album.apply_track_tags(&track);
+
+        assert_eq!(album.title.as_deref(), Some("Opener"));
+        assert_eq!(album.album.as_deref(), Some("Live Album"));
+        assert_eq!(album.duration, 240.0);
+        assert_eq!((album.key, album.bpm, album.loudness), (None, None, None));
+    }
+}
@@ -0,0 +1,667 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use quick_xml::{Reader, events::Event};
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    cue::{self, CueError, TrackRange},
+    metadata::AudioMetadata,
+    queue::QueueItem,
+};
+
+/// File extensions recognised as playlists
+pub const PLAYLIST_EXTENSIONS: &[&str] = &["m3u", "m3u8", "pls", "xspf", "cue"];
+
+#[derive(Error, Debug)]
+pub enum PlaylistError {
+    #[error("cannot access playlist: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unsupported playlist format: {0}")]
+    UnsupportedFormat(String),
+    #[error("invalid XSPF playlist: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error(transparent)]
+    Cue(#[from] CueError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// Extended M3U, also used for `.m3u8`
+    M3u,
+    Pls,
+    Xspf,
+    /// CUE sheet splitting album files into tracks; read only
+    Cue,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u),
+            "pls" => Some(PlaylistFormat::Pls),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            "cue" => Some(PlaylistFormat::Cue),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the path looks like a playlist file
+pub fn is_playlist(path: &Path) -> bool {
+    PlaylistFormat::from_path(path).is_some()
+}
+
+/// One track of a playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistEntry {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// Duration in seconds, if the playlist records it
+    pub duration: Option<f32>,
+    /// Part of the file the entry plays, for tracks of a CUE sheet
+    pub range: Option<TrackRange>,
+}
+
+impl PlaylistEntry {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            title: None,
+            artist: None,
+            duration: None,
+            range: None,
+        }
+    }
+
+    /// Partial metadata carried by the playlist, until the file itself is read
+    pub fn metadata(&self) -> Option<AudioMetadata> {
+        if self.title.is_none() && self.artist.is_none() && self.duration.is_none() {
+            return None;
+        }
+        Some(AudioMetadata {
+            full_file_path: self.path.to_string_lossy().to_string(),
+            title: self.title.clone(),
+            author: self.artist.clone(),
+            duration: self.duration.unwrap_or(0.0),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<&QueueItem> for PlaylistEntry {
+    fn from(item: &QueueItem) -> Self {
+        let metadata = item.metadata.as_ref();
+        Self {
+            // Remote tracks by their page, the cached file is this machine's own
+            path: item
+                .source_url
+                .as_ref()
+                .map_or_else(|| item.path.clone(), PathBuf::from),
+            title: metadata.and_then(|m| m.title.clone()),
+            artist: metadata.and_then(|m| m.author.clone()),
+            duration: metadata.map(|m| m.duration).filter(|d| *d > 0.0),
+            range: item.range,
+        }
+    }
+}
+
+/// Read a playlist, resolving relative entries against the playlist's directory
+pub fn load_playlist(path: &Path) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| PlaylistError::UnsupportedFormat(path.display().to_string()))?;
+    if format == PlaylistFormat::Cue {
+        return Ok(cue_entries(&cue::load(path)?));
+    }
+    let bytes = fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let entries = match format {
+        PlaylistFormat::M3u => parse_m3u(&content, base_dir),
+        PlaylistFormat::Pls => parse_pls(&content, base_dir),
+        PlaylistFormat::Xspf => parse_xspf(&content, base_dir)?,
+        PlaylistFormat::Cue => unreachable!("CUE sheets are read above"),
+    };
+    Ok(entries)
+}
+
+/// Write a playlist in the format given by the file extension. Tracks below the
+/// playlist's directory are stored relative to it (M3U and PLS only).
+pub fn save_playlist(path: &Path, entries: &[PlaylistEntry]) -> Result<(), PlaylistError> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| PlaylistError::UnsupportedFormat(path.display().to_string()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let content = match format {
+        PlaylistFormat::M3u => write_m3u(entries, base_dir),
+        PlaylistFormat::Pls => write_pls(entries, base_dir),
+        PlaylistFormat::Xspf => write_xspf(entries),
+        PlaylistFormat::Cue => {
+            return Err(PlaylistError::UnsupportedFormat(path.display().to_string()));
+        }
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Resolve a playlist location (plain path or `file://` URL) to a local path.
+/// `http(s)://` entries are kept as they are and play as streams.
+fn resolve_location(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    let location = location.trim();
+    if location.is_empty() {
+        return None;
+    }
+
+    if has_url_scheme(location)
+        && let Ok(url) = Url::parse(location)
+    {
+        return match url.scheme() {
+            "file" => url.to_file_path().ok(),
+            "http" | "https" => Some(PathBuf::from(location)),
+            _ => {
+                log::warn!("Skipping non-local playlist entry: {}", location);
+                None
+            }
+        };
+    }
+
+    let path = PathBuf::from(location);
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        Some(base_dir.join(path))
+    }
+}
+
+/// Whether a location is a URL rather than a path. Only `scheme://` and `file:`
+/// count, so relative paths with a colon ("Live: Disc 1/01.flac") and Windows
+/// drive letters stay paths.
+fn has_url_scheme(location: &str) -> bool {
+    if location
+        .get(..5)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("file:"))
+    {
+        return true;
+    }
+    location.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Resolve an XSPF location. Relative ones are URI references, percent-encoded
+/// and resolved against the playlist's directory.
+fn resolve_xspf_location(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    let location = location.trim();
+    if location.is_empty() || has_url_scheme(location) || Path::new(location).is_absolute() {
+        return resolve_location(location, base_dir);
+    }
+    let base_dir = if base_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base_dir
+    };
+    let base = Url::from_directory_path(std::path::absolute(base_dir).ok()?).ok()?;
+    // A colon in the first segment would otherwise be read as a scheme
+    let location = if first_segment_has_colon(location) {
+        format!("./{}", location)
+    } else {
+        location.to_string()
+    };
+    base.join(&location).ok()?.to_file_path().ok()
+}
+
+fn first_segment_has_colon(location: &str) -> bool {
+    location
+        .split('/')
+        .next()
+        .is_some_and(|first| first.contains(':'))
+}
+
+/// Path as written into an XSPF playlist: a `file://` URL for absolute paths and
+/// a percent-encoded relative URI for relative ones. Stream URLs stay as they are.
+fn xspf_location(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    if has_url_scheme(&raw) {
+        return raw.to_string();
+    }
+    if path.is_absolute() {
+        return Url::from_file_path(path)
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| raw.to_string());
+    }
+    // Encode the relative path as if it hung off the root, then drop the root
+    let encoded = Url::from_file_path(Path::new("/").join(path))
+        .ok()
+        .and_then(|url| url.as_str().strip_prefix("file:///").map(str::to_string))
+        .unwrap_or_else(|| raw.to_string());
+    if first_segment_has_colon(&encoded) {
+        format!("./{}", encoded)
+    } else {
+        encoded
+    }
+}
+
+/// Path as written into a playlist: relative when below `base_dir`
+fn relative_location(path: &Path, base_dir: &Path) -> String {
+    let relative = if base_dir.as_os_str().is_empty() {
+        None
+    } else {
+        path.strip_prefix(base_dir).ok()
+    };
+    relative.unwrap_or(path).to_string_lossy().to_string()
+}
+
+/// One entry per track of a CUE sheet, each covering its range of the album file
+fn cue_entries(sheet: &cue::CueSheet) -> Vec<PlaylistEntry> {
+    sheet
+        .tracks
+        .iter()
+        .map(|track| {
+            let metadata = sheet.track_metadata(track);
+            PlaylistEntry {
+                path: track.file.clone(),
+                title: metadata.title,
+                artist: metadata.author,
+                duration: track.range.duration(),
+                range: Some(track.range),
+            }
+        })
+        .collect()
+}
+
+fn parse_m3u(content: &str, base_dir: &Path) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    // #EXTINF applies to the next location line
+    let mut pending_info: Option<(Option<f32>, Option<String>, Option<String>)> = None;
+
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            pending_info = Some(parse_extinf(info));
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let Some(path) = resolve_location(line, base_dir) else {
+            pending_info = None;
+            continue;
+        };
+        let mut entry = PlaylistEntry::new(path);
+        if let Some((duration, artist, title)) = pending_info.take() {
+            entry.duration = duration;
+            entry.artist = artist;
+            entry.title = title;
+        }
+        entries.push(entry);
+    }
+
+    entries
+}
+
+/// Parse `#EXTINF:<seconds> [attributes],<artist> - <title>`
+fn parse_extinf(info: &str) -> (Option<f32>, Option<String>, Option<String>) {
+    let (head, display) = info.split_once(',').unwrap_or((info, ""));
+    let duration = head
+        .split_whitespace()
+        .next()
+        .and_then(|d| d.parse::<f32>().ok())
+        .filter(|d| *d > 0.0);
+
+    let (artist, title) = split_display_title(display);
+    (duration, artist, title)
+}
+
+/// Split an `<artist> - <title>` display string, as used by M3U and PLS
+fn split_display_title(display: &str) -> (Option<String>, Option<String>) {
+    let display = display.trim();
+    let (artist, title) = match display.split_once(" - ") {
+        Some((artist, title)) => (Some(artist.trim()), Some(title.trim())),
+        None => (None, Some(display)),
+    };
+    let non_empty = |s: Option<&str>| s.filter(|s| !s.is_empty()).map(str::to_string);
+
+    (non_empty(artist), non_empty(title))
+}
+
+fn display_title(entry: &PlaylistEntry) -> Option<String> {
+    match (&entry.artist, &entry.title) {
+        (Some(artist), Some(title)) => Some(format!("{} - {}", artist, title)),
+        (None, Some(title)) => Some(title.clone()),
+        _ => None,
+    }
+}
+
+fn write_m3u(entries: &[PlaylistEntry], base_dir: &Path) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for entry in entries {
+        let display = display_title(entry);
+        if display.is_some() || entry.duration.is_some() {
+            let duration = entry.duration.map_or(-1, |d| d.round() as i64);
+            out.push_str(&format!(
+                "#EXTINF:{},{}\n",
+                duration,
+                display.unwrap_or_default()
+            ));
+        }
+        out.push_str(&relative_location(&entry.path, base_dir));
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_pls(content: &str, base_dir: &Path) -> Vec<PlaylistEntry> {
+    // Entries are numbered (File1, Title1, Length1, ...) and may come in any order
+    let mut numbered: BTreeMap<u32, PlaylistEntry> = BTreeMap::new();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let split_at = key.find(|c: char| c.is_ascii_digit()).unwrap_or(key.len());
+        let (field, index) = key.split_at(split_at);
+        let Ok(index) = index.parse::<u32>() else {
+            continue;
+        };
+        let value = value.trim();
+
+        let entry = numbered
+            .entry(index)
+            .or_insert_with(|| PlaylistEntry::new(PathBuf::new()));
+        match field {
+            "file" => {
+                if let Some(path) = resolve_location(value, base_dir) {
+                    entry.path = path;
+                }
+            }
+            "title" => (entry.artist, entry.title) = split_display_title(value),
+            "length" => entry.duration = value.parse::<f32>().ok().filter(|d| *d > 0.0),
+            _ => {}
+        }
+    }
+
+    numbered
+        .into_values()
+        .filter(|entry| !entry.path.as_os_str().is_empty())
+        .collect()
+}
+
+fn write_pls(entries: &[PlaylistEntry], base_dir: &Path) -> String {
+    let mut out = String::from("[playlist]\n");
+    for (i, entry) in entries.iter().enumerate() {
+        let n = i + 1;
+        out.push_str(&format!(
+            "File{}={}\n",
+            n,
+            relative_location(&entry.path, base_dir)
+        ));
+        if let Some(title) = display_title(entry) {
+            out.push_str(&format!("Title{}={}\n", n, title));
+        }
+        let duration = entry.duration.map_or(-1, |d| d.round() as i64);
+        out.push_str(&format!("Length{}={}\n", n, duration));
+    }
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    out
+}
+
+fn parse_xspf(content: &str, base_dir: &Path) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+    let mut reader = Reader::from_str(content);
+    let mut entries = Vec::new();
+
+    // Names of the open elements, innermost last
+    let mut stack: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut current: Option<PlaylistEntry> = None;
+    let mut location: Option<PathBuf> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = e.local_name().into_inner().to_string();
+                if name == "track" {
+                    current = Some(PlaylistEntry::new(PathBuf::new()));
+                    location = None;
+                }
+                stack.push(name);
+                text.clear();
+            }
+            Event::Text(e) => text.push_str(&e.xml10_content()),
+            Event::CData(e) => text.push_str(&e.xml10_content()),
+            Event::GeneralRef(e) => {
+                if let Ok(Some(c)) = e.resolve_char_ref() {
+                    text.push(c);
+                } else if let Some(resolved) =
+                    quick_xml::escape::resolve_predefined_entity(&e.xml10_content())
+                {
+                    text.push_str(resolved);
+                }
+            }
+            Event::End(_) => {
+                let name = stack.pop().unwrap_or_default();
+                let in_track = stack.last().is_some_and(|parent| parent == "track");
+                let value = text.trim().to_string();
+                text.clear();
+
+                match (name.as_str(), current.as_mut()) {
+                    ("track", Some(_)) => {
+                        if let (Some(mut entry), Some(path)) = (current.take(), location.take()) {
+                            entry.path = path;
+                            entries.push(entry);
+                        }
+                    }
+                    // A track may list several locations; use the first local one
+                    ("location", Some(_)) if in_track && location.is_none() => {
+                        location = resolve_xspf_location(&value, base_dir);
+                    }
+                    ("title", Some(entry)) if in_track && !value.is_empty() => {
+                        entry.title = Some(value);
+                    }
+                    ("creator", Some(entry)) if in_track && !value.is_empty() => {
+                        entry.artist = Some(value);
+                    }
+                    ("duration", Some(entry)) if in_track => {
+                        // XSPF durations are in milliseconds
+                        entry.duration = value
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|d| *d > 0.0)
+                            .map(|ms| ms / 1000.0);
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+fn write_xspf(entries: &[PlaylistEntry]) -> String {
+    use quick_xml::escape::escape;
+
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n",
+    );
+    for entry in entries {
+        out.push_str("    <track>\n");
+        let location = xspf_location(&entry.path);
+        out.push_str(&format!(
+            "      <location>{}</location>\n",
+            escape(location.as_str())
+        ));
+        if let Some(title) = &entry.title {
+            out.push_str(&format!(
+                "      <title>{}</title>\n",
+                escape(title.as_str())
+            ));
+        }
+        if let Some(artist) = &entry.artist {
+            out.push_str(&format!(
+                "      <creator>{}</creator>\n",
+                escape(artist.as_str())
+            ));
+        }
+        if let Some(duration) = entry.duration {
+            out.push_str(&format!(
+                "      <duration>{}</duration>\n",
+                (duration * 1000.0).round() as u64
+            ));
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        path: &str,
+        artist: Option<&str>,
+        title: Option<&str>,
+        duration: Option<f32>,
+    ) -> PlaylistEntry {
+        PlaylistEntry {
+            path: PathBuf::from(path),
+            title: title.map(str::to_string),
+            artist: artist.map(str::to_string),
+            duration,
+            range: None,
+        }
+    }
+
+    fn sample_entries() -> Vec<PlaylistEntry> {
+        vec![
+            entry(
+                "/music/Artist/01 Intro.flac",
+                Some("Artist"),
+                Some("Intro"),
+                Some(61.0),
+            ),
+            entry(
+                "/music/Live: Disc 1/02 #1 Hit & More.mp3",
+                None,
+                Some("Hit"),
+                None,
+            ),
+            entry("/elsewhere/100% Pure.ogg", None, None, Some(200.0)),
+            entry("http://radio.example/stream", None, Some("Radio"), None),
+        ]
+    }
+
+    #[test]
+    fn m3u_round_trip() {
+        let base = Path::new("/music");
+        let written = write_m3u(&sample_entries(), base);
+        assert!(written.contains("\nArtist/01 Intro.flac\n"));
+        assert!(written.contains("\nLive: Disc 1/02 #1 Hit & More.mp3\n"));
+        assert_eq!(parse_m3u(&written, base), sample_entries());
+    }
+
+    #[test]
+    fn pls_round_trip() {
+        let base = Path::new("/music");
+        let written = write_pls(&sample_entries(), base);
+        assert!(written.contains("File2=Live: Disc 1/02 #1 Hit & More.mp3\n"));
+        assert_eq!(parse_pls(&written, base), sample_entries());
+    }
+
+    #[test]
+    fn xspf_round_trip() {
+        let written = write_xspf(&sample_entries());
+        assert!(written.contains(
+            "<location>file:///music/Live:%20Disc%201/02%20%231%20Hit%20&amp;%20More.mp3</location>"
+        ));
+        assert!(written.contains("<location>http://radio.example/stream</location>"));
+        assert_eq!(
+            parse_xspf(&written, Path::new("/music")).unwrap(),
+            sample_entries()
+        );
+    }
+
+    #[test]
+    fn xspf_relative_locations_are_uri_encoded() {
+        let entries = vec![entry("Live: Disc 1/100% Pure.ogg", None, None, None)];
+        let written = write_xspf(&entries);
+        assert!(written.contains("<location>./Live:%20Disc%201/100%25%20Pure.ogg</location>"));
+        assert_eq!(
+            parse_xspf(&written, Path::new("/music")).unwrap()[0].path,
+            PathBuf::from("/music/Live: Disc 1/100% Pure.ogg")
+        );
+    }
+
+    #[test]
+    fn relative_paths_with_a_colon_are_not_urls() {
+        let base = Path::new("/music");
+        assert_eq!(
+            resolve_location("Artist: Album/01.mp3", base),
+            Some(PathBuf::from("/music/Artist: Album/01.mp3"))
+        );
+        assert_eq!(
+            resolve_location("C:\\Music\\01.mp3", base),
+            Some(base.join("C:\\Music\\01.mp3"))
+        );
+        assert_eq!(
+            resolve_location("file:///music/a%20b.mp3", base),
+            Some(PathBuf::from("/music/a b.mp3"))
+        );
+        assert_eq!(
+            resolve_location("https://example.com/a.mp3", base),
+            Some(PathBuf::from("https://example.com/a.mp3"))
+        );
+        assert_eq!(resolve_location("ftp://example.com/a.mp3", base), None);
+    }
+
+    #[test]
+    fn extinf_and_pls_titles() {
+        let m3u = "#EXTM3U\n#EXTINF:123 tvg-id=\"x\",Artist - Title - Live\nsong.mp3\n#EXTINF:-1,Just Title\nother.mp3\n";
+        let entries = parse_m3u(m3u, Path::new("/base"));
+        assert_eq!(
+            entries,
+            vec![
+                entry(
+                    "/base/song.mp3",
+                    Some("Artist"),
+                    Some("Title - Live"),
+                    Some(123.0)
+                ),
+                entry("/base/other.mp3", None, Some("Just Title"), None),
+            ]
+        );
+
+        // Numbered keys may come out of order and in any case
+        let pls =
+            "[playlist]\ntitle2=Second\nFILE2=b.mp3\nFile1=a.mp3\nLength1=10\nNumberOfEntries=2\n";
+        let entries = parse_pls(pls, Path::new("/base"));
+        assert_eq!(
+            entries,
+            vec![
+                entry("/base/a.mp3", None, None, Some(10.0)),
+                entry("/base/b.mp3", None, Some("Second"), None),
+            ]
+        );
+    }
+}
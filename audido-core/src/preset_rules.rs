@@ -0,0 +1,349 @@
+use std::path::Path;
+
+use glob::Pattern;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    config,
+    dsp::{eq::EqPreset, normalization::NormalizationMode},
+    metadata::{AudioMetadata, split_genres},
+};
+
+/// File (inside the config directory) the rule table is read from
+pub const RULES_FILE: &str = "rules.toml";
+
+#[derive(Error, Debug)]
+pub enum PresetRuleError {
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    #[error("invalid rules: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("rule {rule}: invalid path pattern '{pattern}': {source}")]
+    InvalidPattern {
+        rule: usize,
+        pattern: String,
+        source: glob::PatternError,
+    },
+    #[error("rule {rule}: unknown EQ preset '{preset}'")]
+    UnknownPreset { rule: usize, preset: String },
+}
+
+/// Normalizer settings applied by a rule. Unset fields keep their current value.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NormalizerRule {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub mode: Option<NormalizationMode>,
+    pub target_level: Option<f32>,
+    pub headroom: Option<f32>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// One entry of the rule table as written in the rules file
+#[derive(Debug, Deserialize)]
+struct RuleEntry {
+    name: Option<String>,
+    genre: Option<String>,
+    path: Option<String>,
+    album: Option<String>,
+    album_artist: Option<String>,
+    eq_preset: Option<String>,
+    normalizer: Option<NormalizerRule>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleEntry>,
+}
+
+/// Maps tracks to an EQ preset and normalizer settings. Every condition that is
+/// set has to match; a rule without conditions matches every track.
+#[derive(Debug, Clone)]
+pub struct PresetRule {
+    pub name: String,
+    pub genre: Option<String>,
+    pub path: Option<Pattern>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub eq_preset: Option<EqPreset>,
+    pub normalizer: Option<NormalizerRule>,
+}
+
+impl PresetRule {
+    pub fn matches(&self, metadata: &AudioMetadata) -> bool {
+        if let Some(genre) = &self.genre {
+            let matched = metadata
+                .genre
+                .as_deref()
+                .is_some_and(|genres| split_genres(genres).any(|g| g.eq_ignore_ascii_case(genre)));
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.path
+            && !pattern.matches_path(Path::new(&metadata.full_file_path))
+        {
+            return false;
+        }
+
+        if let Some(album) = &self.album
+            && !metadata
+                .album
+                .as_deref()
+                .is_some_and(|a| a.trim().eq_ignore_ascii_case(album))
+        {
+            return false;
+        }
+
+        if let Some(album_artist) = &self.album_artist {
+            // Fall back to the track artist for files without an album artist tag
+            let artist = metadata
+                .album_artist
+                .as_deref()
+                .or(metadata.author.as_deref());
+            if !artist.is_some_and(|a| a.trim().eq_ignore_ascii_case(album_artist)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// How narrowly the rule picks tracks, from its most specific condition:
+    /// a path beats an album, an album beats an artist and an artist beats a genre
+    fn specificity(&self) -> u8 {
+        if self.path.is_some() {
+            4
+        } else if self.album.is_some() {
+            3
+        } else if self.album_artist.is_some() {
+            2
+        } else if self.genre.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// Rule table. The most specific matching rule wins, rules equally specific
+/// are taken in file order.
+#[derive(Debug, Clone, Default)]
+pub struct PresetRules {
+    pub rules: Vec<PresetRule>,
+}
+
+impl PresetRules {
+    /// Load the rule table from the config directory. A missing file is an empty table.
+    pub fn load() -> Result<Self, PresetRuleError> {
+        Self::compile(config::load_toml(RULES_FILE)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, PresetRuleError> {
+        Self::compile(toml::from_str(content)?)
+    }
+
+    fn compile(file: RulesFile) -> Result<Self, PresetRuleError> {
+        let rules = file
+            .rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| compile_rule(index + 1, entry))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Most specific rule matching the track, if any
+    pub fn find(&self, metadata: &AudioMetadata) -> Option<&PresetRule> {
+        // `max_by_key` keeps the last of equal keys, so walk the table backwards
+        // to let the earlier rule win a tie
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| rule.matches(metadata))
+            .max_by_key(|rule| rule.specificity())
+    }
+}
+
+fn compile_rule(index: usize, entry: RuleEntry) -> Result<PresetRule, PresetRuleError> {
+    let path = entry
+        .path
+        .as_deref()
+        .map(|pattern| {
+            let expanded = config::expand_home(pattern);
+            Pattern::new(&expanded.to_string_lossy()).map_err(|source| {
+                PresetRuleError::InvalidPattern {
+                    rule: index,
+                    pattern: pattern.to_string(),
+                    source,
+                }
+            })
+        })
+        .transpose()?;
+
+    let eq_preset = entry
+        .eq_preset
+        .map(|preset| {
+            preset
+                .parse::<EqPreset>()
+                .map_err(|_| PresetRuleError::UnknownPreset {
+                    rule: index,
+                    preset,
+                })
+        })
+        .transpose()?;
+
+    // Unnamed rules are labelled by what they match on
+    let name = entry.name.unwrap_or_else(|| {
+        let conditions: Vec<String> = [
+            entry.genre.as_ref().map(|g| format!("genre={}", g)),
+            entry.path.as_ref().map(|p| format!("path={}", p)),
+            entry.album.as_ref().map(|a| format!("album={}", a)),
+            entry.album_artist.as_ref().map(|a| format!("artist={}", a)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if conditions.is_empty() {
+            format!("rule {}", index)
+        } else {
+            conditions.join(", ")
+        }
+    });
+
+    Ok(PresetRule {
+        name,
+        genre: entry.genre,
+        path,
+        album: entry.album,
+        album_artist: entry.album_artist,
+        eq_preset,
+        normalizer: entry.normalizer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track() -> AudioMetadata {
+        AudioMetadata {
+            full_file_path: "/music/Artist/Album/01.flac".to_string(),
+            genre: Some("Rock; Live".to_string()),
+            album: Some("Album".to_string()),
+            author: Some("Artist".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn matched(rules: &str, metadata: &AudioMetadata) -> Option<String> {
+        PresetRules::parse(rules)
+            .unwrap()
+            .find(metadata)
+            .map(|rule| rule.name.clone())
+    }
+
+    const ALL_LEVELS: &str = r#"
+[[rule]]
+name = "genre"
+genre = "rock"
+
+[[rule]]
+name = "artist"
+album_artist = "Artist"
+
+[[rule]]
+name = "album"
+album = "Album"
+
+[[rule]]
+name = "path"
+path = "/music/Artist/**"
+"#;
+
+    #[test]
+    fn path_rule_beats_every_other_condition() {
+        assert_eq!(matched(ALL_LEVELS, &track()).as_deref(), Some("path"));
+    }
+
+    #[test]
+    fn album_beats_artist_and_artist_beats_genre() {
+        let elsewhere = AudioMetadata {
+            full_file_path: "/elsewhere/01.flac".to_string(),
+            ..track()
+        };
+        assert_eq!(matched(ALL_LEVELS, &elsewhere).as_deref(), Some("album"));
+
+        let single = AudioMetadata {
+            album: None,
+            ..elsewhere.clone()
+        };
+        assert_eq!(matched(ALL_LEVELS, &single).as_deref(), Some("artist"));
+
+        let other_artist = AudioMetadata {
+            author: Some("Someone".to_string()),
+            ..single
+        };
+        assert_eq!(matched(ALL_LEVELS, &other_artist).as_deref(), Some("genre"));
+    }
+
+    #[test]
+    fn earlier_rule_wins_between_equally_specific_rules() {
+        let rules = r#"
+[[rule]]
+name = "first"
+genre = "live"
+
+[[rule]]
+name = "second"
+genre = "rock"
+"#;
+        assert_eq!(matched(rules, &track()).as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn every_condition_of_a_rule_has_to_match() {
+        let rules = r#"
+[[rule]]
+name = "rock from elsewhere"
+genre = "rock"
+path = "/elsewhere/**"
+"#;
+        assert_eq!(matched(rules, &track()), None);
+    }
+
+    #[test]
+    fn no_rule_matches_an_unrelated_track() {
+        let unrelated = AudioMetadata {
+            full_file_path: "/podcasts/episode.mp3".to_string(),
+            genre: Some("Podcast".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(matched(ALL_LEVELS, &unrelated), None);
+    }
+
+    #[test]
+    fn unnamed_rules_are_labelled_by_their_conditions() {
+        let rules = PresetRules::parse("[[rule]]\nalbum = \"Album\"\ngenre = \"Rock\"").unwrap();
+        assert_eq!(rules.rules[0].name, "genre=Rock, album=Album");
+    }
+
+    #[test]
+    fn unknown_preset_is_reported() {
+        let err = PresetRules::parse("[[rule]]\neq_preset = \"Loud\"").unwrap_err();
+        assert!(matches!(
+            err,
+            PresetRuleError::UnknownPreset { rule: 1, .. }
+        ));
+    }
+}
@@ -1,11 +1,27 @@
-use std::path::PathBuf;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
+use rand::{Rng, seq::SliceRandom};
 use strum::EnumIter;
 
-use crate::metadata::AudioMetadata;
+use crate::{cue::TrackRange, harmonic, metadata::AudioMetadata};
 
 /// Loop/repeat mode for queue playback
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, strum::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    EnumIter,
+    strum::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum LoopMode {
     #[default]
     #[strum(serialize = "➡️ Off")]
@@ -16,6 +32,40 @@ pub enum LoopMode {
     LoopAll,
     #[strum(serialize = "🔀 Shuffle")]
     Shuffle,
+    /// Albums in random order, each album played through in queue order
+    #[strum(serialize = "💿 Album Shuffle")]
+    AlbumShuffle,
+    /// Shuffle that favors tracks not played recently
+    #[strum(serialize = "⚖️ Weighted Shuffle")]
+    WeightedShuffle,
+}
+
+impl LoopMode {
+    /// Whether playback follows the shuffle order
+    pub fn is_shuffle(&self) -> bool {
+        matches!(
+            self,
+            LoopMode::Shuffle | LoopMode::AlbumShuffle | LoopMode::WeightedShuffle
+        )
+    }
+}
+
+/// Field the queue can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, strum::Display)]
+pub enum QueueSortField {
+    #[default]
+    Title,
+    Artist,
+    Album,
+    /// Album by album, each in disc and track order
+    Track,
+    Year,
+    Composer,
+    Duration,
+    Bitrate,
+    #[strum(to_string = "File Size")]
+    FileSize,
+    Path,
 }
 
 /// A single item in the playback queue
@@ -24,6 +74,10 @@ pub struct QueueItem {
     pub id: usize,
     pub path: PathBuf,
     pub metadata: Option<AudioMetadata>,
+    /// Part of the file this item plays, for tracks of a CUE sheet
+    pub range: Option<TrackRange>,
+    /// Page a remote track was resolved from, `path` being its cached download
+    pub source_url: Option<String>,
 }
 
 /// The playback queue state
@@ -32,8 +86,13 @@ pub struct PlaybackQueue {
     pub items: Vec<QueueItem>,
     pub current_index: Option<usize>,
     pub loop_mode: LoopMode,
+    /// Play order in shuffle modes. Entries before the current track are the
+    /// shuffle history, so "previous" walks back through what was played.
     pub shuffle_order: Vec<usize>,
     next_id: usize,
+    /// Play counter value at each item's (by id) last play, for weighted shuffle
+    last_played: HashMap<usize, u64>,
+    play_counter: u64,
 }
 
 impl PlaybackQueue {
@@ -51,40 +110,183 @@ impl PlaybackQueue {
                 id,
                 path,
                 metadata: None,
+                range: None,
+                source_url: None,
             });
             ids.push(id);
         }
-        // Regenerate shuffle order when items change
-        if self.loop_mode == LoopMode::Shuffle {
-            self.reshuffle();
+        // Merge into the upcoming part of the shuffle order, leaving the history alone
+        if self.loop_mode.is_shuffle() {
+            let start = self.items.len() - ids.len();
+            self.merge_into_shuffle(start..self.items.len());
+        }
+        ids
+    }
+
+    /// Insert paths right after the current track (at the front when nothing is
+    /// playing), returns assigned IDs. In shuffle mode they also play next.
+    pub fn insert_next(&mut self, paths: Vec<PathBuf>) -> Vec<usize> {
+        let at = self.current_index.map_or(0, |i| i + 1);
+        let count = paths.len();
+        let mut ids = Vec::with_capacity(count);
+        let new_items: Vec<QueueItem> = paths
+            .into_iter()
+            .map(|path| {
+                let id = self.next_id;
+                self.next_id += 1;
+                ids.push(id);
+                QueueItem {
+                    id,
+                    path,
+                    metadata: None,
+                    range: None,
+                    source_url: None,
+                }
+            })
+            .collect();
+        self.items.splice(at..at, new_items);
+
+        if let Some(current) = self.current_index
+            && current >= at
+        {
+            self.current_index = Some(current + count);
+        }
+        if self.loop_mode.is_shuffle() {
+            for index in self.shuffle_order.iter_mut() {
+                if *index >= at {
+                    *index += count;
+                }
+            }
+            let shuffle_at = self
+                .current_index
+                .and_then(|current| self.shuffle_order.iter().position(|&i| i == current))
+                .map_or(0, |pos| pos + 1);
+            self.shuffle_order
+                .splice(shuffle_at..shuffle_at, at..at + count);
         }
+
         ids
     }
 
+    /// Position of an item by ID
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        self.items.iter().position(|item| item.id == id)
+    }
+
     /// Remove item by ID, returns true if found and removed
     pub fn remove(&mut self, id: usize) -> bool {
-        if let Some(pos) = self.items.iter().position(|item| item.id == id) {
-            self.items.remove(pos);
-            // Adjust current_index if needed
-            if let Some(idx) = self.current_index {
-                if pos < idx {
-                    self.current_index = Some(idx - 1);
-                } else if pos == idx {
-                    // Current track removed, stay at same index or clamp
-                    if self.items.is_empty() {
-                        self.current_index = None;
-                    } else if idx >= self.items.len() {
-                        self.current_index = Some(self.items.len() - 1);
+        self.remove_many(&[id]) > 0
+    }
+
+    /// Remove items by ID, returns how many were removed
+    pub fn remove_many(&mut self, ids: &[usize]) -> usize {
+        let order: Vec<usize> = (0..self.items.len())
+            .filter(|&i| !ids.contains(&self.items[i].id))
+            .collect();
+        let removed = self.items.len() - order.len();
+        if removed > 0 {
+            self.rearrange(order);
+        }
+        removed
+    }
+
+    /// Move an item to a new position, returns true if found
+    pub fn move_item(&mut self, id: usize, to: usize) -> bool {
+        let Some(from) = self.index_of(id) else {
+            return false;
+        };
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        let index = order.remove(from);
+        order.insert(to.min(order.len()), index);
+        self.rearrange(order);
+        true
+    }
+
+    /// Remove repeated tracks (same path and, for CUE tracks, same range),
+    /// keeping the current track and otherwise the first occurrence. Returns how
+    /// many items were removed.
+    pub fn dedupe(&mut self) -> usize {
+        // f32 isn't hashable, the range start's bits stand in for it
+        let key = |item: &QueueItem| (item.path.clone(), item.range.map(|r| r.start.to_bits()));
+        let mut seen = HashSet::new();
+        if let Some(item) = self.current() {
+            seen.insert(key(item));
+        }
+
+        let order: Vec<usize> = (0..self.items.len())
+            .filter(|&i| Some(i) == self.current_index || seen.insert(key(&self.items[i])))
+            .collect();
+        let removed = self.items.len() - order.len();
+        if removed > 0 {
+            self.rearrange(order);
+        }
+        removed
+    }
+
+    /// Stable sort by a metadata field. Items without the field go last.
+    pub fn sort_by(&mut self, field: QueueSortField) {
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        order.sort_by(|&a, &b| compare_items(&self.items[a], &self.items[b], field));
+        self.rearrange(order);
+    }
+
+    /// Reorder the current track and everything after it for smooth key and
+    /// tempo transitions, starting from the current track. Tracks already played
+    /// keep their place; without a current track the whole queue is ordered.
+    pub fn sort_harmonic(&mut self) {
+        let start = self.current_index.unwrap_or(0);
+        if start >= self.items.len() {
+            return;
+        }
+        let unknown = AudioMetadata::default();
+        let upcoming: Vec<&AudioMetadata> = self.items[start..]
+            .iter()
+            .map(|item| item.metadata.as_ref().unwrap_or(&unknown))
+            .collect();
+        let order: Vec<usize> = (0..start)
+            .chain(
+                harmonic::harmonic_order(&upcoming, 0)
+                    .into_iter()
+                    .map(|i| start + i),
+            )
+            .collect();
+        self.rearrange(order);
+    }
+
+    /// Rebuild the queue from old indices in their new order, dropping indices
+    /// that are left out. The current track and the shuffle order follow their items.
+    fn rearrange(&mut self, order: Vec<usize>) {
+        let mut new_index = vec![None; self.items.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = Some(new);
+        }
+
+        if let Some(current) = self.current_index {
+            self.current_index = match new_index[current] {
+                Some(index) => Some(index),
+                // Current track removed: continue with the next surviving one
+                // in play order, or stop when nothing followed it
+                None => match self.shuffle_position(current) {
+                    Some(pos) if self.loop_mode.is_shuffle() => {
+                        let (before, after) = self.shuffle_order.split_at(pos);
+                        // The shuffle order loops, like `next_index`
+                        after.iter().chain(before).find_map(|&old| new_index[old])
                     }
-                }
-            }
-            if self.loop_mode == LoopMode::Shuffle {
-                self.reshuffle();
-            }
-            true
-        } else {
-            false
+                    _ => (current..self.items.len()).find_map(|old| new_index[old]),
+                },
+            };
         }
+        self.shuffle_order = self
+            .shuffle_order
+            .iter()
+            .filter_map(|&old| new_index.get(old).copied().flatten())
+            .collect();
+
+        let mut old_items: Vec<Option<QueueItem>> = self.items.drain(..).map(Some).collect();
+        self.items = order
+            .into_iter()
+            .filter_map(|old| old_items[old].take())
+            .collect();
     }
 
     /// Clear all items from queue
@@ -92,6 +294,40 @@ impl PlaybackQueue {
         self.items.clear();
         self.current_index = None;
         self.shuffle_order.clear();
+        self.last_played.clear();
+    }
+
+    /// Make `index` the current track and record it as played. In shuffle modes a
+    /// track picked from further ahead is moved up to follow the previous one, so
+    /// the shuffle history stays in the order tracks were played.
+    pub fn set_current(&mut self, index: usize) {
+        if index >= self.items.len() {
+            return;
+        }
+
+        if self.loop_mode.is_shuffle()
+            && let Some(target) = self.shuffle_order.iter().position(|&i| i == index)
+        {
+            let current = self
+                .current_index
+                .and_then(|c| self.shuffle_order.iter().position(|&i| i == c));
+            // Nothing played yet: the picked track starts the order
+            let insert_at = current.map_or(0, |c| c + 1);
+            if target > insert_at {
+                let moved = self.shuffle_order.remove(target);
+                self.shuffle_order.insert(insert_at, moved);
+            }
+        }
+
+        // Outside shuffle the order goes stale, so don't keep it as history
+        if !self.loop_mode.is_shuffle() {
+            self.shuffle_order.clear();
+        }
+
+        self.current_index = Some(index);
+        self.play_counter += 1;
+        self.last_played
+            .insert(self.items[index].id, self.play_counter);
     }
 
     /// Get next track index based on loop mode
@@ -111,7 +347,7 @@ impl PlaybackQueue {
             }
             LoopMode::RepeatOne => Some(current),
             LoopMode::LoopAll => Some((current + 1) % self.items.len()),
-            LoopMode::Shuffle => {
+            LoopMode::Shuffle | LoopMode::AlbumShuffle | LoopMode::WeightedShuffle => {
                 // Find current position in shuffle order and advance
                 if let Some(shuffle_pos) = self.shuffle_order.iter().position(|&i| i == current) {
                     let next_shuffle_pos = (shuffle_pos + 1) % self.shuffle_order.len();
@@ -146,7 +382,7 @@ impl PlaybackQueue {
                     Some(self.items.len() - 1)
                 }
             }
-            LoopMode::Shuffle => {
+            LoopMode::Shuffle | LoopMode::AlbumShuffle | LoopMode::WeightedShuffle => {
                 if let Some(shuffle_pos) = self.shuffle_order.iter().position(|&i| i == current) {
                     let prev_shuffle_pos = if shuffle_pos > 0 {
                         shuffle_pos - 1
@@ -161,13 +397,144 @@ impl PlaybackQueue {
         }
     }
 
-    /// Generate new shuffle order using Fisher-Yates
+    /// Generate a new shuffle order for the current loop mode, starting with the
+    /// current track. Tracks played before it in the previous shuffle order stay
+    /// in front as the history, so going back still retraces them.
     pub fn reshuffle(&mut self) {
-        use rand::seq::SliceRandom;
-        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        let history: Vec<usize> = self
+            .current_index
+            .and_then(|current| self.shuffle_position(current))
+            .map(|pos| self.shuffle_order[..pos].to_vec())
+            .unwrap_or_default();
+
         let mut rng = rand::rng();
-        order.shuffle(&mut rng);
-        self.shuffle_order = order;
+        let mut order = match self.loop_mode {
+            LoopMode::AlbumShuffle => self.album_order(&mut rng),
+            LoopMode::WeightedShuffle => self.weighted_order(&mut rng, 0..self.items.len()),
+            _ => {
+                // Fisher-Yates
+                let mut order: Vec<usize> = (0..self.items.len()).collect();
+                order.shuffle(&mut rng);
+                order
+            }
+        };
+
+        if let Some(current) = self.current_index
+            && let Some(pos) = order.iter().position(|&i| i == current)
+        {
+            if self.loop_mode == LoopMode::AlbumShuffle {
+                // Lead with the rest of the current album, from the current track on
+                let album = self.album_key(current);
+                let start = order[..pos]
+                    .iter()
+                    .rposition(|&i| self.album_key(i) != album)
+                    .map_or(0, |p| p + 1);
+                let end = order[pos..]
+                    .iter()
+                    .position(|&i| self.album_key(i) != album)
+                    .map_or(order.len(), |p| pos + p);
+                let album_tracks: Vec<usize> = order.drain(start..end).collect();
+                let split = pos - start;
+                order.splice(0..0, album_tracks[split..].iter().copied());
+                order.extend_from_slice(&album_tracks[..split]);
+            } else {
+                order.remove(pos);
+                order.insert(0, current);
+            }
+        }
+
+        let played: HashSet<usize> = history.iter().copied().collect();
+        order.retain(|index| !played.contains(index));
+        self.shuffle_order = history;
+        self.shuffle_order.extend(order);
+    }
+
+    /// Position of an item index in the shuffle order
+    fn shuffle_position(&self, index: usize) -> Option<usize> {
+        self.shuffle_order.iter().position(|&i| i == index)
+    }
+
+    /// Albums in random order, tracks of an album kept in queue order
+    fn album_order(&self, rng: &mut impl Rng) -> Vec<usize> {
+        let mut albums: Vec<(String, Vec<usize>)> = Vec::new();
+        for index in 0..self.items.len() {
+            let key = self.album_key(index);
+            match albums.iter_mut().find(|(album, _)| *album == key) {
+                Some((_, tracks)) => tracks.push(index),
+                None => albums.push((key, vec![index])),
+            }
+        }
+        albums.shuffle(rng);
+        albums.into_iter().flat_map(|(_, tracks)| tracks).collect()
+    }
+
+    /// Album a track belongs to; untagged tracks are grouped by folder
+    fn album_key(&self, index: usize) -> String {
+        let item = &self.items[index];
+        let tagged = item.metadata.as_ref().and_then(|m| {
+            let album = m.album.as_deref()?;
+            let artist = m.album_artist.as_deref().or(m.author.as_deref());
+            Some(format!("{}\u{1f}{}", artist.unwrap_or_default(), album))
+        });
+        tagged.unwrap_or_else(|| {
+            item.path
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Weighted random order without replacement. A track's weight grows with the
+    /// number of plays since it was last heard; tracks never played weigh the most.
+    fn weighted_order(
+        &self,
+        rng: &mut impl Rng,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Vec<usize> {
+        let unplayed_weight = (self.items.len() as f64).max(1.0) + 1.0;
+        let mut keyed: Vec<(f64, usize)> = indices
+            .into_iter()
+            .map(|index| {
+                let weight = match self.last_played.get(&self.items[index].id) {
+                    Some(&played) => {
+                        ((self.play_counter - played) as f64 + 1.0).min(unplayed_weight - 1.0)
+                    }
+                    None => unplayed_weight,
+                };
+                // Efraimidis-Spirakis: sort by u^(1/w)
+                let u: f64 = rng.random_range(f64::EPSILON..1.0);
+                (u.powf(1.0 / weight), index)
+            })
+            .collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keyed.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Add new item indices to the shuffle order without disturbing the tracks
+    /// already played or the current one
+    fn merge_into_shuffle(&mut self, indices: std::ops::Range<usize>) {
+        let mut rng = rand::rng();
+        let upcoming_start = self
+            .current_index
+            .and_then(|c| self.shuffle_order.iter().position(|&i| i == c))
+            .map_or(0, |pos| pos + 1);
+
+        match self.loop_mode {
+            // New albums are queued after the ones already lined up
+            LoopMode::AlbumShuffle => self.shuffle_order.extend(indices),
+            LoopMode::WeightedShuffle => {
+                // Re-weigh everything upcoming together with the new tracks
+                let upcoming: Vec<usize> = self.shuffle_order.drain(upcoming_start..).collect();
+                let order = self.weighted_order(&mut rng, upcoming.into_iter().chain(indices));
+                self.shuffle_order.extend(order);
+            }
+            _ => {
+                for index in indices {
+                    let at = rng.random_range(upcoming_start..=self.shuffle_order.len());
+                    self.shuffle_order.insert(at, index);
+                }
+            }
+        }
     }
 
     /// Get current track
@@ -186,4 +553,296 @@ impl PlaybackQueue {
             item.metadata = Some(metadata);
         }
     }
+
+    /// Limit an item (by ID) to part of its file
+    pub fn set_range(&mut self, id: usize, range: TrackRange) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.range = Some(range);
+        }
+    }
+
+    /// Record the page an item (by ID) is downloaded from
+    pub fn set_source_url(&mut self, id: usize, url: String) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.source_url = Some(url);
+        }
+    }
+
+    /// Point an item (by ID) at another file, e.g. a fresh download
+    pub fn set_path(&mut self, id: usize, path: PathBuf) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.path = path;
+        }
+    }
+}
+
+fn compare_items(a: &QueueItem, b: &QueueItem, field: QueueSortField) -> Ordering {
+    fn text(item: &QueueItem, field: QueueSortField) -> Option<String> {
+        let metadata = item.metadata.as_ref();
+        let value = match field {
+            QueueSortField::Title => metadata.and_then(|m| m.title.clone()).or_else(|| {
+                item.path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+            }),
+            QueueSortField::Artist => metadata.and_then(|m| m.author.clone()),
+            QueueSortField::Album => metadata.and_then(|m| m.album.clone()),
+            QueueSortField::Composer => metadata.and_then(|m| m.composer.clone()),
+            QueueSortField::Path => Some(item.path.to_string_lossy().to_string()),
+            _ => None,
+        };
+        value.map(|v| v.to_lowercase())
+    }
+
+    fn number(item: &QueueItem, field: QueueSortField) -> Option<u64> {
+        let metadata = item.metadata.as_ref()?;
+        match field {
+            QueueSortField::Track => metadata.track_number.map(u64::from),
+            QueueSortField::Year => metadata.year.map(u64::from),
+            QueueSortField::Bitrate => metadata.bitrate.map(u64::from),
+            QueueSortField::FileSize => metadata.file_size,
+            _ => None,
+        }
+    }
+
+    // `None` sorts after every value
+    fn missing_last<T>(a: Option<T>, b: Option<T>, cmp: impl Fn(T, T) -> Ordering) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => cmp(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    match field {
+        QueueSortField::Duration => {
+            let duration = |item: &QueueItem| {
+                item.metadata
+                    .as_ref()
+                    .map(|m| m.duration)
+                    .filter(|d| *d > 0.0)
+            };
+            missing_last(duration(a), duration(b), |a, b| a.total_cmp(&b))
+        }
+        QueueSortField::Track => {
+            let disc = |item: &QueueItem| {
+                item.metadata
+                    .as_ref()
+                    .and_then(|m| m.disc_number)
+                    .unwrap_or(1)
+            };
+            let album = QueueSortField::Album;
+            missing_last(text(a, album), text(b, album), |a, b| a.cmp(&b))
+                .then_with(|| disc(a).cmp(&disc(b)))
+                .then_with(|| missing_last(number(a, field), number(b, field), |a, b| a.cmp(&b)))
+        }
+        QueueSortField::Year | QueueSortField::Bitrate | QueueSortField::FileSize => {
+            missing_last(number(a, field), number(b, field), |a, b| a.cmp(&b))
+        }
+        _ => missing_last(text(a, field), text(b, field), |a, b| a.cmp(&b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    fn queue_of(n: usize) -> PlaybackQueue {
+        let mut queue = PlaybackQueue::new();
+        queue.add(
+            (0..n)
+                .map(|i| PathBuf::from(format!("{}.mp3", i)))
+                .collect(),
+        );
+        queue
+    }
+
+    #[test]
+    fn removing_the_current_track_moves_to_the_next_one() {
+        let mut queue = queue_of(3);
+        queue.set_current(1);
+        let id = queue.items[1].id;
+        assert!(queue.remove(id));
+        assert_eq!(queue.current_index, Some(1));
+        assert_eq!(queue.items[1].path, PathBuf::from("2.mp3"));
+    }
+
+    fn shuffled_queue_of(n: usize, mode: LoopMode) -> PlaybackQueue {
+        let mut queue = queue_of(n);
+        queue.loop_mode = mode;
+        queue.reshuffle();
+        queue
+    }
+
+    /// Move on to the next track as the engine does
+    fn play_next(queue: &mut PlaybackQueue) -> usize {
+        let next = queue.next_index().unwrap();
+        queue.set_current(next);
+        next
+    }
+
+    fn with_album(queue: &mut PlaybackQueue, index: usize, album: &str) {
+        let id = queue.items[index].id;
+        queue.set_metadata(
+            id,
+            AudioMetadata {
+                album: Some(album.to_string()),
+                author: Some("Artist".to_string()),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn removing_the_current_track_in_shuffle_moves_to_the_next_in_shuffle_order() {
+        let mut queue = queue_of(4);
+        queue.loop_mode = LoopMode::Shuffle;
+        queue.shuffle_order = vec![2, 0, 3, 1];
+        queue.current_index = Some(0);
+
+        let id = queue.items[0].id;
+        assert!(queue.remove(id));
+        assert_eq!(queue.current().unwrap().path, PathBuf::from("3.mp3"));
+        assert_eq!(queue.shuffle_order, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn removing_the_last_current_track_in_shuffle_wraps_around() {
+        let mut queue = queue_of(3);
+        queue.loop_mode = LoopMode::Shuffle;
+        queue.shuffle_order = vec![2, 0, 1];
+        queue.current_index = Some(1);
+
+        let id = queue.items[1].id;
+        assert!(queue.remove(id));
+        assert_eq!(queue.current().unwrap().path, PathBuf::from("2.mp3"));
+    }
+
+    #[test]
+    fn reshuffle_starts_with_the_current_track() {
+        let mut queue = queue_of(8);
+        queue.set_current(5);
+        queue.loop_mode = LoopMode::Shuffle;
+        queue.reshuffle();
+        assert_eq!(queue.shuffle_order[0], 5);
+        assert_eq!(queue.shuffle_order.len(), 8);
+    }
+
+    #[test]
+    fn previous_and_next_survive_a_reshuffle() {
+        let mut queue = shuffled_queue_of(8, LoopMode::Shuffle);
+        let first = queue.shuffle_order[0];
+        queue.set_current(first);
+        let second = play_next(&mut queue);
+        let third = play_next(&mut queue);
+
+        queue.reshuffle();
+        assert_eq!(&queue.shuffle_order[..3], &[first, second, third]);
+        assert_eq!(queue.prev_index(), Some(second));
+
+        queue.set_current(second);
+        assert_eq!(queue.prev_index(), Some(first));
+        assert_eq!(queue.next_index(), Some(third));
+    }
+
+    #[test]
+    fn shuffle_order_is_forgotten_outside_shuffle() {
+        let mut queue = shuffled_queue_of(6, LoopMode::Shuffle);
+        let first = queue.shuffle_order[0];
+        queue.set_current(first);
+        play_next(&mut queue);
+
+        queue.loop_mode = LoopMode::Off;
+        queue.set_current(4);
+        queue.loop_mode = LoopMode::Shuffle;
+        queue.reshuffle();
+        assert_eq!(queue.shuffle_order[0], 4);
+    }
+
+    #[test]
+    fn tracks_added_while_shuffling_go_into_the_upcoming_order() {
+        for mode in [
+            LoopMode::Shuffle,
+            LoopMode::AlbumShuffle,
+            LoopMode::WeightedShuffle,
+        ] {
+            let mut queue = shuffled_queue_of(5, mode);
+            let first = queue.shuffle_order[0];
+            queue.set_current(first);
+            let second = play_next(&mut queue);
+
+            queue.add(vec![PathBuf::from("5.mp3"), PathBuf::from("6.mp3")]);
+            assert_eq!(queue.shuffle_order.len(), 7, "{mode:?}");
+            assert_eq!(&queue.shuffle_order[..2], &[first, second], "{mode:?}");
+            assert!(queue.shuffle_order[2..].contains(&5), "{mode:?}");
+            assert!(queue.shuffle_order[2..].contains(&6), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn album_shuffle_keeps_albums_together_and_in_order() {
+        let mut queue = queue_of(9);
+        for index in 0..9 {
+            with_album(&mut queue, index, ["A", "B", "C"][index % 3]);
+        }
+        queue.loop_mode = LoopMode::AlbumShuffle;
+        queue.reshuffle();
+
+        for album in queue.shuffle_order.chunks(3) {
+            assert!(album.iter().all(|&i| i % 3 == album[0] % 3), "{album:?}");
+            assert!(album.windows(2).all(|pair| pair[0] < pair[1]), "{album:?}");
+        }
+    }
+
+    #[test]
+    fn album_shuffle_continues_the_current_album_first() {
+        let mut queue = queue_of(6);
+        for index in 0..6 {
+            with_album(&mut queue, index, if index < 3 { "A" } else { "B" });
+        }
+        queue.set_current(1);
+        queue.loop_mode = LoopMode::AlbumShuffle;
+        queue.reshuffle();
+        assert_eq!(&queue.shuffle_order[..2], &[1, 2]);
+        assert_eq!(queue.shuffle_order.last(), Some(&0));
+    }
+
+    #[test]
+    fn weighted_order_is_deterministic_for_a_seed() {
+        let mut queue = queue_of(10);
+        for index in [3, 7, 1] {
+            queue.set_current(index);
+        }
+        let order = |seed| queue.weighted_order(&mut StdRng::seed_from_u64(seed), 0..10);
+
+        let first = order(7);
+        assert_eq!(first, order(7));
+        let mut sorted = first.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn weighted_order_favors_tracks_not_played_recently() {
+        let mut queue = queue_of(4);
+        queue.set_current(0);
+        let mut rng = StdRng::seed_from_u64(1);
+        let last_count = (0..200)
+            .filter(|_| queue.weighted_order(&mut rng, 0..4).last() == Some(&0))
+            .count();
+        // Uniformly it would come last a quarter of the time
+        assert!(last_count > 100, "came last {last_count} times");
+    }
+
+    #[test]
+    fn removing_the_last_current_track_clears_current() {
+        let mut queue = queue_of(3);
+        queue.set_current(2);
+        let id = queue.items[2].id;
+        assert!(queue.remove(id));
+        assert_eq!(queue.current_index, None);
+    }
 }
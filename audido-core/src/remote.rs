@@ -0,0 +1,260 @@
+//! Remote sources: web pages and media URLs resolved with yt-dlp and
+//! downloaded into a local cache, so they play like any local file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+use youtube_dl::{SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+use crate::{config, metadata::AudioMetadata};
+
+/// File (inside the config directory) the remote settings are read from
+pub const REMOTE_CONFIG_FILE: &str = "remote.toml";
+
+/// Executable looked up on the PATH when none is configured
+pub const DEFAULT_YTDLP: &str = "yt-dlp";
+
+/// Prefer formats the decoder handles (AAC, MP3) over Opus/WebM, which it can't
+pub const DEFAULT_FORMAT: &str = "bestaudio[ext=m4a]/bestaudio[ext=mp3]/bestaudio/best";
+
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    #[error("cannot create the download cache: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("no cache directory available on this platform")]
+    NoCacheDir,
+    #[error("yt-dlp failed: {0}")]
+    Ytdlp(#[from] youtube_dl::Error),
+    #[error("nothing to play at {0}")]
+    Empty(String),
+    #[error("yt-dlp did not produce a file for {0}")]
+    NotDownloaded(String),
+}
+
+/// Settings for resolving URLs, from `remote.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// yt-dlp (or compatible) executable, a path or a name on the PATH
+    pub ytdlp: String,
+    /// yt-dlp format selector
+    pub format: String,
+    /// Where downloads are kept, the cache directory when unset
+    pub cache_dir: Option<String>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            ytdlp: DEFAULT_YTDLP.to_string(),
+            format: DEFAULT_FORMAT.to_string(),
+            cache_dir: None,
+        }
+    }
+}
+
+impl RemoteConfig {
+    pub fn load() -> Result<Self, RemoteError> {
+        Ok(config::load_toml(REMOTE_CONFIG_FILE)?)
+    }
+
+    /// Directory downloads go to (e.g. `~/.cache/audido/remote`)
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        match &self.cache_dir {
+            Some(dir) => Some(config::expand_home(dir)),
+            None => config::cache_dir().map(|dir| dir.join("remote")),
+        }
+    }
+
+    fn ytdlp(&self, url: &str) -> YoutubeDl {
+        let mut ytdlp = YoutubeDl::new(url);
+        ytdlp
+            .youtube_dl_path(&self.ytdlp)
+            .format(&self.format)
+            .output_template("%(id)s.%(ext)s");
+        ytdlp
+    }
+}
+
+/// A resolved remote track: its cached file and what yt-dlp reported about it
+#[derive(Debug, Clone)]
+pub struct RemoteTrack {
+    /// Page the track was resolved from
+    pub url: String,
+    pub path: PathBuf,
+    pub metadata: AudioMetadata,
+}
+
+/// Whether a command-line argument or typed location is a URL rather than a file
+pub fn is_url(location: &str) -> bool {
+    url::Url::parse(location).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Whether a playlist entry is a page to resolve rather than a stream to connect
+/// to. Streams are plain http, the pages remote tracks are exported as https.
+pub fn is_page(location: &str) -> bool {
+    url::Url::parse(location).is_ok_and(|url| url.scheme() == "https")
+}
+
+/// Resolve a URL with yt-dlp and download its audio into the cache. Playlist
+/// pages give one track per entry. Files already in the cache aren't
+/// downloaded again.
+///
+/// Blocks until yt-dlp is done, so call it off the audio thread.
+pub fn resolve(url: &str, config: &RemoteConfig) -> Result<Vec<RemoteTrack>, RemoteError> {
+    let cache_dir = config.cache_dir().ok_or(RemoteError::NoCacheDir)?;
+    fs::create_dir_all(&cache_dir)?;
+
+    let videos = match config.ytdlp(url).run()? {
+        YoutubeDlOutput::SingleVideo(video) => vec![*video],
+        YoutubeDlOutput::Playlist(playlist) => playlist.entries.unwrap_or_default(),
+    };
+    if videos.is_empty() {
+        return Err(RemoteError::Empty(url.to_string()));
+    }
+
+    let single = videos.len() == 1;
+    videos
+        .into_iter()
+        .map(|video| {
+            // Entries of a playlist are downloaded from their own page
+            let page = if single {
+                url.to_string()
+            } else {
+                video.webpage_url.clone().unwrap_or_else(|| url.to_string())
+            };
+            let path = match cached_file(&cache_dir, &video) {
+                Some(path) => path,
+                None => {
+                    log::info!("Downloading {} to {}", page, cache_dir.display());
+                    config.ytdlp(&page).download_to(&cache_dir)?;
+                    cached_file(&cache_dir, &video)
+                        .ok_or_else(|| RemoteError::NotDownloaded(page.clone()))?
+                }
+            };
+            let metadata = video_metadata(&video, &path);
+            Ok(RemoteTrack {
+                url: page,
+                path,
+                metadata,
+            })
+        })
+        .collect()
+}
+
+/// The downloaded file of a video: `<id>.<ext>`, or any finished file named
+/// after the id when yt-dlp picked another container
+fn cached_file(cache_dir: &Path, video: &SingleVideo) -> Option<PathBuf> {
+    if let Some(ext) = &video.ext {
+        let path = cache_dir.join(format!("{}.{}", video.id, ext));
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+    fs::read_dir(cache_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            let partial = path
+                .extension()
+                .is_some_and(|ext| ext == "part" || ext == "ytdl");
+            !partial && path.file_stem().is_some_and(|stem| *stem == *video.id)
+        })
+}
+
+/// Metadata from yt-dlp's info, for the queue until the file is read
+fn video_metadata(video: &SingleVideo, path: &Path) -> AudioMetadata {
+    AudioMetadata {
+        format: path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        full_file_path: path.to_string_lossy().to_string(),
+        title: video.track.clone().or_else(|| video.title.clone()),
+        author: video
+            .artist
+            .clone()
+            .or_else(|| video.creator.clone())
+            .or_else(|| video.uploader.clone())
+            .or_else(|| video.channel.clone()),
+        genre: video.genre.clone(),
+        album: video.album.clone(),
+        duration: video
+            .duration
+            .as_ref()
+            .and_then(|duration| duration.as_f64())
+            .unwrap_or(0.0) as f32,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stand-in for yt-dlp: prints the info JSON of one video, and with
+    /// `--no-simulate` writes `<id>.<ext>` to the `-P` directory and logs the
+    /// download next to it
+    #[cfg(unix)]
+    const STUB_YTDLP: &str = r#"#!/bin/sh
+dir=""
+download=0
+prev=""
+for arg in "$@"; do
+    [ "$prev" = "-P" ] && dir="$arg"
+    [ "$arg" = "--no-simulate" ] && download=1
+    prev="$arg"
+done
+if [ "$download" = 1 ]; then
+    echo "$arg" >> "$dir/../downloads.log"
+    printf 'audio' > "$dir/abc123.m4a"
+else
+    printf '{"id":"abc123","ext":"m4a","title":"Stub Song","uploader":"Stub Channel","duration":12.5,"webpage_url":"%s"}\n' "$arg"
+fi
+"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_downloads_once_and_reuses_the_cache() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("audido-remote-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let script = root.join("yt-dlp");
+        fs::write(&script, STUB_YTDLP).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let cache_dir = root.join("cache");
+        let config = RemoteConfig {
+            ytdlp: script.to_string_lossy().to_string(),
+            cache_dir: Some(cache_dir.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let url = "https://example.com/watch?v=abc123";
+
+        let tracks = resolve(url, &config).unwrap();
+        assert_eq!(tracks.len(), 1);
+        let track = &tracks[0];
+        assert_eq!(track.url, url);
+        assert_eq!(track.path, cache_dir.join("abc123.m4a"));
+        assert_eq!(track.metadata.title.as_deref(), Some("Stub Song"));
+        assert_eq!(track.metadata.author.as_deref(), Some("Stub Channel"));
+        assert_eq!(track.metadata.duration, 12.5);
+        assert_eq!(track.metadata.format, "m4a");
+
+        // Already in the cache: resolved again without a second download
+        let again = resolve(url, &config).unwrap();
+        assert_eq!(again[0].path, track.path);
+        let downloads = fs::read_to_string(root.join("downloads.log")).unwrap();
+        assert_eq!(downloads.lines().collect::<Vec<_>>(), [url]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    config,
+    cue::TrackRange,
+    dsp::{
+        eq::{EqPreset, FilterNode},
+        normalization::NormalizationMode,
+    },
+    metadata::AudioMetadata,
+    queue::{LoopMode, PlaybackQueue},
+    stream,
+};
+
+/// File (inside the state directory) the session is written to
+pub const SESSION_FILE: &str = "session.toml";
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("no state directory available on this platform")]
+    NoStateDir,
+    #[error("cannot access session file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid session file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("cannot serialize session: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// Equalizer settings stored in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EqSession {
+    pub enabled: bool,
+    pub preset: EqPreset,
+    /// Pre-amp gain in dB
+    pub master_gain_db: f32,
+    pub filters: Vec<FilterNode>,
+}
+
+/// Normalizer settings stored in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizerSession {
+    pub enabled: bool,
+    pub mode: NormalizationMode,
+    pub target_level: f32,
+    pub headroom: f32,
+}
+
+/// A queued CUE track: which queue entry it is and the part of the file it plays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CueTrackSession {
+    /// Position in `Session::queue`
+    pub index: usize,
+    #[serde(flatten)]
+    pub range: TrackRange,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+}
+
+/// A queued remote track: which queue entry it is and the page it was resolved
+/// from, to download it again when the cache was cleared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteTrackSession {
+    /// Position in `Session::queue`
+    pub index: usize,
+    pub url: String,
+}
+
+/// Player state written on quit and restored on the next launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub queue: Vec<PathBuf>,
+    pub current_index: Option<usize>,
+    /// Playback position in the current track, in seconds
+    pub position: f32,
+    pub loop_mode: LoopMode,
+    pub volume: f32,
+    pub eq: EqSession,
+    pub normalizer: NormalizerSession,
+    /// Queue entries that are tracks of a CUE sheet
+    #[serde(default)]
+    pub cue_tracks: Vec<CueTrackSession>,
+    /// Queue entries downloaded from a URL
+    #[serde(default)]
+    pub remote_tracks: Vec<RemoteTrackSession>,
+}
+
+impl Session {
+    /// Read the last saved session. Returns `None` when there is none.
+    pub fn load() -> Result<Option<Self>, SessionError> {
+        let path = config::state_file(SESSION_FILE).ok_or(SessionError::NoStateDir)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::load_from(&path).map(Some)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, SessionError> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Write the session to the state directory
+    pub fn save(&self) -> Result<(), SessionError> {
+        let path = config::state_file(SESSION_FILE).ok_or(SessionError::NoStateDir)?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), SessionError> {
+        config::write_atomic(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Record the tracks of `queue` and which one is current
+    pub fn set_queue(&mut self, queue: &PlaybackQueue) {
+        self.queue = queue.items.iter().map(|item| item.path.clone()).collect();
+        self.current_index = queue.current_index;
+        self.cue_tracks = queue
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let metadata = item.metadata.as_ref();
+                Some(CueTrackSession {
+                    index,
+                    range: item.range?,
+                    title: metadata.and_then(|m| m.title.clone()),
+                    performer: metadata.and_then(|m| m.author.clone()),
+                })
+            })
+            .collect();
+        self.remote_tracks = queue
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                Some(RemoteTrackSession {
+                    index,
+                    url: item.source_url.clone()?,
+                })
+            })
+            .collect();
+    }
+
+    /// Add the saved tracks to `queue`, skipping local files that no longer
+    /// exist. Returns the index the current track ended up at, `None` when it
+    /// was skipped.
+    pub fn restore_queue(&self, queue: &mut PlaybackQueue) -> Option<usize> {
+        let mut current = None;
+        for (index, path) in self.queue.iter().enumerate() {
+            let remote = self.remote_tracks.iter().find(|track| track.index == index);
+            // Remote tracks are downloaded again when their cached file is gone
+            if remote.is_none() && !stream::is_stream(path) && !path.exists() {
+                log::warn!("Skipping missing session track {}", path.display());
+                continue;
+            }
+
+            let Some(&id) = queue.add(vec![path.clone()]).first() else {
+                continue;
+            };
+            if self.current_index == Some(index) {
+                current = queue.index_of(id);
+            }
+            if let Some(track) = self.cue_tracks.iter().find(|track| track.index == index) {
+                queue.set_range(id, track.range);
+                queue.set_metadata(
+                    id,
+                    AudioMetadata {
+                        full_file_path: path.to_string_lossy().to_string(),
+                        title: track.title.clone(),
+                        author: track.performer.clone(),
+                        duration: track.range.duration().unwrap_or(0.0),
+                        ..Default::default()
+                    },
+                );
+            }
+            if let Some(track) = remote {
+                queue.set_source_url(id, track.url.clone());
+            }
+        }
+        current
+    }
+
+    /// File name of the track the session stopped on (the title of a CUE
+    /// track), for display
+    pub fn current_track_name(&self) -> Option<String> {
+        let index = self.current_index?;
+        if let Some(title) = self
+            .cue_tracks
+            .iter()
+            .find(|track| track.index == index)
+            .and_then(|track| track.title.clone())
+        {
+            return Some(title);
+        }
+        let path = self.queue.get(index)?;
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("audido-session-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn touch(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, b"").unwrap();
+        path
+    }
+
+    fn empty_session() -> Session {
+        Session {
+            queue: Vec::new(),
+            current_index: None,
+            position: 42.5,
+            loop_mode: LoopMode::LoopAll,
+            volume: 0.6,
+            eq: EqSession {
+                enabled: true,
+                preset: EqPreset::BassBoosted,
+                master_gain_db: -3.0,
+                filters: EqPreset::BassBoosted.set_filters(),
+            },
+            normalizer: NormalizerSession {
+                enabled: true,
+                mode: NormalizationMode::RMS,
+                target_level: -18.0,
+                headroom: 2.0,
+            },
+            cue_tracks: Vec::new(),
+            remote_tracks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn session_round_trips_through_its_file() {
+        let dir = temp_dir("round-trip");
+        let album = touch(&dir, "album.flac");
+        let single = touch(&dir, "single.mp3");
+        let download = touch(&dir, "download.opus");
+
+        let mut queue = PlaybackQueue::new();
+        let ids = queue.add(vec![single.clone(), album.clone(), download.clone()]);
+        let range = TrackRange {
+            start: 30.0,
+            end: Some(95.0),
+        };
+        queue.set_range(ids[1], range);
+        queue.set_metadata(
+            ids[1],
+            AudioMetadata {
+                title: Some("Second".to_string()),
+                author: Some("Band".to_string()),
+                ..Default::default()
+            },
+        );
+        queue.set_source_url(ids[2], "https://example.com/watch?v=1".to_string());
+        queue.set_current(1);
+
+        let mut session = empty_session();
+        session.set_queue(&queue);
+        let file = dir.join(SESSION_FILE);
+        session.save_to(&file).unwrap();
+        let loaded = Session::load_from(&file).unwrap();
+
+        assert_eq!(loaded.position, 42.5);
+        assert_eq!(loaded.volume, 0.6);
+        assert_eq!(loaded.loop_mode, LoopMode::LoopAll);
+        assert_eq!(loaded.eq.filters, session.eq.filters);
+        assert_eq!(loaded.normalizer.target_level, -18.0);
+        assert_eq!(loaded.current_track_name().as_deref(), Some("Second"));
+
+        let mut restored = PlaybackQueue::new();
+        assert_eq!(loaded.restore_queue(&mut restored), Some(1));
+        let paths: Vec<&PathBuf> = restored.items.iter().map(|item| &item.path).collect();
+        assert_eq!(paths, [&single, &album, &download]);
+        assert_eq!(restored.items[1].range, Some(range));
+        let metadata = restored.items[1].metadata.as_ref().unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Second"));
+        assert_eq!(metadata.author.as_deref(), Some("Band"));
+        assert_eq!(
+            restored.items[2].source_url.as_deref(),
+            Some("https://example.com/watch?v=1")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_files_are_skipped_on_restore() {
+        let dir = temp_dir("missing");
+        let first = touch(&dir, "first.mp3");
+        let gone = dir.join("gone.mp3");
+        let current = touch(&dir, "current.mp3");
+        let range = TrackRange {
+            start: 0.0,
+            end: None,
+        };
+
+        let session = Session {
+            queue: vec![first.clone(), gone, current.clone()],
+            current_index: Some(2),
+            cue_tracks: vec![CueTrackSession {
+                index: 2,
+                range,
+                title: None,
+                performer: None,
+            }],
+            ..empty_session()
+        };
+
+        let mut queue = PlaybackQueue::new();
+        assert_eq!(session.restore_queue(&mut queue), Some(1));
+        let paths: Vec<&PathBuf> = queue.items.iter().map(|item| &item.path).collect();
+        assert_eq!(paths, [&first, &current]);
+        assert_eq!(queue.items[1].range, Some(range));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_current_track_is_not_resumed() {
+        let dir = temp_dir("missing-current");
+        let first = touch(&dir, "first.mp3");
+        let session = Session {
+            queue: vec![first, dir.join("gone.mp3")],
+            current_index: Some(1),
+            ..empty_session()
+        };
+
+        let mut queue = PlaybackQueue::new();
+        assert_eq!(session.restore_queue(&mut queue), None);
+        assert_eq!(queue.items.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn remote_tracks_and_streams_are_kept_without_a_local_file() {
+        let session = Session {
+            queue: vec![
+                PathBuf::from("/cache/remote/removed.opus"),
+                PathBuf::from("http://radio.example.com/live"),
+            ],
+            current_index: Some(0),
+            remote_tracks: vec![RemoteTrackSession {
+                index: 0,
+                url: "https://example.com/watch?v=2".to_string(),
+            }],
+            ..empty_session()
+        };
+
+        let mut queue = PlaybackQueue::new();
+        assert_eq!(session.restore_queue(&mut queue), Some(0));
+        assert_eq!(queue.items.len(), 2);
+    }
+}
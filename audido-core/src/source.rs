@@ -1,5 +1,5 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     path::Path,
     sync::{
         Arc, Mutex,
@@ -11,26 +11,41 @@ use std::{
 
 use anyhow::Context;
 use crossbeam_channel::Receiver;
-use lofty::{file::TaggedFileExt, probe::Probe, tag::Accessor};
+use lofty::{
+    file::{AudioFile, TaggedFile, TaggedFileExt},
+    probe::Probe,
+    tag::{Accessor, ItemKey},
+};
 use rodio::{Decoder, Source};
 
 use crate::{
+    analysis::{self, Analysis, REPLAY_GAIN_REFERENCE},
     commands::RealtimeAudioCommand,
+    cue::TrackRange,
     dsp::{dsp_graph::DspNode, eq::Equalizer, normalization::Normalizer},
+    formats::{self, Codec},
+    lyrics,
     metadata::{AudioMetadata, ChannelLayout},
+    stream::{self, LiveStream, StreamBuffer, StreamEvent},
+    tags::TagChange,
 };
 
-use crate::dsp::pitch_detection::{SongKeyArgsBuilder, detect_song_key};
-
 const CHUNK_SIZE: usize = 512;
 
 /// Shared position tracker between source and engine
 #[derive(Clone)]
 pub struct PositionTracker {
-    /// Current sample position (atomic for thread-safe access)
+    /// Current sample position in the file (atomic for thread-safe access)
     position: Arc<AtomicUsize>,
-    /// Total number of samples
-    total_samples: usize,
+    /// Sample the track starts at: 0, or where a CUE track begins
+    start: usize,
+    /// Sample the track ends at, `None` for live streams
+    end: Option<usize>,
+    /// Sample the source stops at: the track's end, or further on while the
+    /// next track carries on in the same file
+    stop_at: Arc<AtomicUsize>,
+    /// Total number of samples in the file, `None` for live streams
+    total_samples: Option<usize>,
     /// Sample rate for time calculations
     sample_rate: u32,
     /// Number of channels
@@ -41,53 +56,175 @@ impl PositionTracker {
     pub fn new(total_samples: usize, sample_rate: u32, channels: u16) -> Self {
         Self {
             position: Arc::new(AtomicUsize::new(0)),
-            total_samples,
+            start: 0,
+            end: Some(total_samples),
+            stop_at: Arc::new(AtomicUsize::new(total_samples)),
+            total_samples: Some(total_samples),
+            sample_rate,
+            channels,
+        }
+    }
+
+    /// Tracker for a live stream, which has no known length
+    pub fn live(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            position: Arc::new(AtomicUsize::new(0)),
+            start: 0,
+            end: None,
+            stop_at: Arc::new(AtomicUsize::new(usize::MAX)),
+            total_samples: None,
             sample_rate,
             channels,
         }
     }
 
+    /// Tracker for part of the same file, positioned at its start. Times are
+    /// then relative to the range.
+    pub fn with_range(&self, range: TrackRange) -> Self {
+        let tracker = self.continued(range);
+        Self {
+            position: Arc::new(AtomicUsize::new(tracker.start)),
+            stop_at: Arc::new(AtomicUsize::new(tracker.end.unwrap_or(usize::MAX))),
+            ..tracker
+        }
+    }
+
+    /// Like `with_range`, sharing the position with this tracker: a source
+    /// playing this track that runs on into the range keeps going without a gap
+    pub fn continued(&self, range: TrackRange) -> Self {
+        let total = self.total_samples.unwrap_or(usize::MAX);
+        let start = self.sample_at(range.start).min(total);
+        let end = range
+            .end
+            .map_or(total, |end| self.sample_at(end).min(total));
+        Self {
+            start,
+            end: Some(end.max(start)),
+            ..self.clone()
+        }
+    }
+
+    /// Let the source run on to the end of `next`, a track that follows this one
+    /// in the same file, or stop at the end of this track when `None`
+    pub fn play_through(&self, next: Option<TrackRange>) {
+        let Some(end) = self.end else {
+            return;
+        };
+        let stop_at = next.map_or(end, |next| self.continued(next).end.unwrap_or(end));
+        self.stop_at.store(stop_at, Ordering::Relaxed);
+    }
+
+    /// Whether playback has gone past the end of the track
+    pub fn past_end(&self) -> bool {
+        self.end
+            .is_some_and(|end| self.position.load(Ordering::Relaxed) >= end)
+    }
+
     /// Get current position in seconds
     pub fn position_seconds(&self) -> f32 {
-        let pos = self.position.load(Ordering::Relaxed);
+        let pos = self
+            .position
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.start);
         let frames = pos / (self.channels as usize);
         (frames as f32) / (self.sample_rate as f32)
     }
 
-    /// Get total duration in seconds
-    pub fn duration_seconds(&self) -> f32 {
-        let frames = self.total_samples / (self.channels as usize);
-        (frames as f32) / (self.sample_rate as f32)
+    /// Get total duration in seconds, `None` when unknown (live streams)
+    pub fn duration_seconds(&self) -> Option<f32> {
+        let frames = (self.end? - self.start) / (self.channels as usize);
+        Some((frames as f32) / (self.sample_rate as f32))
     }
 
     /// Set position from seconds
     pub fn seek_to_seconds(&self, seconds: f32) {
-        let frames = (seconds * (self.sample_rate as f32)) as usize;
-        let sample_pos = (frames * (self.channels as usize)).min(self.total_samples);
+        let sample_pos = self.start + self.sample_at(seconds);
+        let sample_pos = self.end.map_or(sample_pos, |end| sample_pos.min(end));
         self.position.store(sample_pos, Ordering::Relaxed);
     }
 
     /// Reset position to start
     pub fn reset(&self) {
-        self.position.store(0, Ordering::Relaxed);
+        self.position.store(self.start, Ordering::Relaxed);
     }
+
+    /// Sample index of a time, on a frame boundary
+    fn sample_at(&self, seconds: f32) -> usize {
+        let frames = (seconds.max(0.0) * (self.sample_rate as f32)) as usize;
+        frames * (self.channels as usize)
+    }
+}
+
+/// Where a source's samples come from
+#[derive(Clone)]
+enum Samples {
+    /// A whole file, decoded up front
+    Decoded(Arc<Vec<f32>>),
+    /// A live stream, decoded as it arrives
+    Live(Arc<StreamBuffer>),
 }
 
 pub struct AudioPlaybackData {
     metadata: Arc<Mutex<AudioMetadata>>,
-    buffer: Arc<Vec<f32>>,
+    /// Results of the background analysis, until the engine takes them
+    analysis: Arc<Mutex<Option<Analysis>>>,
+    /// Title, performer and length of a CUE track, over the file's own tags
+    track: Option<AudioMetadata>,
+    samples: Samples,
     position_tracker: PositionTracker,
-}
-
-pub enum AudioSource {
-    Local { data: AudioPlaybackData },
+    /// Connection of a live stream
+    live: Option<LiveStream>,
 }
 
 impl AudioPlaybackData {
+    /// Open a queue entry: a local file, or a stream when it is a URL
+    pub fn load(path: &str) -> anyhow::Result<AudioPlaybackData> {
+        if stream::is_stream(Path::new(path)) {
+            Self::open_stream(path)
+        } else {
+            Self::load_local_audio(path)
+        }
+    }
+
+    /// Connect to an HTTP stream; playback starts once its prebuffer is full
+    pub fn open_stream(url: &str) -> anyhow::Result<AudioPlaybackData> {
+        let live = LiveStream::open(url).context("Failed to open the stream")?;
+        Ok(AudioPlaybackData {
+            metadata: Arc::new(Mutex::new(live.metadata.clone())),
+            analysis: Arc::new(Mutex::new(None)),
+            track: None,
+            samples: Samples::Live(Arc::clone(&live.buffer)),
+            position_tracker: PositionTracker::live(live.sample_rate, live.channels),
+            live: Some(live),
+        })
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.live.is_some()
+    }
+
+    /// News from a live stream since the last call; new titles are already in
+    /// the metadata
+    pub fn poll_stream(&mut self) -> Vec<StreamEvent> {
+        let Some(live) = &mut self.live else {
+            return Vec::new();
+        };
+        let events = live.poll();
+        for event in &events {
+            if let StreamEvent::Title(title) = event {
+                let mut guard = self.metadata.lock().expect("metadata mutex poisoned");
+                stream::apply_stream_title(&mut guard, title);
+            }
+        }
+        events
+    }
+
     pub fn load_local_audio(path: &str) -> anyhow::Result<AudioPlaybackData> {
         // calculate time required for performance monitoring
         let start_time = Instant::now();
 
+        // Says which file and why, where the decoder would only fail
+        let codec = formats::probe(Path::new(path))?;
         let file = File::open(path).context("Failed to open the file")?;
         let decoder = Decoder::try_from(file).context("Failed to decode the opened audio file")?;
 
@@ -124,27 +261,45 @@ impl AudioPlaybackData {
             channel_layout,
             duration: duration_in_seconds,
             format: file_ext.clone(),
+            full_file_path: path.to_string(),
             ..Default::default()
         };
 
         // Read static metadata immediately
-        Self::read_audio_metadata(path, &mut initial_metadata)?;
+        let tagged_file = Probe::open(path).and_then(|p| p.read());
+        Self::read_audio_metadata(path, Some(codec), tagged_file, &mut initial_metadata);
+        initial_metadata.lyric = match lyrics::load_lyrics(Path::new(path)) {
+            Ok(lyric) => lyric.map(Arc::new),
+            Err(e) => {
+                log::warn!("Failed to read lyrics: {}", e);
+                None
+            }
+        };
 
+        let known = initial_metadata.clone();
         let metadata = Arc::new(Mutex::new(initial_metadata));
+        let analysis = Arc::new(Mutex::new(None));
         let samples_arc = Arc::new(samples);
 
-        // Spawn analysis in background thread
+        // Analyse what the tags don't tell in a background thread
         let metadata_for_thread = Arc::clone(&metadata);
+        let analysis_for_thread = Arc::clone(&analysis);
         let samples_for_thread = Arc::clone(&samples_arc);
 
         thread::spawn(move || {
-            if let Err(e) = Self::analyze_audio_properties(
+            log::info!("Starting background audio analysis...");
+            let result = analysis::analyze_cached(
+                Path::new(&known.full_file_path),
                 &samples_for_thread,
                 sample_rate as f32,
                 num_channels,
-                &metadata_for_thread,
-            ) {
-                log::error!("Audio analysis failed: {}", e);
+                &known,
+            );
+            if let Ok(mut meta) = metadata_for_thread.lock() {
+                result.apply_to(&mut meta);
+            }
+            if let Ok(mut slot) = analysis_for_thread.lock() {
+                *slot = Some(result);
             }
         });
 
@@ -153,57 +308,97 @@ impl AudioPlaybackData {
 
         let playback_data = AudioPlaybackData {
             metadata,
-            buffer: samples_arc,
+            analysis,
+            track: None,
+            samples: Samples::Decoded(samples_arc),
             position_tracker,
+            live: None,
         };
 
         log::debug!("Load audio finished in {:?} seconds", start_time.elapsed());
         Ok(playback_data)
     }
 
-    /// Analyze audio properties in background and update metadata when done
-    // FIXME: Incorrectly classify the song key
-    fn analyze_audio_properties(
-        buffer: &[f32],
-        sample_rate: f32,
-        num_channels: u16,
-        metadata: &Arc<Mutex<AudioMetadata>>,
-    ) -> anyhow::Result<()> {
-        let start = Instant::now();
-        log::info!("Starting background audio analysis...");
-
-        // Perform key detection
-        let song_key_args = SongKeyArgsBuilder::new(buffer, sample_rate)
-            .channel_layout(ChannelLayout::from_channels(num_channels))
-            .build()?;
-
-        let key = detect_song_key(song_key_args)?;
-
-        // Lock mutex and update metadata
-        {
-            let mut meta = metadata
-                .lock()
-                .map_err(|e| anyhow::anyhow!("Failed to lock metadata mutex: {}", e))?;
-            meta.key = Some(key);
-            log::info!(
-                "Audio analysis completed in {:?}. Detected key: {:?}",
-                start.elapsed(),
-                meta.key
-            );
-        }
+    /// Read the tags and stream properties of a file without decoding it
+    pub fn read_tags(path: &str) -> AudioMetadata {
+        let mut metadata = AudioMetadata {
+            full_file_path: path.to_string(),
+            format: Path::new(path)
+                .extension()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string(),
+            ..Default::default()
+        };
 
-        Ok(())
+        let tagged_file = Probe::open(path).and_then(|p| p.read());
+        if let Ok(tagged_file) = &tagged_file {
+            let properties = tagged_file.properties();
+            metadata.duration = properties.duration().as_secs_f32();
+            metadata.sample_rate = properties.sample_rate().unwrap_or(0);
+            metadata.num_channels = properties.channels().unwrap_or(0) as u16;
+            metadata.channel_layout = ChannelLayout::from_channels(metadata.num_channels);
+        }
+        let codec = formats::probe(Path::new(path)).ok();
+        Self::read_audio_metadata(path, codec, tagged_file, &mut metadata);
+        metadata
     }
 
-    //// Get audio metadata from loaded file (title, author, album, genre, etc)
-    fn read_audio_metadata(path: &str, metadata: &mut AudioMetadata) -> anyhow::Result<()> {
-        match Probe::open(path).and_then(|p| p.read()) {
+    /// Get audio metadata from loaded file (title, author, album, genre, etc),
+    /// given its codec and the file as lofty read it. Missing tags only leave
+    /// the fields unset.
+    fn read_audio_metadata(
+        path: &str,
+        codec: Option<Codec>,
+        tagged_file: lofty::error::Result<TaggedFile>,
+        metadata: &mut AudioMetadata,
+    ) {
+        metadata.file_size = fs::metadata(path).ok().map(|m| m.len());
+        if let Some(codec) = codec {
+            metadata.codec = Some(codec.name);
+            metadata.bit_depth = codec
+                .bits_per_sample
+                .and_then(|bits| u8::try_from(bits).ok());
+        }
+
+        match tagged_file {
             Ok(tagged_file) => {
+                let properties = tagged_file.properties();
+                metadata.bit_depth = properties.bit_depth().or(metadata.bit_depth);
+                metadata.bitrate = properties
+                    .audio_bitrate()
+                    .or(properties.overall_bitrate())
+                    .filter(|kbps| *kbps > 0);
+
                 if let Some(tag) = tagged_file.primary_tag() {
                     metadata.title = tag.title().map(|s| s.to_string());
                     metadata.author = tag.artist().map(|s| s.to_string());
                     metadata.album = tag.album().map(|s| s.to_string());
                     metadata.genre = tag.genre().map(|s| s.to_string());
+                    metadata.album_artist =
+                        tag.get_string(&ItemKey::AlbumArtist).map(|s| s.to_string());
+                    metadata.track_number = tag.track();
+                    metadata.disc_number = tag.disk();
+                    metadata.year = tag.year();
+                    metadata.composer = tag.get_string(&ItemKey::Composer).map(|s| s.to_string());
+                    metadata.comment = tag.comment().map(|s| s.to_string());
+                    metadata.bpm = tag
+                        .get_string(&ItemKey::Bpm)
+                        .or_else(|| tag.get_string(&ItemKey::IntegerBpm))
+                        .and_then(|bpm| bpm.trim().parse::<f32>().ok())
+                        .filter(|bpm| *bpm > 0.0);
+                    // Keys written by DJ software, as a name ("Am") or Camelot code ("8A")
+                    metadata.key = tag
+                        .get_string(&ItemKey::InitialKey)
+                        .and_then(|key| key.parse().ok());
+                    // ReplayGain is written as e.g. "-6.20 dB"
+                    metadata.loudness = tag
+                        .get_string(&ItemKey::ReplayGainTrackGain)
+                        .and_then(|gain| gain.trim().trim_end_matches("dB").trim().parse().ok())
+                        .map(|gain: f32| REPLAY_GAIN_REFERENCE - gain);
+                    metadata.peak = tag
+                        .get_string(&ItemKey::ReplayGainTrackPeak)
+                        .and_then(|peak| peak.trim().parse().ok());
 
                     log::info!(
                         "Metadata loaded: {:?} by {:?}",
@@ -223,13 +418,65 @@ impl AudioPlaybackData {
                 .and_then(|s| s.to_str())
                 .map(|s| s.to_string());
         }
-        Ok(())
     }
 
     /// Get a cloned copy of the audio metadata
     pub fn metadata(&self) -> AudioMetadata {
         let guard = self.metadata.lock().expect("metadata mutex poisoned");
-        guard.clone()
+        let mut metadata = guard.clone();
+        if let Some(track) = &self.track {
+            metadata.apply_track_tags(track);
+        }
+        metadata
+    }
+
+    /// One track of a CUE sheet: a range of this (already decoded) file,
+    /// positioned at its start
+    pub fn track(&self, range: TrackRange, known: &AudioMetadata) -> AudioPlaybackData {
+        self.with_tracker(self.position_tracker.with_range(range), known)
+    }
+
+    /// The track following this one in the same file, continuing the playback
+    /// position so the running source plays into it without a gap
+    pub fn next_track(&self, range: TrackRange, known: &AudioMetadata) -> AudioPlaybackData {
+        self.with_tracker(self.position_tracker.continued(range), known)
+    }
+
+    fn with_tracker(&self, tracker: PositionTracker, known: &AudioMetadata) -> AudioPlaybackData {
+        let mut track = known.clone();
+        track.duration = tracker.duration_seconds().unwrap_or(0.0);
+        AudioPlaybackData {
+            metadata: Arc::clone(&self.metadata),
+            // The file's analysis covers the whole album, not this track
+            analysis: Arc::new(Mutex::new(None)),
+            track: Some(track),
+            samples: self.samples.clone(),
+            position_tracker: tracker,
+            live: None,
+        }
+    }
+
+    /// Results of the background analysis once it finished, only the first
+    /// time this is called
+    pub fn take_analysis(&self) -> Option<Analysis> {
+        self.analysis
+            .lock()
+            .expect("analysis mutex poisoned")
+            .take()
+    }
+
+    /// Fill tags the file lacks from metadata known beforehand
+    pub fn fill_missing_tags(&self, known: &AudioMetadata) {
+        let mut guard = self.metadata.lock().expect("metadata mutex poisoned");
+        guard.fill_missing_tags(known);
+    }
+
+    /// Show tag changes just written to the file
+    pub fn apply_tag_changes(&self, changes: &[TagChange]) {
+        let mut guard = self.metadata.lock().expect("metadata mutex poisoned");
+        for change in changes {
+            change.apply_to(&mut guard);
+        }
     }
 
     /// Get a reference to the position tracker
@@ -240,17 +487,21 @@ impl AudioPlaybackData {
     /// Create a rodio Source from the buffered audio data
     pub fn create_source(
         &self,
-        initial_eq: Equalizer,
+        mut initial_eq: Equalizer,
         eq_enabled: bool,
+        normalizer: Normalizer,
+        normalizer_enabled: bool,
         cmd_rx: Receiver<RealtimeAudioCommand>,
     ) -> BufferedSource {
+        // A new source starts without filter history, so there is nothing to smooth from
+        initial_eq.settle();
         BufferedSource::new(
-            self.buffer.clone(),
+            self.samples.clone(),
             self.metadata().sample_rate,
             self.metadata().num_channels,
             self.position_tracker.clone(),
-            initial_eq,
-            eq_enabled,
+            DspNode::new_with_state(initial_eq, eq_enabled),
+            DspNode::new_with_state(normalizer, normalizer_enabled),
             cmd_rx,
         )
     }
@@ -258,7 +509,7 @@ impl AudioPlaybackData {
 
 /// A buffered audio source that implements rodio's Source trait
 pub struct BufferedSource {
-    samples: Arc<Vec<f32>>,
+    samples: Samples,
     sample_rate: u32,
     channels: u16,
     position_tracker: PositionTracker,
@@ -272,13 +523,13 @@ pub struct BufferedSource {
 }
 
 impl BufferedSource {
-    pub fn new(
-        samples: Arc<Vec<f32>>,
+    fn new(
+        samples: Samples,
         sample_rate: u32,
         channels: u16,
         position_tracker: PositionTracker,
-        equalizer: Equalizer,
-        eq_enabled: bool,
+        equalizer: DspNode<Equalizer>,
+        normalizer: DspNode<Normalizer>,
         cmd_rx: Receiver<RealtimeAudioCommand>,
     ) -> Self {
         Self {
@@ -286,14 +537,22 @@ impl BufferedSource {
             sample_rate,
             channels,
             position_tracker,
-            equalizer: DspNode::new_with_state(equalizer, eq_enabled),
-            normalizer: DspNode::new_with_state(Normalizer::new(), false),
+            equalizer,
+            normalizer,
             cmd_rx,
             process_buffer: Vec::with_capacity(CHUNK_SIZE),
             process_buffer_idx: 0,
         }
     }
 
+    /// Where the decoded samples stop playing: the end of the track or the file
+    fn stop_at(&self, samples: &[f32]) -> usize {
+        self.position_tracker
+            .stop_at
+            .load(Ordering::Relaxed)
+            .min(samples.len())
+    }
+
     fn fill_buffer(&mut self) -> bool {
         self.process_buffer.clear();
         self.process_buffer_idx = 0;
@@ -313,6 +572,10 @@ impl BufferedSource {
                 RealtimeAudioCommand::SetEqPreset(preset) => {
                     self.equalizer.instance.update_preset(preset);
                 }
+                RealtimeAudioCommand::SetEqState(preset, filter_nodes) => {
+                    self.equalizer.instance.preset = preset;
+                    self.equalizer.set_all_filters(filter_nodes);
+                }
                 RealtimeAudioCommand::SetEqEnabled(enabled) => {
                     self.equalizer.on = enabled;
                 }
@@ -322,6 +585,15 @@ impl BufferedSource {
                 RealtimeAudioCommand::ResetEqFilterNode(index) => {
                     let _ = self.equalizer.instance.reset_filter_node_param(index);
                 }
+                RealtimeAudioCommand::AddEqFilter(filter_node) => {
+                    self.equalizer.instance.add_filter(filter_node);
+                }
+                RealtimeAudioCommand::RemoveEqFilter(index) => {
+                    self.equalizer.instance.remove_filter(index);
+                }
+                RealtimeAudioCommand::SetEqSolo(solo) => {
+                    self.equalizer.instance.set_solo(solo);
+                }
                 RealtimeAudioCommand::SetNormalizerMode(mode) => {
                     self.normalizer.instance.set_mode(mode);
                 }
@@ -338,14 +610,24 @@ impl BufferedSource {
         }
 
         // Fetch Audio
-        let global_pos = self.position_tracker.position.load(Ordering::Relaxed);
-        if global_pos >= self.samples.len() {
-            return false;
-        }
+        match &self.samples {
+            Samples::Decoded(samples) => {
+                let global_pos = self.position_tracker.position.load(Ordering::Relaxed);
+                let stop_at = self.stop_at(samples);
+                if global_pos >= stop_at {
+                    return false;
+                }
 
-        let end_pos = (global_pos + CHUNK_SIZE).min(self.samples.len());
-        self.process_buffer
-            .extend_from_slice(&self.samples[global_pos..end_pos]);
+                let end_pos = (global_pos + CHUNK_SIZE).min(stop_at);
+                self.process_buffer
+                    .extend_from_slice(&samples[global_pos..end_pos]);
+            }
+            Samples::Live(buffer) => {
+                if !buffer.pop_into(&mut self.process_buffer, CHUNK_SIZE) {
+                    return false;
+                }
+            }
+        }
 
         // Apply DSP filters in order: EQ -> Normalizer
         if self.equalizer.on {
@@ -392,8 +674,13 @@ impl Iterator for BufferedSource {
 
 impl Source for BufferedSource {
     fn current_span_len(&self) -> Option<usize> {
-        let pos = self.position_tracker.position.load(Ordering::Relaxed);
-        Some(self.samples.len() - pos)
+        match &self.samples {
+            Samples::Decoded(samples) => {
+                let pos = self.position_tracker.position.load(Ordering::Relaxed);
+                Some(self.stop_at(samples).saturating_sub(pos))
+            }
+            Samples::Live(_) => None,
+        }
     }
 
     fn channels(&self) -> u16 {
@@ -405,7 +692,10 @@ impl Source for BufferedSource {
     }
 
     fn total_duration(&self) -> Option<std::time::Duration> {
-        let frames = self.samples.len() / (self.channels as usize);
+        let Samples::Decoded(samples) = &self.samples else {
+            return None;
+        };
+        let frames = samples.len() / (self.channels as usize);
         Some(std::time::Duration::from_secs_f64(
             (frames as f64) / (self.sample_rate as f64),
         ))
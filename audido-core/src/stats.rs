@@ -0,0 +1,280 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config;
+
+/// File (inside the data directory) the statistics are stored in
+pub const STATS_FILE: &str = "stats.json";
+
+/// Share of a track that has to be heard for it to count as played; leaving
+/// earlier with Next counts as a skip
+pub const PLAYED_FRACTION: f32 = 0.5;
+
+#[derive(Error, Debug)]
+pub enum StatsError {
+    #[error("no data directory available on this platform")]
+    NoDataDir,
+    #[error("cannot access statistics file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid statistics file: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// Statistics of one file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrackStats {
+    pub play_count: u32,
+    /// Times Next was pressed before half of the track
+    pub skip_count: u32,
+    /// Seconds since the Unix epoch
+    pub last_played: Option<u64>,
+    /// Seconds actually spent listening, pauses excluded
+    pub listening_time: f64,
+}
+
+/// How listening to a track ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenEnd {
+    /// Played to the end
+    Finished,
+    /// Left with Next
+    Skipped,
+    /// Left any other way (another track picked, queue cleared, quit)
+    Interrupted,
+}
+
+/// Play statistics per file path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayStats {
+    tracks: BTreeMap<PathBuf, TrackStats>,
+}
+
+impl PlayStats {
+    /// Read the statistics from the data directory. A missing file is empty.
+    pub fn load() -> Result<Self, StatsError> {
+        let path = config::data_file(STATS_FILE).ok_or(StatsError::NoDataDir)?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load_from(&path)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, StatsError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self) -> Result<(), StatsError> {
+        let path = config::data_file(STATS_FILE).ok_or(StatsError::NoDataDir)?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), StatsError> {
+        config::write_atomic(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&TrackStats> {
+        self.tracks.get(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Record the end of listening to a track: `listened` seconds of
+    /// listening that reached `fraction` (0-1) of the track
+    pub fn record_listen(&mut self, path: &Path, listened: f64, fraction: f32, end: ListenEnd) {
+        if listened <= 0.0 && end != ListenEnd::Finished {
+            return;
+        }
+        let stats = self.tracks.entry(path.to_path_buf()).or_default();
+        stats.listening_time += listened;
+        stats.last_played = Some(unix_now());
+        if end == ListenEnd::Finished || fraction >= PLAYED_FRACTION {
+            stats.play_count += 1;
+        } else if end == ListenEnd::Skipped {
+            stats.skip_count += 1;
+        }
+    }
+
+    /// Played files, most plays first
+    pub fn most_played(&self, limit: usize) -> Vec<(&Path, &TrackStats)> {
+        let mut tracks: Vec<(&Path, &TrackStats)> = self
+            .iter()
+            .filter(|(_, stats)| stats.play_count > 0)
+            .collect();
+        tracks.sort_by(|a, b| {
+            b.1.play_count
+                .cmp(&a.1.play_count)
+                .then(b.1.last_played.cmp(&a.1.last_played))
+        });
+        tracks.truncate(limit);
+        tracks
+    }
+
+    /// Files listened to, most recent first
+    pub fn recently_played(&self, limit: usize) -> Vec<(&Path, &TrackStats)> {
+        let mut tracks: Vec<(&Path, &TrackStats)> = self
+            .iter()
+            .filter(|(_, stats)| stats.last_played.is_some())
+            .collect();
+        tracks.sort_by_key(|(_, stats)| Reverse(stats.last_played));
+        tracks.truncate(limit);
+        tracks
+    }
+
+    /// The given files that were never played to at least half
+    pub fn never_played<'a>(&self, paths: impl IntoIterator<Item = &'a Path>) -> Vec<&'a Path> {
+        let played: HashSet<&Path> = self
+            .iter()
+            .filter(|(_, stats)| stats.play_count > 0)
+            .map(|(path, _)| path)
+            .collect();
+        paths
+            .into_iter()
+            .filter(|path| !played.contains(path))
+            .collect()
+    }
+
+    /// Total listening time over all files, in seconds
+    pub fn total_listening_time(&self) -> f64 {
+        self.tracks.values().map(|stats| stats.listening_time).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &TrackStats)> {
+        self.tracks
+            .iter()
+            .map(|(path, stats)| (path.as_path(), stats))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn played(play_count: u32, last_played: u64) -> TrackStats {
+        TrackStats {
+            play_count,
+            last_played: Some(last_played),
+            ..Default::default()
+        }
+    }
+
+    fn stats_with(tracks: &[(&str, TrackStats)]) -> PlayStats {
+        PlayStats {
+            tracks: tracks
+                .iter()
+                .map(|(path, stats)| (PathBuf::from(path), stats.clone()))
+                .collect(),
+        }
+    }
+
+    fn paths<'a>(tracks: &[(&'a Path, &TrackStats)]) -> Vec<&'a str> {
+        tracks.iter().map(|(p, _)| p.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn listening_to_half_or_finishing_counts_as_a_play() {
+        let mut stats = PlayStats::default();
+        let path = Path::new("/music/a.flac");
+        stats.record_listen(path, 100.0, 0.5, ListenEnd::Skipped);
+        stats.record_listen(path, 150.0, 0.8, ListenEnd::Interrupted);
+        stats.record_listen(path, 10.0, 0.1, ListenEnd::Finished);
+
+        let track = stats.get(path).unwrap();
+        assert_eq!(track.play_count, 3);
+        assert_eq!(track.skip_count, 0);
+        assert_eq!(track.listening_time, 260.0);
+        assert!(track.last_played.is_some());
+        assert_eq!(stats.total_listening_time(), 260.0);
+    }
+
+    #[test]
+    fn only_next_before_half_counts_as_a_skip() {
+        let mut stats = PlayStats::default();
+        let path = Path::new("/music/a.flac");
+        stats.record_listen(path, 20.0, 0.1, ListenEnd::Skipped);
+        stats.record_listen(path, 20.0, 0.1, ListenEnd::Interrupted);
+
+        let track = stats.get(path).unwrap();
+        assert_eq!(track.skip_count, 1);
+        assert_eq!(track.play_count, 0);
+        assert_eq!(track.listening_time, 40.0);
+    }
+
+    #[test]
+    fn leaving_before_hearing_anything_is_not_recorded() {
+        let mut stats = PlayStats::default();
+        stats.record_listen(Path::new("/music/a.flac"), 0.0, 0.0, ListenEnd::Skipped);
+        stats.record_listen(Path::new("/music/b.flac"), 0.0, 0.0, ListenEnd::Interrupted);
+        assert!(stats.is_empty());
+    }
+
+    #[test]
+    fn most_played_orders_by_count_then_recency() {
+        let stats = stats_with(&[
+            ("/a", played(2, 100)),
+            ("/b", played(5, 50)),
+            ("/c", played(2, 300)),
+            ("/skipped", played(0, 400)),
+        ]);
+        assert_eq!(paths(&stats.most_played(10)), ["/b", "/c", "/a"]);
+        assert_eq!(paths(&stats.most_played(1)), ["/b"]);
+    }
+
+    #[test]
+    fn recently_played_includes_skipped_tracks() {
+        let stats = stats_with(&[
+            ("/a", played(2, 100)),
+            ("/b", played(5, 50)),
+            ("/skipped", played(0, 400)),
+            ("/never", TrackStats::default()),
+        ]);
+        assert_eq!(paths(&stats.recently_played(10)), ["/skipped", "/a", "/b"]);
+        assert_eq!(paths(&stats.recently_played(2)), ["/skipped", "/a"]);
+    }
+
+    #[test]
+    fn never_played_keeps_unplayed_and_skipped_files_in_order() {
+        let stats = stats_with(&[("/a", played(1, 100)), ("/skipped", played(0, 200))]);
+        let library = [Path::new("/new"), Path::new("/a"), Path::new("/skipped")];
+        assert_eq!(
+            stats.never_played(library),
+            [Path::new("/new"), Path::new("/skipped")]
+        );
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("audido-stats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(STATS_FILE);
+
+        let stats = stats_with(&[("/a", played(3, 100)), ("/b", TrackStats::default())]);
+        stats.save_to(&path).unwrap();
+        let loaded = PlayStats::load_from(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(Path::new("/a")), stats.get(Path::new("/a")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
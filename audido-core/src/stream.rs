@@ -0,0 +1,798 @@
+//! Internet radio: HTTP audio streams (Icecast/Shoutcast) decoded as they
+//! arrive, with ICY title metadata and reconnection when the connection drops.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    net::TcpStream,
+    path::Path,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use rodio::{Decoder, Source};
+use thiserror::Error;
+use url::Url;
+
+use crate::metadata::{AudioMetadata, ChannelLayout};
+
+/// Audio buffered before playback starts, and again after an underrun
+const PREBUFFER_SECONDS: f32 = 2.0;
+/// Most audio decoded ahead of playback
+const MAX_BUFFER_SECONDS: f32 = 10.0;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// A stream that sends nothing for this long counts as dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_REDIRECTS: usize = 5;
+const RECONNECT_ATTEMPTS: u32 = 8;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// Samples handed to the buffer at once
+const PUSH_CHUNK: usize = 4096;
+
+#[derive(Error, Debug)]
+pub enum StreamError {
+    #[error("invalid stream URL: {0}")]
+    InvalidUrl(String),
+    #[error("{0} streams are not supported, only http://")]
+    UnsupportedScheme(String),
+    #[error("connection failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid HTTP response: {0}")]
+    Http(String),
+    #[error("server answered {0}")]
+    Status(u16),
+    #[error("too many redirects")]
+    TooManyRedirects,
+    #[error("cannot decode stream: {0}")]
+    Decode(String),
+}
+
+/// What a live stream is doing, for the UI
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamState {
+    /// Filling the buffer before playing, at the start or after an underrun
+    Buffering,
+    Playing,
+    /// The connection dropped, trying again
+    Reconnecting {
+        attempt: u32,
+    },
+    /// Gave up; playback ends once the buffer runs dry
+    Failed(String),
+}
+
+/// News from a live stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// The station announced a new `StreamTitle`
+    Title(String),
+    State(StreamState),
+}
+
+/// Whether a queue entry is a stream URL rather than a file
+pub fn is_stream(path: &Path) -> bool {
+    path.to_str().is_some_and(crate::remote::is_url)
+}
+
+/// Put a `StreamTitle` into the metadata. Stations usually send
+/// "Artist - Title", which is split when present.
+pub fn apply_stream_title(metadata: &mut AudioMetadata, stream_title: &str) {
+    match stream_title.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+            metadata.author = Some(artist.trim().to_string());
+            metadata.title = Some(title.trim().to_string());
+        }
+        _ => {
+            metadata.author = None;
+            metadata.title = Some(stream_title.trim().to_string());
+        }
+    }
+}
+
+/// Samples decoded ahead of playback, shared between the decoding thread and
+/// the audio source. Playback waits (playing silence) until the prebuffer is
+/// full, both at the start and whenever the buffer runs dry.
+pub struct StreamBuffer {
+    samples: Mutex<VecDeque<f32>>,
+    capacity: usize,
+    prebuffer: usize,
+    buffering: AtomicBool,
+    /// No more samples will come
+    finished: AtomicBool,
+}
+
+impl StreamBuffer {
+    fn new(sample_rate: u32, channels: u16) -> Self {
+        let samples_per_second = sample_rate as f32 * channels as f32;
+        Self {
+            samples: Mutex::new(VecDeque::new()),
+            capacity: (samples_per_second * MAX_BUFFER_SECONDS) as usize,
+            prebuffer: (samples_per_second * PREBUFFER_SECONDS) as usize,
+            buffering: AtomicBool::new(true),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    /// Append samples unless the buffer is full
+    fn try_push(&self, chunk: &[f32]) -> bool {
+        let mut samples = self.samples.lock().expect("stream buffer mutex poisoned");
+        if samples.len() + chunk.len() > self.capacity {
+            return false;
+        }
+        samples.extend(chunk);
+        true
+    }
+
+    fn finish(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_buffering(&self) -> bool {
+        self.buffering.load(Ordering::Relaxed)
+    }
+
+    /// Move up to `count` samples into `out`, padding with silence while
+    /// buffering. Returns false once the stream ended and the buffer is empty.
+    pub fn pop_into(&self, out: &mut Vec<f32>, count: usize) -> bool {
+        let mut samples = self.samples.lock().expect("stream buffer mutex poisoned");
+        let finished = self.finished.load(Ordering::Relaxed);
+        if finished && samples.is_empty() {
+            return false;
+        }
+
+        if self.buffering.load(Ordering::Relaxed) {
+            if samples.len() >= self.prebuffer || finished {
+                self.buffering.store(false, Ordering::Relaxed);
+            } else {
+                out.resize(out.len() + count, 0.0);
+                return true;
+            }
+        }
+
+        let available = samples.len().min(count);
+        out.extend(samples.drain(..available));
+        if available < count && !finished {
+            // Underrun: play silence until the prebuffer is full again
+            self.buffering.store(true, Ordering::Relaxed);
+            out.resize(out.len() + count - available, 0.0);
+        }
+        true
+    }
+}
+
+/// An open stream: its format, the buffer its decoding thread fills and the
+/// events it reports
+pub struct LiveStream {
+    pub buffer: Arc<StreamBuffer>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Station name and genre from the ICY headers
+    pub metadata: AudioMetadata,
+    events: Receiver<StreamEvent>,
+    /// Last news about the connection: `Playing` while connected
+    connection: StreamState,
+    /// Last state reported by `poll`
+    state: Option<StreamState>,
+}
+
+impl LiveStream {
+    /// Connect to an HTTP stream and start decoding it on a background thread.
+    /// The thread stops once the returned stream and every source playing it
+    /// are dropped.
+    pub fn open(location: &str) -> Result<Self, StreamError> {
+        let url = Url::parse(location).map_err(|e| StreamError::InvalidUrl(e.to_string()))?;
+        let (events_tx, events) = unbounded();
+        let (head, decoder) = connect(&url, &events_tx)?;
+        let sample_rate = decoder.sample_rate();
+        let channels = decoder.channels();
+
+        let metadata = AudioMetadata {
+            format: head.format().to_string(),
+            sample_rate,
+            num_channels: channels,
+            channel_layout: ChannelLayout::from_channels(channels),
+            full_file_path: location.to_string(),
+            title: head.header("icy-name").map(str::to_string),
+            genre: head.header("icy-genre").map(str::to_string),
+            // The station, so the title can change with every song
+            album: head.header("icy-name").map(str::to_string),
+            ..Default::default()
+        };
+        log::info!(
+            "Connected to stream {} ({}, {} Hz, {} channels)",
+            location,
+            metadata.format,
+            sample_rate,
+            channels
+        );
+
+        let buffer = Arc::new(StreamBuffer::new(sample_rate, channels));
+        let weak = Arc::downgrade(&buffer);
+        thread::spawn(move || {
+            decode_stream(url, decoder, (sample_rate, channels), weak, events_tx);
+        });
+
+        Ok(Self {
+            buffer,
+            sample_rate,
+            channels,
+            metadata,
+            events,
+            connection: StreamState::Playing,
+            state: None,
+        })
+    }
+
+    /// Events since the last call. State changes come from the connection
+    /// while it is down, else from the buffer (buffering or playing).
+    pub fn poll(&mut self) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        for event in self.events.try_iter() {
+            match event {
+                StreamEvent::State(state) => self.connection = state,
+                title => events.push(title),
+            }
+        }
+        let state = match &self.connection {
+            StreamState::Reconnecting { .. } | StreamState::Failed(_) => self.connection.clone(),
+            _ if self.buffer.is_buffering() => StreamState::Buffering,
+            _ => StreamState::Playing,
+        };
+        if self.state.as_ref() != Some(&state) {
+            self.state = Some(state.clone());
+            events.push(StreamEvent::State(state));
+        }
+        events
+    }
+}
+
+/// Headers of a stream's HTTP response
+struct ResponseHead {
+    /// Lowercase header names
+    headers: HashMap<String, String>,
+}
+
+impl ResponseHead {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Bytes of audio between ICY metadata blocks
+    fn metaint(&self) -> Option<usize> {
+        self.header("icy-metaint")
+            .and_then(|value| value.parse().ok())
+            .filter(|&n| n > 0)
+    }
+
+    /// File extension for the content type, a hint for the decoder
+    fn format(&self) -> &'static str {
+        let content_type = self.header("content-type").unwrap_or("");
+        match content_type.split(';').next().unwrap_or("").trim() {
+            "audio/mpeg" | "audio/mp3" => "mp3",
+            "audio/aac" | "audio/aacp" | "audio/x-aac" => "aac",
+            "audio/mp4" | "audio/x-m4a" => "m4a",
+            "audio/ogg" | "application/ogg" | "audio/vorbis" => "ogg",
+            "audio/flac" | "audio/x-flac" => "flac",
+            "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+            _ => "stream",
+        }
+    }
+}
+
+/// Send a GET request and read the response head, following redirects.
+/// Returns the head and the reader positioned at the body.
+fn request(url: &Url) -> Result<(ResponseHead, BufReader<TcpStream>), StreamError> {
+    let mut url = url.clone();
+    for _ in 0..=MAX_REDIRECTS {
+        if url.scheme() != "http" {
+            return Err(StreamError::UnsupportedScheme(url.scheme().to_string()));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| StreamError::InvalidUrl(url.to_string()))?;
+        // IPv6 hosts come bracketed, which only the URL knows to resolve
+        let addr = url
+            .socket_addrs(|| Some(80))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| StreamError::InvalidUrl(url.to_string()))?;
+        let host_header = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        // HTTP/1.0 keeps the body free of chunked encoding
+        write!(
+            stream,
+            "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: audido/{}\r\nAccept: */*\r\nIcy-MetaData: 1\r\nConnection: close\r\n\r\n",
+            path,
+            host_header,
+            env!("CARGO_PKG_VERSION")
+        )?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line)?;
+        // Shoutcast v1 answers "ICY 200 OK"
+        let status: u16 = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| StreamError::Http(status_line.trim().to_string()))?;
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        match status {
+            200..=299 => return Ok((ResponseHead { headers }, reader)),
+            300..=399 => {
+                let location = headers
+                    .get("location")
+                    .ok_or_else(|| StreamError::Http("redirect without location".to_string()))?;
+                url = url
+                    .join(location)
+                    .map_err(|e| StreamError::InvalidUrl(e.to_string()))?;
+                log::debug!("Stream redirected to {}", url);
+            }
+            _ => return Err(StreamError::Status(status)),
+        }
+    }
+    Err(StreamError::TooManyRedirects)
+}
+
+type StreamDecoder = Decoder<Unseekable<IcyReader<BufReader<TcpStream>>>>;
+
+/// Connect and set up a decoder for the body
+fn connect(
+    url: &Url,
+    events: &Sender<StreamEvent>,
+) -> Result<(ResponseHead, StreamDecoder), StreamError> {
+    let (head, reader) = request(url)?;
+    let body = IcyReader::new(reader, head.metaint(), events.clone());
+    let mut builder = Decoder::builder()
+        .with_data(Unseekable::new(body))
+        .with_seekable(false);
+    if head.format() != "stream" {
+        builder = builder.with_hint(head.format());
+    }
+    if let Some(content_type) = head.header("content-type") {
+        builder = builder.with_mime_type(content_type);
+    }
+    let decoder = builder
+        .build()
+        .map_err(|e| StreamError::Decode(e.to_string()))?;
+    Ok((head, decoder))
+}
+
+/// Decoding thread: fill the buffer, reconnecting with backoff when the
+/// connection drops, until the buffer's owners are gone
+fn decode_stream(
+    url: Url,
+    mut decoder: StreamDecoder,
+    format: (u32, u16),
+    buffer: Weak<StreamBuffer>,
+    events: Sender<StreamEvent>,
+) {
+    let mut chunk = Vec::with_capacity(PUSH_CHUNK);
+    loop {
+        for sample in decoder.by_ref() {
+            chunk.push(sample);
+            if chunk.len() == PUSH_CHUNK && !push_chunk(&buffer, &mut chunk) {
+                return;
+            }
+        }
+        if !push_chunk(&buffer, &mut chunk) {
+            return;
+        }
+
+        log::warn!("Stream {} dropped", url);
+        match reconnect(&url, format, &buffer, &events) {
+            Some(next) => decoder = next,
+            None => {
+                if let Some(buffer) = buffer.upgrade() {
+                    buffer.finish();
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Hand samples to the buffer, waiting while it's full. False once nobody
+/// plays the stream anymore.
+fn push_chunk(buffer: &Weak<StreamBuffer>, chunk: &mut Vec<f32>) -> bool {
+    if chunk.is_empty() {
+        return true;
+    }
+    loop {
+        let Some(buffer) = buffer.upgrade() else {
+            return false;
+        };
+        if buffer.try_push(chunk) {
+            chunk.clear();
+            return true;
+        }
+        drop(buffer);
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Try to get the stream back, waiting longer after each failed attempt.
+/// `None` when giving up or when nobody plays the stream anymore.
+fn reconnect(
+    url: &Url,
+    format: (u32, u16),
+    buffer: &Weak<StreamBuffer>,
+    events: &Sender<StreamEvent>,
+) -> Option<StreamDecoder> {
+    let mut delay = RECONNECT_BASE_DELAY;
+    let mut last_error = String::new();
+    for attempt in 1..=RECONNECT_ATTEMPTS {
+        let _ = events.send(StreamEvent::State(StreamState::Reconnecting { attempt }));
+        log::info!(
+            "Reconnecting to {} in {:?} (attempt {})",
+            url,
+            delay,
+            attempt
+        );
+        if !sleep_while_used(delay, buffer) {
+            return None;
+        }
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+
+        match connect(url, events) {
+            Ok((_, decoder)) if (decoder.sample_rate(), decoder.channels()) == format => {
+                log::info!("Reconnected to {}", url);
+                let _ = events.send(StreamEvent::State(StreamState::Playing));
+                return Some(decoder);
+            }
+            Ok((_, decoder)) => {
+                // The source plays at the rate it was created with
+                let message = format!(
+                    "stream format changed to {} Hz, {} channels",
+                    decoder.sample_rate(),
+                    decoder.channels()
+                );
+                log::error!("Cannot resume {}: {}", url, message);
+                let _ = events.send(StreamEvent::State(StreamState::Failed(message)));
+                return None;
+            }
+            Err(e) => {
+                log::warn!("Reconnecting to {} failed: {}", url, e);
+                last_error = e.to_string();
+            }
+        }
+    }
+    let _ = events.send(StreamEvent::State(StreamState::Failed(last_error)));
+    None
+}
+
+/// Sleep in short steps, stopping early when nobody plays the stream anymore
+fn sleep_while_used(duration: Duration, buffer: &Weak<StreamBuffer>) -> bool {
+    const STEP: Duration = Duration::from_millis(100);
+    let mut slept = Duration::ZERO;
+    while slept < duration {
+        if buffer.strong_count() == 0 {
+            return false;
+        }
+        thread::sleep(STEP);
+        slept += STEP;
+    }
+    buffer.strong_count() > 0
+}
+
+/// Strips the ICY metadata blocks interleaved with the audio, reporting each
+/// new `StreamTitle`
+struct IcyReader<R> {
+    inner: R,
+    metaint: Option<usize>,
+    /// Audio bytes left before the next metadata block
+    until_metadata: usize,
+    last_title: Option<String>,
+    events: Sender<StreamEvent>,
+}
+
+impl<R: Read> IcyReader<R> {
+    fn new(inner: R, metaint: Option<usize>, events: Sender<StreamEvent>) -> Self {
+        Self {
+            inner,
+            metaint,
+            until_metadata: metaint.unwrap_or(0),
+            last_title: None,
+            events,
+        }
+    }
+
+    fn read_metadata(&mut self) -> io::Result<()> {
+        let mut length = [0u8; 1];
+        self.inner.read_exact(&mut length)?;
+        let mut block = vec![0u8; length[0] as usize * 16];
+        self.inner.read_exact(&mut block)?;
+        if let Some(title) = parse_stream_title(&block)
+            && self.last_title.as_ref() != Some(&title)
+        {
+            log::info!("Stream title: {}", title);
+            let _ = self.events.send(StreamEvent::Title(title.clone()));
+            self.last_title = Some(title);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(metaint) = self.metaint else {
+            return self.inner.read(buf);
+        };
+        if self.until_metadata == 0 {
+            self.read_metadata()?;
+            self.until_metadata = metaint;
+        }
+        let len = buf.len().min(self.until_metadata);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.until_metadata -= read;
+        Ok(read)
+    }
+}
+
+/// `StreamTitle='Artist - Title';` from an ICY metadata block
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    let end = rest
+        .find("';")
+        .unwrap_or_else(|| rest.trim_end_matches('\0').len());
+    let title = rest[..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// A network body for the decoder, which needs `Seek` but is told not to seek.
+/// Only reports the position.
+struct Unseekable<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R> Unseekable<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+}
+
+impl<R: Read> Read for Unseekable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R> Seek for Unseekable<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Current(0) => Ok(self.position),
+            SeekFrom::Start(offset) if offset == self.position => Ok(self.position),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek in a live stream",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    /// Answer one connection per canned response on `bind`, handing back the
+    /// request heads. `None` when the address can't be bound here.
+    fn serve(
+        bind: &str,
+        responses: Vec<Vec<u8>>,
+    ) -> Option<(Url, thread::JoinHandle<Vec<String>>)> {
+        let listener = TcpListener::bind(bind).ok()?;
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().ok()?)).ok()?;
+        let server = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    let mut head = String::new();
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim_end().is_empty() {
+                            break;
+                        }
+                        head.push_str(&line);
+                    }
+                    reader.get_mut().write_all(&response).unwrap();
+                    head
+                })
+                .collect()
+        });
+        Some((url, server))
+    }
+
+    /// A short mono 16-bit WAV file
+    fn wav(samples: usize) -> Vec<u8> {
+        let data_len = (samples * 2) as u32;
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&8000u32.to_le_bytes());
+        out.extend_from_slice(&16000u32.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        for i in 0..samples {
+            out.extend_from_slice(&((i % 64) as i16 * 256).to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn request_follows_redirects_and_accepts_icy_status() {
+        let (url, server) = serve(
+            "127.0.0.1:0",
+            vec![
+                b"HTTP/1.1 302 Found\r\nLocation: /live?bitrate=128\r\n\r\n".to_vec(),
+                b"ICY 200 OK\r\nContent-Type: audio/mpeg\r\nicy-metaint: 16000\r\n\r\nbody"
+                    .to_vec(),
+            ],
+        )
+        .unwrap();
+        let port = url.port().unwrap();
+
+        let (head, mut reader) = request(&url.join("/radio").unwrap()).unwrap();
+        assert_eq!(head.format(), "mp3");
+        assert_eq!(head.metaint(), Some(16000));
+        let mut body = String::new();
+        reader.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "body");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /radio HTTP/1.0\r\n"));
+        assert!(requests[1].starts_with("GET /live?bitrate=128 HTTP/1.0\r\n"));
+        for request in &requests {
+            assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", port)));
+        }
+    }
+
+    #[test]
+    fn request_reaches_ipv6_hosts() {
+        // Not every machine has IPv6 loopback
+        let Some((url, server)) = serve(
+            "[::1]:0",
+            vec![b"HTTP/1.0 200 OK\r\nContent-Type: audio/ogg\r\n\r\n".to_vec()],
+        ) else {
+            return;
+        };
+
+        let (head, _) = request(&url).unwrap();
+        assert_eq!(head.format(), "ogg");
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains(&format!("Host: [::1]:{}\r\n", url.port().unwrap())));
+    }
+
+    #[test]
+    fn request_reports_error_status() {
+        let (url, _server) = serve(
+            "127.0.0.1:0",
+            vec![b"HTTP/1.0 404 Not Found\r\n\r\n".to_vec()],
+        )
+        .unwrap();
+        assert!(matches!(request(&url), Err(StreamError::Status(404))));
+    }
+
+    #[test]
+    fn icy_reader_strips_metadata_blocks() {
+        fn block(text: &str) -> Vec<u8> {
+            let mut block = text.as_bytes().to_vec();
+            block.resize(text.len().div_ceil(16) * 16, 0);
+            let mut out = vec![(block.len() / 16) as u8];
+            out.extend(block);
+            out
+        }
+        let mut stream = b"abcd".to_vec();
+        stream.extend(block("StreamTitle='Artist - Song';"));
+        stream.extend_from_slice(b"efgh");
+        // An empty block, then the same title again
+        stream.push(0);
+        stream.extend_from_slice(b"ijkl");
+        stream.extend(block("StreamTitle='Artist - Song';StreamUrl='';"));
+        stream.extend_from_slice(b"mn");
+
+        let (tx, rx) = unbounded();
+        let mut reader = IcyReader::new(stream.as_slice(), Some(4), tx);
+        let mut audio = Vec::new();
+        reader.read_to_end(&mut audio).unwrap();
+
+        assert_eq!(audio, b"abcdefghijklmn");
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            [StreamEvent::Title("Artist - Song".to_string())]
+        );
+    }
+
+    #[test]
+    fn parse_stream_title_reads_the_quoted_title() {
+        assert_eq!(
+            parse_stream_title(b"StreamTitle='Artist - It's Live';StreamUrl='x';"),
+            Some("Artist - It's Live".to_string())
+        );
+        assert_eq!(
+            parse_stream_title(b"StreamTitle='No terminator\0\0\0"),
+            Some("No terminator".to_string())
+        );
+        assert_eq!(parse_stream_title(b"StreamTitle='';\0\0"), None);
+        assert_eq!(parse_stream_title(b"StreamUrl='http://example.com';"), None);
+    }
+
+    #[test]
+    fn live_stream_reconnects_after_a_drop() {
+        let mut response =
+            b"HTTP/1.0 200 OK\r\nContent-Type: audio/wav\r\nicy-name: Test FM\r\n\r\n".to_vec();
+        response.extend(wav(800));
+        let (url, server) = serve("127.0.0.1:0", vec![response.clone(), response]).unwrap();
+
+        let stream = LiveStream::open(url.as_str()).unwrap();
+        assert_eq!((stream.sample_rate, stream.channels), (8000, 1));
+        assert_eq!(stream.metadata.title.as_deref(), Some("Test FM"));
+
+        // The body ends after 0.1 s: the connection counts as dropped and
+        // comes back on the first attempt
+        let mut states = Vec::new();
+        while states.last() != Some(&StreamState::Playing) {
+            match stream.events.recv_timeout(Duration::from_secs(10)) {
+                Ok(StreamEvent::State(state)) => states.push(state),
+                Ok(_) => {}
+                Err(e) => panic!("no reconnection, got {:?}: {}", states, e),
+            }
+        }
+        assert_eq!(
+            states,
+            [
+                StreamState::Reconnecting { attempt: 1 },
+                StreamState::Playing
+            ]
+        );
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,353 @@
+//! Editing the tags of audio files: reading the editable fields and writing
+//! changes back through lofty.
+
+use std::path::Path;
+
+use lofty::{
+    config::WriteOptions,
+    file::TaggedFileExt,
+    probe::Probe,
+    tag::{Accessor, ItemKey, Tag, TagExt},
+};
+use strum::EnumIter;
+use thiserror::Error;
+
+use crate::{analysis::Analysis, metadata::AudioMetadata};
+
+#[derive(Error, Debug)]
+pub enum TagError {
+    #[error("cannot read or write tags: {0}")]
+    Lofty(#[from] lofty::error::LoftyError),
+    #[error("{field} must be a number, not '{value}'")]
+    NotANumber { field: TagField, value: String },
+}
+
+/// A tag field the editor can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, strum::Display)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    #[strum(to_string = "Track")]
+    TrackNumber,
+    Year,
+    Comment,
+}
+
+impl TagField {
+    /// Whether the field differs from track to track, so setting one value on
+    /// a whole album makes no sense
+    pub fn is_per_track(&self) -> bool {
+        matches!(self, TagField::Title | TagField::TrackNumber)
+    }
+
+    /// Whether the field holds a number
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, TagField::TrackNumber | TagField::Year)
+    }
+}
+
+/// The editable fields of a file's tags, as text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagValues {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub track_number: Option<String>,
+    pub year: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl TagValues {
+    pub fn get(&self, field: TagField) -> Option<&str> {
+        match field {
+            TagField::Title => self.title.as_deref(),
+            TagField::Artist => self.artist.as_deref(),
+            TagField::Album => self.album.as_deref(),
+            TagField::Genre => self.genre.as_deref(),
+            TagField::TrackNumber => self.track_number.as_deref(),
+            TagField::Year => self.year.as_deref(),
+            TagField::Comment => self.comment.as_deref(),
+        }
+    }
+}
+
+/// A new value for one field; `None` removes the field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagChange {
+    pub field: TagField,
+    pub value: Option<String>,
+}
+
+impl TagChange {
+    /// Change `field` to `value`, removing it when the value is blank
+    pub fn new(field: TagField, value: &str) -> Self {
+        let value = value.trim();
+        Self {
+            field,
+            value: (!value.is_empty()).then(|| value.to_string()),
+        }
+    }
+
+    /// Check that numeric fields hold numbers, before any file is touched
+    pub fn validate(&self) -> Result<(), TagError> {
+        self.number().map(|_| ())
+    }
+
+    /// Show the change in metadata read before it was written
+    pub fn apply_to(&self, metadata: &mut AudioMetadata) {
+        let field = match self.field {
+            TagField::Title => &mut metadata.title,
+            TagField::Artist => &mut metadata.author,
+            TagField::Album => &mut metadata.album,
+            TagField::Genre => &mut metadata.genre,
+            TagField::Comment => &mut metadata.comment,
+            TagField::TrackNumber => {
+                metadata.track_number = self.number().ok().flatten();
+                return;
+            }
+            TagField::Year => {
+                metadata.year = self.number().ok().flatten();
+                return;
+            }
+        };
+        field.clone_from(&self.value);
+    }
+
+    fn number(&self) -> Result<Option<u32>, TagError> {
+        match &self.value {
+            Some(value) if self.field.is_numeric() => {
+                value.parse().map(Some).map_err(|_| TagError::NotANumber {
+                    field: self.field,
+                    value: value.clone(),
+                })
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The editable fields of a file's tags; all empty when it has none
+pub fn read_tag_values(path: &Path) -> Result<TagValues, TagError> {
+    let tagged_file = Probe::open(path)?.read()?;
+    let Some(tag) = tagged_file.primary_tag().or(tagged_file.first_tag()) else {
+        return Ok(TagValues::default());
+    };
+    Ok(TagValues {
+        title: tag.title().map(|s| s.to_string()),
+        artist: tag.artist().map(|s| s.to_string()),
+        album: tag.album().map(|s| s.to_string()),
+        genre: tag.genre().map(|s| s.to_string()),
+        track_number: tag.track().map(|n| n.to_string()),
+        year: tag.year().map(|n| n.to_string()),
+        comment: tag.comment().map(|s| s.to_string()),
+    })
+}
+
+/// Apply the changes to the file's main tag, creating it when the file has none
+pub fn write_tags(path: &Path, changes: &[TagChange]) -> Result<(), TagError> {
+    for change in changes {
+        change.validate()?;
+    }
+    edit_primary_tag(path, |tag| {
+        for change in changes {
+            apply_change(tag, change);
+        }
+    })
+}
+
+/// Keep analysis results in the file's main tag: the key (`TKEY`,
+/// `INITIALKEY`), the tempo (`TBPM`, `BPM`, `tmpo`) and the loudness as
+/// ReplayGain track gain and peak
+pub fn write_analysis(path: &Path, analysis: &Analysis) -> Result<(), TagError> {
+    edit_primary_tag(path, |tag| {
+        if let Some(key) = analysis.key {
+            tag.insert_text(ItemKey::InitialKey, key.to_string());
+        }
+        if let Some(bpm) = analysis.bpm {
+            // ID3v2 and MP4 only hold whole beats, Vorbis comments take decimals;
+            // each format keeps the one it has a field for
+            tag.insert_text(ItemKey::IntegerBpm, format!("{:.0}", bpm));
+            tag.insert_text(ItemKey::Bpm, format!("{:.1}", bpm));
+        }
+        if let Some(gain) = analysis.replay_gain() {
+            tag.insert_text(ItemKey::ReplayGainTrackGain, format!("{:.2} dB", gain));
+        }
+        if let Some(peak) = analysis.peak {
+            tag.insert_text(ItemKey::ReplayGainTrackPeak, format!("{:.6}", peak));
+        }
+    })
+}
+
+/// Change the file's main tag, created when the file has none, and save it
+fn edit_primary_tag(path: &Path, edit: impl FnOnce(&mut Tag)) -> Result<(), TagError> {
+    let mut tagged_file = Probe::open(path)?.read()?;
+    let tag_type = tagged_file.primary_tag_type();
+    if tagged_file.tag(tag_type).is_none() {
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    let Some(tag) = tagged_file.tag_mut(tag_type) else {
+        return Ok(());
+    };
+    edit(tag);
+    tag.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+fn apply_change(tag: &mut Tag, change: &TagChange) {
+    // Numbers were validated before any file was touched
+    let number = change.number().ok().flatten();
+    match (change.field, change.value.clone()) {
+        (TagField::Title, Some(value)) => tag.set_title(value),
+        (TagField::Title, None) => tag.remove_title(),
+        (TagField::Artist, Some(value)) => tag.set_artist(value),
+        (TagField::Artist, None) => tag.remove_artist(),
+        (TagField::Album, Some(value)) => tag.set_album(value),
+        (TagField::Album, None) => tag.remove_album(),
+        (TagField::Genre, Some(value)) => tag.set_genre(value),
+        (TagField::Genre, None) => tag.remove_genre(),
+        (TagField::Comment, Some(value)) => tag.set_comment(value),
+        (TagField::Comment, None) => tag.remove_comment(),
+        (TagField::TrackNumber, _) => match number {
+            Some(track) => tag.set_track(track),
+            None => tag.remove_track(),
+        },
+        (TagField::Year, _) => match number {
+            Some(year) => tag.set_year(year),
+            None => tag.remove_year(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::{metadata::MusicalSongKey, source::AudioPlaybackData, testing};
+
+    fn test_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("audido-tags-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("track.wav");
+        fs::write(&path, testing::wav(8000, 8000)).unwrap();
+        path
+    }
+
+    fn read_tags(path: &Path) -> AudioMetadata {
+        AudioPlaybackData::read_tags(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn written_tags_read_back_and_untouched_fields_stay() {
+        let path = test_file("edit");
+        assert_eq!(read_tag_values(&path).unwrap(), TagValues::default());
+
+        write_tags(
+            &path,
+            &[
+                TagChange::new(TagField::Title, "First"),
+                TagChange::new(TagField::Artist, "Artist"),
+                TagChange::new(TagField::Album, "Album"),
+                TagChange::new(TagField::TrackNumber, "3"),
+                TagChange::new(TagField::Year, "1999"),
+                TagChange::new(TagField::Comment, "Remove me"),
+            ],
+        )
+        .unwrap();
+        // A field the editor doesn't show
+        edit_primary_tag(&path, |tag| {
+            tag.insert_text(ItemKey::Composer, "Composer".to_string());
+        })
+        .unwrap();
+
+        write_tags(
+            &path,
+            &[
+                TagChange::new(TagField::Title, "Second"),
+                TagChange::new(TagField::Comment, "  "),
+            ],
+        )
+        .unwrap();
+        let values = read_tag_values(&path).unwrap();
+        assert_eq!(
+            values,
+            TagValues {
+                title: Some("Second".to_string()),
+                artist: Some("Artist".to_string()),
+                album: Some("Album".to_string()),
+                genre: None,
+                track_number: Some("3".to_string()),
+                year: Some("1999".to_string()),
+                comment: None,
+            }
+        );
+        assert_eq!(read_tags(&path).composer.as_deref(), Some("Composer"));
+        // The audio is still there
+        assert!(AudioPlaybackData::load(path.to_str().unwrap()).is_ok());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn invalid_numbers_leave_the_file_untouched() {
+        let path = test_file("invalid");
+        let before = fs::read(&path).unwrap();
+        let error = write_tags(
+            &path,
+            &[
+                TagChange::new(TagField::Title, "Title"),
+                TagChange::new(TagField::Year, "nineteen"),
+            ],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            TagError::NotANumber {
+                field: TagField::Year,
+                ..
+            }
+        ));
+        assert_eq!(fs::read(&path).unwrap(), before);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn analysis_results_read_back_without_touching_other_tags() {
+        let path = test_file("analysis");
+        write_tags(
+            &path,
+            &[
+                TagChange::new(TagField::Title, "Title"),
+                TagChange::new(TagField::Genre, "House"),
+            ],
+        )
+        .unwrap();
+
+        let analysis = Analysis {
+            key: Some(MusicalSongKey::AMin),
+            bpm: Some(124.4),
+            loudness: Some(-9.5),
+            peak: Some(0.891251),
+            waveform: Vec::new(),
+        };
+        write_analysis(&path, &analysis).unwrap();
+
+        let metadata = read_tags(&path);
+        assert_eq!(metadata.key, Some(MusicalSongKey::AMin));
+        let bpm = metadata.bpm.unwrap();
+        assert!((bpm - 124.4).abs() <= 0.5, "{bpm}");
+        assert!((metadata.loudness.unwrap() - -9.5).abs() < 0.01);
+        assert!((metadata.peak.unwrap() - 0.891251).abs() < 1e-5);
+        assert_eq!(metadata.title.as_deref(), Some("Title"));
+        assert_eq!(metadata.genre.as_deref(), Some("House"));
+
+        // Nothing found leaves the tags as they were
+        write_analysis(&path, &Analysis::default()).unwrap();
+        assert_eq!(read_tags(&path).key, Some(MusicalSongKey::AMin));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
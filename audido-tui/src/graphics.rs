@@ -0,0 +1,326 @@
+//! Cover art on the terminal: Unicode half blocks everywhere, or real pixels
+//! through the kitty graphics protocol or sixel where the terminal has them.
+//!
+//! Half blocks are ordinary cells and go through ratatui. Kitty and sixel images
+//! are written straight to the terminal after each frame (`flush`), over cells
+//! ratatui was told to skip.
+
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use audido_core::cover::CoverArt;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ratatui::{
+    Frame, Terminal,
+    backend::Backend,
+    buffer::Buffer,
+    crossterm::{cursor::MoveTo, queue, terminal},
+    layout::{Rect, Size},
+    style::Color,
+};
+
+use crate::states::CoverState;
+
+/// Image id used for the cover with the kitty protocol
+const KITTY_IMAGE_ID: u32 = 31;
+
+/// Bytes of base64 per kitty escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Cell size assumed when the terminal doesn't report its pixel size
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+/// How cover art is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// `▀` cells with the upper pixel as foreground and the lower as background
+    HalfBlocks,
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    /// The best protocol the terminal is known to support. `AUDIDO_GRAPHICS`
+    /// (`kitty`, `sixel` or `halfblocks`) overrides the guess.
+    pub fn detect() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        match var("AUDIDO_GRAPHICS").to_lowercase().as_str() {
+            "kitty" => return GraphicsProtocol::Kitty,
+            "sixel" => return GraphicsProtocol::Sixel,
+            "halfblocks" => return GraphicsProtocol::HalfBlocks,
+            _ => {}
+        }
+
+        // tmux and screen would need escape passthrough
+        if !var("TMUX").is_empty() || var("TERM").starts_with("screen") {
+            return GraphicsProtocol::HalfBlocks;
+        }
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+        if !var("KITTY_WINDOW_ID").is_empty()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            GraphicsProtocol::Kitty
+        } else if term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.contains("sixel")
+            || program == "contour"
+        {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::HalfBlocks
+        }
+    }
+}
+
+/// Draw the cover centered in `area`, as large as fits while keeping its
+/// aspect ratio
+pub fn render_cover(frame: &mut Frame, area: Rect, art: &CoverArt, cover: &CoverState) {
+    let cell = cell_size();
+    let target = fit(area, art, cell);
+    if target.is_empty() {
+        return;
+    }
+
+    let buf = frame.buffer_mut();
+    match cover.protocol {
+        GraphicsProtocol::HalfBlocks => draw_half_blocks(buf, target, art),
+        GraphicsProtocol::Kitty | GraphicsProtocol::Sixel => {
+            // The image is written over these cells after the frame
+            for y in target.top()..target.bottom() {
+                for x in target.left()..target.right() {
+                    buf[(x, y)].set_skip(true);
+                }
+            }
+            cover.placement.set(Some(target));
+        }
+    }
+}
+
+/// Write the cover placed during the last frame, removing the previous one.
+/// Images are only sent again when the art, its place or the terminal size changed.
+pub fn flush<B: Backend + Write>(
+    terminal: &mut Terminal<B>,
+    cover: &mut CoverState,
+) -> io::Result<()> {
+    let size = terminal.size().unwrap_or_default();
+    let wanted = cover
+        .placement
+        .get()
+        .zip(cover.art.as_ref())
+        .map(|(rect, art)| (rect, Arc::as_ptr(art) as usize, size));
+    if wanted == cover.shown {
+        return Ok(());
+    }
+
+    let out = terminal.backend_mut();
+    if cover.protocol == GraphicsProtocol::Kitty && cover.shown.is_some() {
+        kitty_delete(out)?;
+    }
+    if let (Some((rect, ..)), Some(art)) = (wanted, &cover.art) {
+        queue!(out, MoveTo(rect.x, rect.y))?;
+        match cover.protocol {
+            GraphicsProtocol::Kitty => write_kitty(out, art, rect)?,
+            GraphicsProtocol::Sixel => {
+                let (cell_w, cell_h) = cell_size();
+                let width = (rect.width * cell_w) as u32;
+                let height = (rect.height * cell_h) as u32;
+                write_sixel(out, art, width, height)?;
+            }
+            GraphicsProtocol::HalfBlocks => {}
+        }
+    }
+    cover.shown = wanted;
+    Write::flush(out)
+}
+
+/// Remove a kitty image before leaving, it would outlive the alternate screen
+pub fn clear<W: Write>(out: &mut W, cover: &CoverState) -> io::Result<()> {
+    if cover.protocol == GraphicsProtocol::Kitty && cover.shown.is_some() {
+        kitty_delete(out)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Pixel size of a terminal cell
+fn cell_size() -> (u16, u16) {
+    match terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns).max(1),
+            (size.height / size.rows).max(1),
+        ),
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+/// Largest rectangle of cells inside `area` with the image's aspect ratio, centered
+fn fit(area: Rect, art: &CoverArt, (cell_w, cell_h): (u16, u16)) -> Rect {
+    if art.width == 0 || art.height == 0 {
+        return Rect::default();
+    }
+    let area_w = area.width as f32 * cell_w as f32;
+    let area_h = area.height as f32 * cell_h as f32;
+    let scale = (area_w / art.width as f32).min(area_h / art.height as f32);
+    let size = Size::new(
+        ((art.width as f32 * scale / cell_w as f32).round() as u16).clamp(1, area.width),
+        ((art.height as f32 * scale / cell_h as f32).round() as u16).clamp(1, area.height),
+    );
+    Rect::new(
+        area.x + (area.width - size.width) / 2,
+        area.y + (area.height - size.height) / 2,
+        size.width,
+        size.height,
+    )
+}
+
+/// Average color of the image over a region given in fractions of its size
+fn sample(art: &CoverArt, x0: f32, y0: f32, x1: f32, y1: f32) -> [u8; 3] {
+    let px = |f: f32, len: u32| ((f * len as f32) as u32).min(len - 1);
+    let (left, right) = (px(x0, art.width), px(x1, art.width).max(px(x0, art.width)));
+    let (top, bottom) = (
+        px(y0, art.height),
+        px(y1, art.height).max(px(y0, art.height)),
+    );
+
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+    for y in top..=bottom {
+        for x in left..=right {
+            for (total, value) in sum.iter_mut().zip(art.pixel(x, y)) {
+                *total += value as u32;
+            }
+            count += 1;
+        }
+    }
+    sum.map(|total| (total / count) as u8)
+}
+
+fn draw_half_blocks(buf: &mut Buffer, area: Rect, art: &CoverArt) {
+    // Every cell holds two pixels, one above the other
+    let cols = area.width as f32;
+    let rows = area.height as f32 * 2.0;
+    for row in 0..area.height {
+        for col in 0..area.width {
+            let x0 = col as f32 / cols;
+            let x1 = (col + 1) as f32 / cols;
+            let y = row as f32 * 2.0;
+            let [r, g, b] = sample(art, x0, y / rows, x1, (y + 1.0) / rows);
+            let upper = Color::Rgb(r, g, b);
+            let [r, g, b] = sample(art, x0, (y + 1.0) / rows, x1, (y + 2.0) / rows);
+            let lower = Color::Rgb(r, g, b);
+            buf[(area.x + col, area.y + row)]
+                .set_char('▀')
+                .set_fg(upper)
+                .set_bg(lower);
+        }
+    }
+}
+
+fn kitty_delete<W: Write>(out: &mut W) -> io::Result<()> {
+    write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_IMAGE_ID)
+}
+
+/// Send the image as raw RGB, scaled by the terminal to `rect`. `q=2` keeps the
+/// terminal from answering on stdin, where the answer would read as key presses.
+fn write_kitty<W: Write>(out: &mut W, art: &CoverArt, rect: Rect) -> io::Result<()> {
+    let data = STANDARD.encode(&art.pixels);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=24,s={},v={},c={},r={},i={},C=1,q=2,m={};",
+                art.width, art.height, rect.width, rect.height, KITTY_IMAGE_ID, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Levels per channel of the sixel palette, a 6×6×6 color cube
+const SIXEL_LEVELS: u32 = 6;
+
+/// Encode the image scaled to `width`×`height` pixels as sixel, with colors
+/// mapped onto a fixed color cube
+fn write_sixel<W: Write>(out: &mut W, art: &CoverArt, width: u32, height: u32) -> io::Result<()> {
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    let max = SIXEL_LEVELS - 1;
+    let level = |value: u8| (value as u32 * max + 127) / 255;
+    let color_at = |x: u32, y: u32| {
+        let [r, g, b] = art.pixel(x * art.width / width, y * art.height / height);
+        ((level(r) * SIXEL_LEVELS + level(g)) * SIXEL_LEVELS + level(b)) as usize
+    };
+
+    let mut sixel = String::from("\x1bP0;1q");
+    sixel.push_str(&format!("\"1;1;{};{}", width, height));
+    let colors = (SIXEL_LEVELS * SIXEL_LEVELS * SIXEL_LEVELS) as usize;
+    for index in 0..colors {
+        let index = index as u32;
+        let percent = |l: u32| l * 100 / max;
+        sixel.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            percent(index / (SIXEL_LEVELS * SIXEL_LEVELS)),
+            percent(index / SIXEL_LEVELS % SIXEL_LEVELS),
+            percent(index % SIXEL_LEVELS)
+        ));
+    }
+
+    // Each band is six pixel rows; every color in it is a pass over the band
+    let mut bands: Vec<Vec<u8>> = vec![Vec::new(); colors];
+    for top in (0..height).step_by(6) {
+        for band in bands.iter_mut() {
+            band.clear();
+        }
+        for x in 0..width {
+            for bit in 0..6.min(height - top) {
+                let band = &mut bands[color_at(x, top + bit)];
+                band.resize(width as usize, 0);
+                band[x as usize] |= 1 << bit;
+            }
+        }
+        let mut first = true;
+        for (index, band) in bands.iter().enumerate() {
+            if band.is_empty() {
+                continue;
+            }
+            if !first {
+                sixel.push('$');
+            }
+            first = false;
+            sixel.push_str(&format!("#{}", index));
+            push_sixel_runs(&mut sixel, band);
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+    out.write_all(sixel.as_bytes())
+}
+
+/// Append one color's pass over a band, run-length encoded
+fn push_sixel_runs(sixel: &mut String, band: &[u8]) {
+    let mut i = 0;
+    while i < band.len() {
+        let bits = band[i];
+        let run = band[i..].iter().take_while(|&&b| b == bits).count();
+        let c = (b'?' + bits) as char;
+        if run > 3 {
+            sixel.push_str(&format!("!{}{}", run, c));
+        } else {
+            (0..run).for_each(|_| sixel.push(c));
+        }
+        i += run;
+    }
+}
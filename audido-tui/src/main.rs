@@ -1,8 +1,11 @@
 use std::fs::canonicalize;
 use std::time::Duration;
-use std::{io, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
-use audido_core::browser;
+use audido_core::{browser, playlist, remote, session::Session};
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
@@ -17,6 +20,7 @@ use audido_core::{
     engine::{AudioEngine, AudioEngineHandle},
 };
 
+mod graphics;
 mod logger;
 mod router;
 mod routes;
@@ -28,7 +32,9 @@ use router::{Router, route_for_name, tab_names};
 use state::AppState;
 
 use crate::router::InterceptKeyResult;
+use crate::routes::analysis::AnalysisTagsRoute;
 use crate::routes::playback::PlaybackRoute;
+use crate::routes::session::SessionRestoreRoute;
 
 fn main() -> anyhow::Result<()> {
     logger::setup_logging()?;
@@ -42,10 +48,13 @@ fn main() -> anyhow::Result<()> {
     let (engine, handle) = AudioEngine::new()?;
 
     // Spawn audio engine on dedicated thread
-    let _engine_thread = engine.spawn();
+    let engine_thread = engine.spawn();
+
+    let result = run_tui(handle, args);
 
-    // Ensure clean shutdown
-    run_tui(handle, args)
+    // Ensure clean shutdown: the engine saves the session on its way out
+    let _ = engine_thread.join();
+    result
 }
 
 fn run_tui(handle: AudioEngineHandle, initial_files: Vec<String>) -> anyhow::Result<()> {
@@ -59,6 +68,11 @@ fn run_tui(handle: AudioEngineHandle, initial_files: Vec<String>) -> anyhow::Res
     let mut state = AppState::new();
     let mut router = Router::new(Box::new(PlaybackRoute));
 
+    // Offer to resume the previous session unless files were given
+    if initial_files.is_empty() {
+        offer_session_restore(&mut router, &mut state, &handle)?;
+    }
+
     // Handle initial setup (Browser context & Queue loading)
     setup_initial_state(&mut state, &handle, initial_files)?;
 
@@ -67,9 +81,13 @@ fn run_tui(handle: AudioEngineHandle, initial_files: Vec<String>) -> anyhow::Res
         while let Ok(response) = handle.resp_rx.try_recv() {
             state.handle_response(response);
         }
+        state.library.poll_scan();
+        offer_analysis_tags(&mut router, &mut state, &handle)?;
 
         // Draw UI
+        state.cover.placement.set(None);
         terminal.draw(|f| ui::draw(f, &state, &router))?;
+        graphics::flush(&mut terminal, &mut state.cover)?;
 
         // Handle input
         if event::poll(Duration::from_millis(100))?
@@ -100,6 +118,7 @@ fn run_tui(handle: AudioEngineHandle, initial_files: Vec<String>) -> anyhow::Res
     }
 
     // Restore terminal
+    graphics::clear(terminal.backend_mut(), &state.cover)?;
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
@@ -117,7 +136,7 @@ fn setup_initial_state(
     }
 
     // Set Browser Context based on the first file
-    if let Some(first_file) = files.first() {
+    if let Some(first_file) = files.iter().find(|file| !remote::is_url(file)) {
         let path = PathBuf::from(first_file);
 
         let target_dir = if let Ok(abs_path) = canonicalize(&path) {
@@ -133,7 +152,7 @@ fn setup_initial_state(
         };
 
         if let Some(dir) = target_dir
-            && let Ok(items) = browser::get_directory_content(&dir)
+            && let Ok(items) = browser::get_directory_content(&dir, &state.browser.formats)
         {
             state.browser.current_dir = dir;
             state.browser.items = items;
@@ -142,13 +161,74 @@ fn setup_initial_state(
         }
     }
 
-    log::info!("Adding {} files to queue from CLI", files.len());
-    handle.cmd_tx.send(AudioCommand::AddToQueue(files))?;
+    // Playlists and URLs are expanded by the engine, keeping their position among the files
+    let mut pending_files = Vec::new();
+    for file in files {
+        let is_url = remote::is_url(&file);
+        if is_url || playlist::is_playlist(Path::new(&file)) {
+            if !pending_files.is_empty() {
+                handle
+                    .cmd_tx
+                    .send(AudioCommand::AddToQueue(std::mem::take(&mut pending_files)))?;
+            }
+            if is_url {
+                log::info!("Adding URL from CLI: {}", file);
+                handle.cmd_tx.send(AudioCommand::AddUrl(file))?;
+            } else {
+                log::info!("Loading playlist from CLI: {}", file);
+                handle.cmd_tx.send(AudioCommand::LoadPlaylist(file))?;
+            }
+        } else {
+            pending_files.push(file);
+        }
+    }
+    if !pending_files.is_empty() {
+        log::info!("Adding {} files to queue from CLI", pending_files.len());
+        handle
+            .cmd_tx
+            .send(AudioCommand::AddToQueue(pending_files))?;
+    }
     state.audio.status_message = "Loading queue...".to_string();
 
     Ok(())
 }
 
+fn offer_session_restore(
+    router: &mut Router,
+    state: &mut AppState,
+    handle: &AudioEngineHandle,
+) -> anyhow::Result<()> {
+    match Session::load() {
+        Ok(Some(session)) if !session.queue.is_empty() => {
+            router.push(Box::new(SessionRestoreRoute::new(session)), state, handle)?;
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Cannot read previous session: {}", e),
+    }
+    Ok(())
+}
+
+/// Ask whether a finished analysis goes into the tags, once no other dialog
+/// or sub-page is open
+fn offer_analysis_tags(
+    router: &mut Router,
+    state: &mut AppState,
+    handle: &AudioEngineHandle,
+) -> anyhow::Result<()> {
+    if router.depth() > 1 {
+        return Ok(());
+    }
+    if let Some((path, analysis)) = state.audio.pending_analysis.take() {
+        let under = router.current().name().to_string();
+        router.push(
+            Box::new(AnalysisTagsRoute::new(path, analysis, &under)),
+            state,
+            handle,
+        )?;
+    }
+    Ok(())
+}
+
 /// Handle global keys and delegate route-specific input to router
 fn handle_global_keys(
     key: KeyCode,
@@ -172,12 +252,10 @@ fn handle_global_keys(
             router.replace(next_route, state, handle)?;
             return Ok(false);
         }
-        KeyCode::Esc => {
-            // Try to pop from router (go back)
-            if router.depth() > 1 {
-                router.pop(state, handle)?;
-                return Ok(false);
-            }
+        // Try to pop from router (go back)
+        KeyCode::Esc if router.depth() > 1 => {
+            router.pop(state, handle)?;
+            return Ok(false);
         }
         _ => {}
     }
@@ -3,8 +3,8 @@ use ratatui::{Frame, layout::Rect};
 
 use crate::{
     routes::{
-        browser::BrowserRoute, eq::EqualizerRoute, log::LogRoute, playback::PlaybackRoute,
-        queue::QueueRoute, settings::SettingsRoute,
+        browser::BrowserRoute, eq::EqualizerRoute, library::LibraryRoute, log::LogRoute,
+        playback::PlaybackRoute, queue::QueueRoute, settings::SettingsRoute,
     },
     state::AppState,
 };
@@ -65,7 +65,6 @@ pub enum RouteAction {
     /// Do nothing, stay on current route
     None,
     /// Go back to previous route
-    #[allow(dead_code)]
     Pop,
     /// Navigate to a new route
     Push(Box<dyn RouteHandler>),
@@ -83,7 +82,6 @@ pub enum RouteAction {
 pub enum InterceptKeyResult {
     Handled,
     Ignored,
-    #[allow(dead_code)]
     HandledAndNavigate(RouteAction),
 }
 
@@ -219,6 +217,7 @@ pub fn route_for_name(name: &str) -> Box<dyn RouteHandler> {
         "Playback" => Box::new(PlaybackRoute),
         "Queue" => Box::new(QueueRoute),
         "Browser" => Box::new(BrowserRoute),
+        "Library" => Box::new(LibraryRoute),
         "Settings" => Box::new(SettingsRoute),
         "Log" => Box::new(LogRoute::new()),
         "Equalizer" => Box::new(EqualizerRoute::default()),
@@ -226,7 +225,7 @@ pub fn route_for_name(name: &str) -> Box<dyn RouteHandler> {
     }
 }
 
-static TAB_NAMES: [&str; 5] = ["Playback", "Queue", "Browser", "Settings", "Log"];
+static TAB_NAMES: [&str; 6] = ["Playback", "Queue", "Browser", "Library", "Settings", "Log"];
 
 /// Get all main tab names in order
 pub fn tab_names() -> &'static [&'static str] {
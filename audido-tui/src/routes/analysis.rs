@@ -0,0 +1,115 @@
+use audido_core::{
+    analysis::{Analysis, TagWriteMode},
+    commands::AudioCommand,
+    engine::AudioEngineHandle,
+};
+use ratatui::{Frame, crossterm::event::KeyCode, layout::Rect};
+
+use crate::{
+    router::{InterceptKeyResult, RouteAction, RouteHandler, route_for_name},
+    state::AppState,
+    ui::{DialogProperties, draw_generic_dialog},
+};
+
+const OPTIONS: [&str; 4] = ["Save to Tags", "Not Now", "Always Save", "Never Save"];
+
+/// Prompt asking whether a track's analysis results go into its tags, shown
+/// over the tab that was open when the analysis finished
+#[derive(Debug)]
+pub struct AnalysisTagsRoute {
+    path: String,
+    analysis: Analysis,
+    /// Tab drawn behind the prompt
+    under: Box<dyn RouteHandler>,
+    selected: usize,
+}
+
+impl AnalysisTagsRoute {
+    pub fn new(path: String, analysis: Analysis, under: &str) -> Self {
+        Self {
+            path,
+            analysis,
+            under: route_for_name(under),
+            selected: 0,
+        }
+    }
+
+    fn write(&self, handle: &AudioEngineHandle) {
+        let _ = handle.cmd_tx.send(AudioCommand::WriteAnalysis {
+            path: self.path.clone(),
+            analysis: self.analysis.clone(),
+        });
+    }
+
+    /// Remember the choice for the next tracks too
+    fn set_mode(&self, mode: TagWriteMode, state: &mut AppState, handle: &AudioEngineHandle) {
+        if handle
+            .cmd_tx
+            .send(AudioCommand::SetAnalysisTagWrite(mode))
+            .is_ok()
+        {
+            state.settings.analysis_tags = mode;
+        }
+    }
+}
+
+impl RouteHandler for AnalysisTagsRoute {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
+        self.under.render(frame, area, state);
+
+        let title = format!("Save to tags? {}", self.analysis);
+        let props = DialogProperties {
+            title: &title,
+            options: OPTIONS.to_vec(),
+            selected_index: self.selected,
+        };
+        draw_generic_dialog(frame, area, props);
+    }
+
+    fn handle_input(
+        &mut self,
+        _key: KeyCode,
+        _state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> anyhow::Result<RouteAction> {
+        Ok(RouteAction::None)
+    }
+
+    fn name(&self) -> &str {
+        "Save Analysis"
+    }
+
+    // The prompt is modal: only quitting gets through
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        match key {
+            KeyCode::Up => {
+                self.selected = (self.selected + OPTIONS.len() - 1) % OPTIONS.len();
+                InterceptKeyResult::Handled
+            }
+            KeyCode::Down => {
+                self.selected = (self.selected + 1) % OPTIONS.len();
+                InterceptKeyResult::Handled
+            }
+            KeyCode::Enter => {
+                match self.selected {
+                    0 => self.write(handle),
+                    2 => {
+                        self.set_mode(TagWriteMode::Always, state, handle);
+                        self.write(handle);
+                    }
+                    3 => self.set_mode(TagWriteMode::Never, state, handle),
+                    _ => {}
+                }
+                InterceptKeyResult::HandledAndNavigate(RouteAction::Pop)
+            }
+            KeyCode::Esc => InterceptKeyResult::HandledAndNavigate(RouteAction::Pop),
+            KeyCode::Char('q') => InterceptKeyResult::Ignored,
+            _ => InterceptKeyResult::Handled,
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use audido_core::engine::AudioEngineHandle;
+use std::path::Path;
+
+use audido_core::{commands::AudioCommand, engine::AudioEngineHandle, playlist};
 use ratatui::{
     Frame,
     crossterm::event::KeyCode,
@@ -9,10 +11,13 @@ use ratatui::{
 };
 
 use crate::{
-    router::{RouteAction, RouteHandler},
-    routes::playback::PlaybackRoute,
+    router::{InterceptKeyResult, RouteAction, RouteHandler},
+    routes::{
+        playback::PlaybackRoute,
+        tags::{TagEditorOrigin, TagEditorRoute},
+    },
     state::AppState,
-    states::{BrowserFileDialog, BrowserState},
+    states::{BrowserFileDialog, BrowserState, SearchInput},
     ui::{DialogProperties, draw_generic_dialog},
 };
 
@@ -30,7 +35,7 @@ impl RouteHandler for BrowserRoute {
                 .map(|s| s.to_string_lossy().to_string())
                 .unwrap_or_else(|| "Unknown File".to_string());
 
-            let options = vec!["Play Now", "Add to Queue"];
+            let options = dialog_options(path);
 
             let props = DialogProperties {
                 title: &filename,
@@ -52,37 +57,43 @@ impl RouteHandler for BrowserRoute {
         if state.browser.is_dialog_open() {
             match key {
                 KeyCode::Up | KeyCode::Down => {
-                    state.browser.dialog_toggle();
+                    let option_count = match &state.browser.dialog {
+                        BrowserFileDialog::Open { path, .. } => dialog_options(path).len(),
+                        BrowserFileDialog::None => 0,
+                    };
+                    let offset = if key == KeyCode::Up { -1 } else { 1 };
+                    state.browser.dialog_move(offset, option_count);
                 }
                 KeyCode::Enter => {
                     if let BrowserFileDialog::Open { path, selected } = &state.browser.dialog {
                         let path_str = path.to_string_lossy().to_string();
 
-                        if *selected == 0 {
-                            // Play Now
-                            handle
-                                .cmd_tx
-                                .send(audido_core::commands::AudioCommand::ClearQueue)?;
-                            handle
-                                .cmd_tx
-                                .send(audido_core::commands::AudioCommand::AddToQueue(vec![
-                                    path_str,
-                                ]))?;
-                            handle
-                                .cmd_tx
-                                .send(audido_core::commands::AudioCommand::PlayQueueIndex(0))?;
-                            state.browser.close_dialog();
-                            // Navigate to playback
-                            return Ok(RouteAction::Replace(Box::new(PlaybackRoute)));
-                        } else {
-                            // Add to Queue
-                            handle
-                                .cmd_tx
-                                .send(audido_core::commands::AudioCommand::AddToQueue(vec![
-                                    path_str,
+                        match dialog_options(path).get(*selected).copied() {
+                            Some(PLAY_NOW) => {
+                                handle.cmd_tx.send(AudioCommand::ReplaceQueue {
+                                    paths: vec![path_str],
+                                    start: 0,
+                                })?;
+                                state.browser.close_dialog();
+                                // Navigate to playback
+                                return Ok(RouteAction::Replace(Box::new(PlaybackRoute)));
+                            }
+                            Some(PLAY_NEXT) => {
+                                handle.cmd_tx.send(AudioCommand::PlayNext(vec![
+                                    path.to_string_lossy().to_string(),
                                 ]))?;
-                            state.browser.close_dialog();
+                            }
+                            _ => {
+                                // Add to Queue, playlists expand into their tracks
+                                let enqueue = if playlist::is_playlist(path) {
+                                    AudioCommand::LoadPlaylist(path_str)
+                                } else {
+                                    AudioCommand::AddToQueue(vec![path_str])
+                                };
+                                handle.cmd_tx.send(enqueue)?;
+                            }
                         }
+                        state.browser.close_dialog();
                     }
                 }
                 KeyCode::Esc => {
@@ -91,8 +102,18 @@ impl RouteHandler for BrowserRoute {
                 _ => {}
             }
         } else {
+            match state.browser.search.handle_key(key) {
+                SearchInput::Changed => {
+                    state.browser.apply_search();
+                    return Ok(RouteAction::None);
+                }
+                SearchInput::Handled => return Ok(RouteAction::None),
+                SearchInput::Ignored => {}
+            }
+
             // Normal browser navigation
             match key {
+                KeyCode::Char('/') => state.browser.search.start(),
                 KeyCode::Up => state.browser.prev(),
                 KeyCode::Down => state.browser.next(),
                 KeyCode::Enter => {
@@ -101,6 +122,21 @@ impl RouteHandler for BrowserRoute {
                         state.browser.open_dialog(path);
                     }
                 }
+                KeyCode::Char('m') => {
+                    state.browser.toggle_mark();
+                    state.browser.next();
+                }
+                KeyCode::Char('e') => {
+                    let files = state.browser.tag_targets();
+                    if files.is_empty() {
+                        state.audio.status_message = "No audio files to edit".to_string();
+                        return Ok(RouteAction::None);
+                    }
+                    match TagEditorRoute::open(files, TagEditorOrigin::Browser) {
+                        Ok(editor) => return Ok(RouteAction::Push(Box::new(editor))),
+                        Err(e) => state.audio.status_message = format!("Cannot read tags: {}", e),
+                    }
+                }
                 _ => {}
             }
         }
@@ -110,6 +146,39 @@ impl RouteHandler for BrowserRoute {
     fn name(&self) -> &str {
         "Browser"
     }
+
+    // While typing a search query, keys like 'q' and Tab are text
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        if !state.browser.search.editing || state.browser.is_dialog_open() {
+            return InterceptKeyResult::Ignored;
+        }
+        match state.browser.search.handle_key(key) {
+            SearchInput::Changed => {
+                state.browser.apply_search();
+                InterceptKeyResult::Handled
+            }
+            SearchInput::Handled => InterceptKeyResult::Handled,
+            SearchInput::Ignored => InterceptKeyResult::Ignored,
+        }
+    }
+}
+
+const PLAY_NOW: &str = "Play Now";
+const PLAY_NEXT: &str = "Play Next";
+const ADD_TO_QUEUE: &str = "Add to Queue";
+
+/// Options of the file dialog. Playlists are only appended as a whole.
+fn dialog_options(path: &Path) -> Vec<&'static str> {
+    if playlist::is_playlist(path) {
+        vec![PLAY_NOW, ADD_TO_QUEUE]
+    } else {
+        vec![PLAY_NOW, PLAY_NEXT, ADD_TO_QUEUE]
+    }
 }
 
 pub fn draw_browser_panel(f: &mut Frame, area: Rect, browser_state: &BrowserState) {
@@ -117,13 +186,16 @@ pub fn draw_browser_panel(f: &mut Frame, area: Rect, browser_state: &BrowserStat
     let is_active = true;
 
     // Title shows current path
-    let title = if browser_state.current_dir.as_os_str().is_empty() {
+    let mut title = if browser_state.current_dir.as_os_str().is_empty() {
         " Browser: System Drives ".to_string()
     } else {
         format!(" Browser: {} ", browser_state.current_dir.to_string_lossy())
     };
+    if !browser_state.marked.is_empty() {
+        title.push_str(&format!("({} marked) ", browser_state.marked.len()));
+    }
 
-    let block = Block::default()
+    let mut block = Block::default()
         .title(title)
         .borders(Borders::ALL)
         .border_style(if is_active {
@@ -131,20 +203,40 @@ pub fn draw_browser_panel(f: &mut Frame, area: Rect, browser_state: &BrowserStat
         } else {
             Style::default()
         });
+    if let Some(search) = browser_state.search.title() {
+        block = block.title_bottom(search);
+    }
 
     let items: Vec<ListItem> = browser_state
-        .items
-        .iter()
-        .map(|item| {
-            let icon = if item.is_dir { "📁" } else { "🎵" };
+        .search
+        .visible(browser_state.items.len())
+        .into_iter()
+        .map(|i| {
+            let item = &browser_state.items[i];
+            let is_playlist = !item.is_dir && playlist::is_playlist(&item.path);
+            let icon = if item.is_dir {
+                "📁"
+            } else if is_playlist {
+                "📜"
+            } else {
+                "🎵"
+            };
             let color = if item.is_dir {
                 Color::Blue
+            } else if is_playlist {
+                Color::Green
             } else {
                 Color::White
             };
+            let mark = if browser_state.marked.contains(&item.path) {
+                "● "
+            } else {
+                ""
+            };
 
             ListItem::new(Line::from(vec![
                 Span::styled(format!("{} ", icon), Style::default().fg(color)),
+                Span::raw(mark),
                 Span::raw(&item.name),
             ]))
         })
@@ -1,7 +1,7 @@
 use anyhow::Ok;
 use audido_core::{
     commands::AudioCommand,
-    dsp::eq::{Equalizer, FilterNode},
+    dsp::eq::{DynamicBand, Equalizer, FilterNode, MAX_EQ_FILTERS},
     engine::AudioEngineHandle,
 };
 use ratatui::{
@@ -18,7 +18,7 @@ use strum::VariantArray;
 use crate::{
     router::{InterceptKeyResult, RouteAction, RouteHandler, get_next_tab, route_for_name},
     state::AppState,
-    states::{AudioState, EqMode, EqState},
+    states::{AudioState, EqEdit, EqMode, EqState},
     ui::{draw_generic_dialog, open_modal},
 };
 
@@ -215,34 +215,24 @@ impl EqualizerRoute {
         handle: &AudioEngineHandle,
     ) -> anyhow::Result<RouteAction> {
         match key {
-            KeyCode::Up => {
-                if !self.locked_in {
-                    self.prev_config_param();
-                }
+            KeyCode::Up if !self.locked_in => {
+                self.prev_config_param();
             }
-            KeyCode::Down => {
-                if !self.locked_in {
-                    self.next_config_param();
-                }
+            KeyCode::Down if !self.locked_in => {
+                self.next_config_param();
             }
             KeyCode::Esc => {
                 self.close_filter_band_config();
                 self.locked_in = false;
             }
-            KeyCode::Enter => {
-                if self.eq_filter_band_config_opened.is_some() {
-                    self.locked_in = !self.locked_in;
-                }
+            KeyCode::Enter if self.eq_filter_band_config_opened.is_some() => {
+                self.locked_in = !self.locked_in;
             }
-            KeyCode::Left => {
-                if self.locked_in {
-                    self.handle_set_parameter(state, handle, false)?;
-                }
+            KeyCode::Left if self.locked_in => {
+                self.handle_set_parameter(state, handle, false)?;
             }
-            KeyCode::Right => {
-                if self.locked_in {
-                    self.handle_set_parameter(state, handle, true)?;
-                }
+            KeyCode::Right if self.locked_in => {
+                self.handle_set_parameter(state, handle, true)?;
             }
             _ => {}
         }
@@ -256,6 +246,11 @@ impl EqualizerRoute {
         is_increment: bool,
     ) -> anyhow::Result<RouteAction> {
         if let Some(config) = &mut self.eq_filter_band_config_opened {
+            state.eq.checkpoint(EqEdit::BandParameter {
+                band: config.selected_band,
+                param: config.selected_param,
+            });
+
             // get this mutable ref of filter node
             let Some(filter_node) = state.eq.local_filters.get_mut(config.selected_band) else {
                 return Ok(RouteAction::None);
@@ -289,13 +284,41 @@ impl EqualizerRoute {
                     };
                     filter_node.set_order(new_order);
                 }
+                5 => {
+                    let dynamic = if filter_node.is_dynamic() {
+                        None
+                    } else {
+                        Some(DynamicBand::default())
+                    };
+                    filter_node.set_dynamic(dynamic);
+                }
+                6 => {
+                    if let Some(dynamic) = &mut filter_node.dynamic {
+                        let delta = if is_increment { 1.0 } else { -1.0 };
+                        dynamic.set_threshold(dynamic.threshold_db + delta);
+                    }
+                }
+                7 => {
+                    if let Some(dynamic) = &mut filter_node.dynamic {
+                        let delta = if is_increment { 1.0 } else { -1.0 };
+                        dynamic.set_attack(dynamic.attack_ms + delta);
+                    }
+                }
+                8 => {
+                    if let Some(dynamic) = &mut filter_node.dynamic {
+                        let delta = if is_increment { 10.0 } else { -10.0 };
+                        dynamic.set_release(dynamic.release_ms + delta);
+                    }
+                }
                 _ => {}
             }
 
-            // Send the updated filters to the audio engine
-            handle.cmd_tx.send(AudioCommand::EqSetAllFilters(
-                state.eq.local_filters.clone(),
+            // Send the updated band to the audio engine
+            handle.cmd_tx.send(AudioCommand::EqSetFilter(
+                config.selected_band,
+                filter_node.clone(),
             ))?;
+            send_master_gain_if_comparing(state, handle)?;
         }
         Ok(RouteAction::None)
     }
@@ -332,23 +355,33 @@ impl EqualizerRoute {
                                     "Gain".to_string(),
                                     "Q Factor".to_string(),
                                     "Order".to_string(),
+                                    "Dynamic".to_string(),
+                                    "Threshold".to_string(),
+                                    "Attack".to_string(),
+                                    "Release".to_string(),
                                 ],
                                 selected_param: 0,
                             });
                         }
                         EqDialogOption::ResetBand => {
+                            state.eq.checkpoint(EqEdit::Bands);
                             // Reset the local filter to preset default
                             let preset_filters = state.eq.local_preset.set_filters();
-                            if let Some(default_node) = preset_filters.get(selected_band).cloned() {
-                                if let Some(filter) = state.eq.local_filters.get_mut(selected_band)
-                                {
-                                    *filter = default_node;
-                                }
+                            if let Some(filter) = state.eq.local_filters.get_mut(selected_band) {
+                                let default_node = preset_filters
+                                    .get(selected_band)
+                                    .cloned()
+                                    .unwrap_or_default();
+                                *filter = FilterNode {
+                                    id: filter.id,
+                                    ..default_node
+                                };
                             }
                             // Send command to audio engine
                             handle
                                 .cmd_tx
                                 .send(AudioCommand::EqResetFilterNode(selected_band))?;
+                            send_master_gain_if_comparing(state, handle)?;
                         }
                     }
                 }
@@ -376,10 +409,9 @@ impl EqualizerRoute {
             KeyCode::Up => {
                 match self.eq_focus {
                     EqFocus::CurvePanel => {
+                        state.eq.checkpoint(EqEdit::MasterGain);
                         state.eq.local_master_gain = (state.eq.local_master_gain + 0.5).min(12.0);
-                        handle
-                            .cmd_tx
-                            .send(AudioCommand::EqSetMasterGain(state.eq.local_master_gain))?;
+                        send_master_gain(state, handle)?;
                     }
                     EqFocus::BandPanel => {
                         match state.eq.eq_mode {
@@ -395,10 +427,9 @@ impl EqualizerRoute {
             }
             KeyCode::Down => match self.eq_focus {
                 EqFocus::CurvePanel => {
+                    state.eq.checkpoint(EqEdit::MasterGain);
                     state.eq.local_master_gain = (state.eq.local_master_gain - 0.5).max(-12.0);
-                    handle
-                        .cmd_tx
-                        .send(AudioCommand::EqSetMasterGain(state.eq.local_master_gain))?;
+                    send_master_gain(state, handle)?;
                 }
                 EqFocus::BandPanel => {
                     self.next(num_filters);
@@ -413,26 +444,105 @@ impl EqualizerRoute {
             KeyCode::Char('m') => {
                 state.eq.toggle_mode();
             }
-            KeyCode::Char('a') => {
-                if state.eq.local_filters.len() < 8 {
-                    let new_id = state.eq.local_filters.len() as i16;
-                    let new_filter = FilterNode::new(new_id, 1000.0);
-                    state.eq.local_filters.push(new_filter);
-                    handle.cmd_tx.send(AudioCommand::EqSetAllFilters(
-                        state.eq.local_filters.clone(),
-                    ))?;
+            KeyCode::Char('a') if state.eq.local_filters.len() < MAX_EQ_FILTERS => {
+                state.eq.checkpoint(EqEdit::Bands);
+                let new_filter = FilterNode::new(state.eq.next_filter_id(), 1000.0);
+                state.eq.local_filters.push(new_filter.clone());
+                self.eq_selected_band = state.eq.local_filters.len() - 1;
+                handle.cmd_tx.send(AudioCommand::EqAddFilter(new_filter))?;
+            }
+            KeyCode::Char('d') if self.is_editing_bands(state) => {
+                let band = self.eq_selected_band;
+                state.eq.checkpoint(EqEdit::Bands);
+                if state.eq.remove_filter(band).is_some() {
+                    self.eq_selected_band =
+                        band.min(state.eq.local_filters.len().saturating_sub(1));
+                    handle.cmd_tx.send(AudioCommand::EqRemoveFilter(band))?;
+                    send_master_gain_if_comparing(state, handle)?;
                 }
             }
-            KeyCode::Enter => {
-                if self.eq_focus == EqFocus::BandPanel && state.eq.eq_mode == EqMode::Advanced {
-                    self.open_filter_band_dialog();
+            KeyCode::Char('s') if self.is_editing_bands(state) => {
+                let band = self.eq_selected_band;
+                state.eq.solo_band = if state.eq.solo_band == Some(band) {
+                    None
+                } else {
+                    Some(band)
+                };
+                handle
+                    .cmd_tx
+                    .send(AudioCommand::EqSetSolo(state.eq.solo_band))?;
+            }
+            KeyCode::Char('b') if self.is_editing_bands(state) => {
+                let band = self.eq_selected_band;
+                state.eq.checkpoint(EqEdit::Bands);
+                if let Some(filter) = state.eq.local_filters.get_mut(band) {
+                    filter.set_bypass(!filter.bypass);
+                    handle
+                        .cmd_tx
+                        .send(AudioCommand::EqSetFilter(band, filter.clone()))?;
+                    send_master_gain_if_comparing(state, handle)?;
+                }
+            }
+            KeyCode::Char('R') => {
+                // Reset the whole EQ to the preset; undoable
+                state.eq.checkpoint(EqEdit::Bands);
+                state.eq.local_filters = state.eq.local_preset.set_filters();
+                state.eq.local_master_gain = 0.0;
+                state.eq.solo_band = None;
+                self.clamp_selected_band(state);
+                handle.cmd_tx.send(AudioCommand::EqResetParameters)?;
+                handle.cmd_tx.send(AudioCommand::EqSetSolo(None))?;
+                send_master_gain_if_comparing(state, handle)?;
+            }
+            KeyCode::Char('u') => {
+                if state.eq.undo() {
+                    self.clamp_selected_band(state);
+                    send_eq_state(state, handle)?;
+                } else {
+                    state.audio.status_message = "Nothing to undo".to_string();
                 }
             }
+            KeyCode::Char('r') => {
+                if state.eq.redo() {
+                    self.clamp_selected_band(state);
+                    send_eq_state(state, handle)?;
+                } else {
+                    state.audio.status_message = "Nothing to redo".to_string();
+                }
+            }
+            KeyCode::Char('x') => {
+                state.eq.toggle_ab();
+                self.clamp_selected_band(state);
+                send_eq_state(state, handle)?;
+            }
+            KeyCode::Char('X') if state.eq.ab.is_some() => {
+                state.eq.end_ab();
+                send_master_gain(state, handle)?;
+            }
+            KeyCode::Enter
+                if self.eq_focus == EqFocus::BandPanel && state.eq.eq_mode == EqMode::Advanced =>
+            {
+                self.open_filter_band_dialog();
+            }
             _ => {}
         }
         Ok(RouteAction::None)
     }
 
+    /// Keep the band selection valid after the band list was replaced
+    fn clamp_selected_band(&mut self, state: &AppState) {
+        self.eq_selected_band = self
+            .eq_selected_band
+            .min(state.eq.local_filters.len().saturating_sub(1));
+    }
+
+    /// Band actions only apply to the band list in Advanced mode
+    fn is_editing_bands(&self, state: &AppState) -> bool {
+        self.eq_focus == EqFocus::BandPanel
+            && state.eq.eq_mode == EqMode::Advanced
+            && !state.eq.local_filters.is_empty()
+    }
+
     fn has_floating_panel(&self) -> bool {
         self.eq_filter_band_config_opened.is_some() || self.eq_dialog_state != EqDialogState::None
     }
@@ -537,6 +647,45 @@ impl RouteHandler for EqualizerRoute {
     }
 }
 
+// ── Engine sync helpers ───────────────────────────────────────────────────
+
+fn sample_rate(state: &AppState) -> u32 {
+    state
+        .audio
+        .metadata
+        .as_ref()
+        .map_or(44100, |m| m.sample_rate)
+}
+
+fn send_master_gain(state: &AppState, handle: &AudioEngineHandle) -> anyhow::Result<()> {
+    let gain = state.eq.effective_master_gain(sample_rate(state));
+    handle.cmd_tx.send(AudioCommand::EqSetMasterGain(gain))?;
+    Ok(())
+}
+
+/// While comparing A/B the loudness compensation follows the curve
+fn send_master_gain_if_comparing(
+    state: &AppState,
+    handle: &AudioEngineHandle,
+) -> anyhow::Result<()> {
+    if state.eq.ab.is_some() {
+        send_master_gain(state, handle)?;
+    }
+    Ok(())
+}
+
+/// Push the whole local EQ state to the engine (after undo/redo or an A/B switch)
+fn send_eq_state(state: &AppState, handle: &AudioEngineHandle) -> anyhow::Result<()> {
+    handle.cmd_tx.send(AudioCommand::EqSetState {
+        preset: state.eq.local_preset,
+        filters: state.eq.local_filters.clone(),
+    })?;
+    handle
+        .cmd_tx
+        .send(AudioCommand::EqSetSolo(state.eq.solo_band))?;
+    send_master_gain(state, handle)
+}
+
 // ── Draw helpers ──────────────────────────────────────────────────────────
 
 pub fn draw_eq_panel(
@@ -598,7 +747,7 @@ fn draw_eq_mode_toggle(f: &mut Frame, area: Rect, eq_state: &EqState) {
         Style::default().fg(Color::DarkGray)
     };
 
-    let mode_line = Line::from(vec![
+    let mut mode_line = Line::from(vec![
         Span::styled("EQ: ", Style::default().fg(Color::White)),
         Span::styled(if is_enabled { "ON" } else { "OFF" }, enabled_style),
         Span::raw("  │  "),
@@ -614,6 +763,15 @@ fn draw_eq_mode_toggle(f: &mut Frame, area: Rect, eq_state: &EqState) {
         Span::styled("[M]", Style::default().fg(Color::Yellow)),
         Span::raw(" Mode"),
     ]);
+    if let Some(ab) = &eq_state.ab {
+        mode_line.push_span(Span::raw("  │  "));
+        mode_line.push_span(Span::styled(
+            format!("A/B: {}", ab.active),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
 
     let paragraph = Paragraph::new(mode_line).block(Block::default().borders(Borders::BOTTOM));
     f.render_widget(paragraph, area);
@@ -728,16 +886,28 @@ fn draw_eq_controls(
                     Style::default()
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD)
+                } else if !eq_state.is_band_audible(i) {
+                    Style::default().fg(Color::DarkGray)
                 } else {
                     Style::default().fg(Color::White)
                 };
-                let filter_info = format!(
+                let mut filter_info = format!(
                     "{}Band {}: {:?} @ {}Hz",
                     prefix,
                     i + 1,
                     filter.filter_type,
                     filter.freq as i32
                 );
+                if filter.is_dynamic() {
+                    let live_gain = eq_state.dynamic_gain(i).unwrap_or(0.0);
+                    filter_info.push_str(&format!(" ⚡{:+.1}dB", live_gain));
+                }
+                if eq_state.solo_band == Some(i) {
+                    filter_info.push_str(" [S]");
+                }
+                if filter.bypass {
+                    filter_info.push_str(" [B]");
+                }
                 ListItem::new(filter_info).style(style)
             })
             .collect();
@@ -756,7 +926,11 @@ fn draw_eq_controls(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(band_border_style)
-                    .title(" Bands (↑↓ Select) "),
+                    .title(format!(
+                        " Bands {}/{} (↑↓ Select) ",
+                        eq_state.local_filters.len(),
+                        MAX_EQ_FILTERS
+                    )),
             );
 
             let mut list_state = ratatui::widgets::ListState::default();
@@ -784,12 +958,19 @@ fn draw_filter_details(f: &mut Frame, area: Rect, eq_state: &EqState, eq_selecte
     }
 
     let filter = &eq_state.local_filters[eq_selected_band];
-    let params = [
+    let mut params = vec![
         ("Type", format!("{:?}", filter.filter_type)),
         ("Freq", format!("{} Hz", filter.freq as i32)),
         ("Gain", format!("{:+.1} dB", filter.gain)),
         ("Q", format!("{:.2}", filter.q)),
     ];
+    if let Some(dynamic) = &filter.dynamic {
+        let live_gain = eq_state.dynamic_gain(eq_selected_band).unwrap_or(0.0);
+        params.push((
+            "Dyn",
+            format!("{:+.1} dB @ {:.0} dB", live_gain, dynamic.threshold_db),
+        ));
+    }
 
     let text: Vec<Line> = params
         .iter()
@@ -822,6 +1003,7 @@ fn draw_eq_graph(
     eq.filters = eq_state.local_filters.clone();
     eq.master_gain = (10.0f32).powf(eq_state.local_master_gain / 20.0); // Convert dB to linear
     eq.parameters_changed();
+    eq.set_solo(eq_state.solo_band);
 
     let width = 100;
     let data = eq.get_response_curve(100);
@@ -837,7 +1019,10 @@ fn draw_eq_graph(
 
     let mut filter_curves: Vec<Vec<(f64, f64)>> = Vec::new();
 
-    for filter in &eq_state.local_filters {
+    for (band, filter) in eq_state.local_filters.iter().enumerate() {
+        if !eq_state.is_band_audible(band) {
+            continue;
+        }
         let mut curve_points = Vec::with_capacity(width);
 
         // Generate points across the frequency spectrum for this single filter
@@ -866,8 +1051,10 @@ fn draw_eq_graph(
             // Calculate the total response at the filter's center frequency
             // local_master_gain is already in dB, so use it directly
             let mut total_db = eq_state.local_master_gain;
-            for flt in &eq_state.local_filters {
-                total_db += flt.magnitude_db(filter.freq, sample_rate as f32);
+            for (band, flt) in eq_state.local_filters.iter().enumerate() {
+                if eq_state.is_band_audible(band) {
+                    total_db += flt.magnitude_db(filter.freq, sample_rate as f32);
+                }
             }
             ((filter.freq as f64).log10(), total_db as f64)
         })
@@ -952,7 +1139,7 @@ fn draw_filter_band_config_modal(
     //     filter.order
     // );
 
-    let params: Vec<(&str, String, Color)> = vec![
+    let mut params: Vec<(&str, String, Color)> = vec![
         ("Type", format!("{}", filter.filter_type), Color::Cyan),
         (
             "Frequency",
@@ -972,6 +1159,32 @@ fn draw_filter_band_config_modal(
         ("Order", filter.order.to_string(), Color::Magenta),
     ];
 
+    // Detector settings only mean something once the band is dynamic
+    let dynamic = filter.dynamic;
+    let dynamic_value = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    params.extend([
+        (
+            "Dynamic",
+            if dynamic.is_some() { "On" } else { "Off" }.to_string(),
+            Color::Cyan,
+        ),
+        (
+            "Threshold",
+            dynamic_value(dynamic.map(|d| format!("{:.0} dB", d.threshold_db))),
+            Color::Yellow,
+        ),
+        (
+            "Attack",
+            dynamic_value(dynamic.map(|d| format!("{:.0} ms", d.attack_ms))),
+            Color::Green,
+        ),
+        (
+            "Release",
+            dynamic_value(dynamic.map(|d| format!("{:.0} ms", d.release_ms))),
+            Color::Green,
+        ),
+    ]);
+
     let mut text: Vec<Line> = params
         .iter()
         .enumerate()
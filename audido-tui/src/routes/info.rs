@@ -0,0 +1,173 @@
+use audido_core::{engine::AudioEngineHandle, metadata::AudioMetadata};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    router::{InterceptKeyResult, RouteAction, RouteHandler, route_for_name},
+    state::AppState,
+    states::AudioState,
+};
+
+/// Width of the labels column
+const LABEL_WIDTH: usize = 13;
+
+/// Tags and technical details of one track, shown over the tab it was opened from
+#[derive(Debug)]
+pub struct TrackInfoRoute {
+    metadata: AudioMetadata,
+    /// Tab drawn behind the panel
+    under: Box<dyn RouteHandler>,
+    scroll: u16,
+}
+
+impl TrackInfoRoute {
+    pub fn new(metadata: AudioMetadata, under: &str) -> Self {
+        Self {
+            metadata,
+            under: route_for_name(under),
+            scroll: 0,
+        }
+    }
+
+    /// Label and value of every detail the track has
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let m = &self.metadata;
+        let track = m.track_number.map(|track| match m.disc_number {
+            Some(disc) => format!("{} (disc {})", track, disc),
+            None => track.to_string(),
+        });
+        let rows = [
+            ("Title", m.title.clone()),
+            ("Artist", m.author.clone()),
+            ("Album", m.album.clone()),
+            ("Album artist", m.album_artist.clone()),
+            ("Composer", m.composer.clone()),
+            ("Track", track),
+            ("Year", m.year.map(|year| year.to_string())),
+            ("Genre", m.genre.clone()),
+            ("Comment", m.comment.clone()),
+            (
+                "Key",
+                m.key.map(|key| format!("{} ({})", key, key.camelot_code())),
+            ),
+            ("BPM", m.bpm.map(|bpm| format!("{:.1}", bpm))),
+            (
+                "Loudness",
+                m.loudness.map(|loudness| format!("{:.1} LUFS", loudness)),
+            ),
+            (
+                "Duration",
+                (m.duration > 0.0).then(|| AudioState::format_time(m.duration)),
+            ),
+            (
+                "Format",
+                (!m.format.is_empty()).then(|| m.format.to_uppercase()),
+            ),
+            ("Codec", m.codec.clone()),
+            (
+                "Sample rate",
+                (m.sample_rate > 0).then(|| format!("{:.1} kHz", m.sample_rate as f32 / 1000.0)),
+            ),
+            ("Bit depth", m.bit_depth.map(|bits| format!("{} bit", bits))),
+            (
+                "Channels",
+                (m.num_channels > 0).then(|| format!("{} ({})", m.num_channels, m.channel_layout)),
+            ),
+            ("Bitrate", m.bitrate.map(|kbps| format!("{} kbps", kbps))),
+            ("File size", m.file_size.map(format_size)),
+            ("Path", Some(m.full_file_path.clone())),
+        ];
+        rows.into_iter()
+            .filter_map(|(label, value)| value.map(|value| (label, value)))
+            .collect()
+    }
+}
+
+/// Bytes in the largest unit that keeps them above 1, e.g. "8.4 MB"
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+impl RouteHandler for TrackInfoRoute {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
+        self.under.render(frame, area, state);
+
+        let lines: Vec<Line> = self
+            .rows()
+            .into_iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<width$}", label, width = LABEL_WIDTH),
+                        Style::default().fg(Color::Gray),
+                    ),
+                    Span::raw(value),
+                ])
+            })
+            .collect();
+
+        let width = area.width.saturating_sub(10).min(80);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let dialog_area = Rect::new(x, y, width, height);
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Track Info ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+        let paragraph = Paragraph::new(lines).block(block).scroll((self.scroll, 0));
+        frame.render_widget(paragraph, dialog_area);
+    }
+
+    fn handle_input(
+        &mut self,
+        _key: KeyCode,
+        _state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> anyhow::Result<RouteAction> {
+        Ok(RouteAction::None)
+    }
+
+    fn name(&self) -> &str {
+        "Track Info"
+    }
+
+    // The panel is modal: only quitting gets through
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        _state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        match key {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('i') => {
+                return InterceptKeyResult::HandledAndNavigate(RouteAction::Pop);
+            }
+            KeyCode::Char('q') => return InterceptKeyResult::Ignored,
+            _ => {}
+        }
+        InterceptKeyResult::Handled
+    }
+}
@@ -0,0 +1,291 @@
+use audido_core::{commands::AudioCommand, engine::AudioEngineHandle};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+
+use crate::{
+    router::{InterceptKeyResult, RouteAction, RouteHandler},
+    routes::playback::PlaybackRoute,
+    state::AppState,
+    states::{AudioState, LibraryEntry, LibraryState, SearchInput, library::LibraryLevel},
+};
+
+/// Library route - artist/album/track and genre drill-down over the library index
+#[derive(Debug, Clone)]
+pub struct LibraryRoute;
+
+impl RouteHandler for LibraryRoute {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
+        draw_library_panel(frame, area, &state.library);
+    }
+
+    fn handle_input(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        handle: &AudioEngineHandle,
+    ) -> anyhow::Result<RouteAction> {
+        let library = &mut state.library;
+        match library.search.handle_key(key) {
+            SearchInput::Changed => {
+                library.apply_search();
+                return Ok(RouteAction::None);
+            }
+            SearchInput::Handled => return Ok(RouteAction::None),
+            SearchInput::Ignored => {}
+        }
+
+        match key {
+            KeyCode::Char('/') => library.search.start(),
+            KeyCode::Up => library.prev(),
+            KeyCode::Down => library.next(),
+            KeyCode::Enter
+                if library
+                    .selected_entry()
+                    .is_some_and(|entry| matches!(entry, LibraryEntry::Track { .. })) =>
+            {
+                return play_from_selected(library, handle);
+            }
+            KeyCode::Enter | KeyCode::Right => library.drill_down(),
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Esc => library.go_back(),
+            KeyCode::Char('p') => {
+                if let Some(paths) = selected_paths(library) {
+                    handle
+                        .cmd_tx
+                        .send(AudioCommand::ReplaceQueue { paths, start: 0 })?;
+                    return Ok(RouteAction::Replace(Box::new(PlaybackRoute)));
+                }
+            }
+            KeyCode::Char('a') => {
+                if let Some(paths) = selected_paths(library) {
+                    state.audio.status_message = format!("Added {} tracks to queue", paths.len());
+                    handle.cmd_tx.send(AudioCommand::AddToQueue(paths))?;
+                }
+            }
+            KeyCode::Char('n') => {
+                if let Some(paths) = selected_paths(library) {
+                    state.audio.status_message = format!("Playing {} tracks next", paths.len());
+                    handle.cmd_tx.send(AudioCommand::PlayNext(paths))?;
+                }
+            }
+            KeyCode::Char('v') => library.toggle_view(),
+            KeyCode::Char('r') => library.start_scan(),
+            _ => {}
+        }
+        Ok(RouteAction::None)
+    }
+
+    fn name(&self) -> &str {
+        "Library"
+    }
+
+    fn on_enter(
+        &mut self,
+        state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> anyhow::Result<()> {
+        // Scan once per run when the tab is first opened; 'r' rescans
+        if state.library.library.is_none() && !state.library.is_scanning() {
+            state.library.start_scan();
+        }
+        Ok(())
+    }
+
+    // While typing a search query, keys like 'q' and Tab are text
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        if !state.library.search.editing {
+            return InterceptKeyResult::Ignored;
+        }
+        match state.library.search.handle_key(key) {
+            SearchInput::Changed => {
+                state.library.apply_search();
+                InterceptKeyResult::Handled
+            }
+            SearchInput::Handled => InterceptKeyResult::Handled,
+            SearchInput::Ignored => InterceptKeyResult::Ignored,
+        }
+    }
+}
+
+/// On a track: replace the queue with the shown tracks and play from the selected one
+fn play_from_selected(
+    library: &LibraryState,
+    handle: &AudioEngineHandle,
+) -> anyhow::Result<RouteAction> {
+    let Some(selected) = library.list_state.selected() else {
+        return Ok(RouteAction::None);
+    };
+    let paths: Vec<String> = library
+        .visible_entries()
+        .flat_map(|entry| library.entry_paths(entry))
+        .collect();
+    handle.cmd_tx.send(AudioCommand::ReplaceQueue {
+        paths,
+        start: selected,
+    })?;
+    Ok(RouteAction::Replace(Box::new(PlaybackRoute)))
+}
+
+/// Files of the selected entry, `None` when there is nothing to queue
+fn selected_paths(library: &LibraryState) -> Option<Vec<String>> {
+    let paths = library.entry_paths(library.selected_entry()?);
+    (!paths.is_empty()).then_some(paths)
+}
+
+pub fn draw_library_panel(f: &mut Frame, area: Rect, library_state: &LibraryState) {
+    let title = if library_state.is_scanning() {
+        format!(" Library: {} (scanning...) ", library_state.breadcrumb())
+    } else {
+        format!(" Library: {} ", library_state.breadcrumb())
+    };
+    let mut block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    if let Some(search) = library_state.search.title() {
+        block = block.title_bottom(search);
+    } else if let Some(status) = &library_state.status {
+        block = block.title_bottom(format!(" {} ", status));
+    }
+
+    let entries: Vec<&LibraryEntry> = library_state.visible_entries().collect();
+    if entries.is_empty() {
+        let message = if library_state.library.is_none() {
+            "Loading library..."
+        } else if library_state.search.is_filtering() {
+            "Nothing matches the search."
+        } else {
+            match library_state.level {
+                LibraryLevel::Playlists => {
+                    "No smart playlists. Define them in smart_playlists.toml and press r."
+                }
+                LibraryLevel::StatsTracks { .. } => "Nothing played yet.",
+                _ => "Nothing here. Add music folders to library.toml and press r to rescan.",
+            }
+        };
+        let paragraph = Paragraph::new(message)
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let detail_style = Style::default().fg(Color::DarkGray);
+    let items: Vec<ListItem> = entries
+        .into_iter()
+        .map(|entry| {
+            let spans = match entry {
+                LibraryEntry::Artist {
+                    name,
+                    album_count,
+                    track_count,
+                    duration,
+                } => vec![
+                    Span::styled("👤 ", Style::default().fg(Color::Blue)),
+                    Span::raw(name.clone()),
+                    Span::styled(
+                        format!(
+                            "  {} albums, {} tracks, {}",
+                            album_count,
+                            track_count,
+                            AudioState::format_time(*duration)
+                        ),
+                        detail_style,
+                    ),
+                ],
+                LibraryEntry::Album {
+                    album,
+                    track_count,
+                    duration,
+                    ..
+                } => vec![
+                    Span::styled("💿 ", Style::default().fg(Color::Blue)),
+                    Span::raw(album.clone()),
+                    Span::styled(
+                        format!(
+                            "  {} tracks, {}",
+                            track_count,
+                            AudioState::format_time(*duration)
+                        ),
+                        detail_style,
+                    ),
+                ],
+                LibraryEntry::Genre { name, track_count } => vec![
+                    Span::styled("🏷 ", Style::default().fg(Color::Green)),
+                    Span::raw(name.clone()),
+                    Span::styled(format!("  {} tracks", track_count), detail_style),
+                ],
+                LibraryEntry::Playlist {
+                    name,
+                    track_count,
+                    duration,
+                } => vec![
+                    Span::styled("✨ ", Style::default().fg(Color::Magenta)),
+                    Span::raw(name.clone()),
+                    Span::styled(
+                        format!(
+                            "  {} tracks, {}",
+                            track_count,
+                            AudioState::format_time(*duration)
+                        ),
+                        detail_style,
+                    ),
+                ],
+                LibraryEntry::StatsList { list, track_count } => vec![
+                    Span::styled("📊 ", Style::default().fg(Color::Yellow)),
+                    Span::raw(list.label()),
+                    Span::styled(format!("  {} tracks", track_count), detail_style),
+                ],
+                LibraryEntry::Track {
+                    title,
+                    artist,
+                    duration,
+                    detail,
+                    ..
+                } => {
+                    let mut spans = vec![Span::raw("🎵 "), Span::raw(title.clone())];
+                    if let Some(artist) = artist {
+                        spans.push(Span::styled(format!(" - {}", artist), detail_style));
+                    }
+                    // Played files outside the library have no known duration
+                    if *duration > 0.0 {
+                        spans.push(Span::styled(
+                            format!("  {}", AudioState::format_time(*duration)),
+                            detail_style,
+                        ));
+                    }
+                    if let Some(detail) = detail {
+                        spans.push(Span::styled(
+                            format!("  {}", detail),
+                            Style::default().fg(Color::Yellow),
+                        ));
+                    }
+                    spans
+                }
+            };
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let mut list_state = library_state.list_state;
+    f.render_stateful_widget(list, area, &mut list_state);
+}
@@ -1,6 +1,12 @@
+pub mod analysis;
 pub mod browser;
 pub mod eq;
+pub mod info;
+pub mod library;
 pub mod log;
 pub mod playback;
 pub mod queue;
+pub mod session;
 pub mod settings;
+pub mod tags;
+pub mod url;
@@ -1,17 +1,24 @@
-use audido_core::{commands::AudioCommand, engine::AudioEngineHandle};
+use audido_core::{
+    analysis::Analysis,
+    commands::AudioCommand,
+    engine::AudioEngineHandle,
+    lyrics::{LyricLine, Lyrics},
+};
 use ratatui::{
     Frame,
     crossterm::event::KeyCode,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Gauge, Paragraph},
 };
 
 use crate::{
+    graphics,
     router::{RouteAction, RouteHandler},
+    routes::info::TrackInfoRoute,
     state::AppState,
-    states::AudioState,
+    states::{AudioState, CoverState},
 };
 
 // ==================================================================
@@ -23,7 +30,7 @@ pub struct PlaybackRoute;
 
 impl RouteHandler for PlaybackRoute {
     fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
-        draw_playback_panel(frame, area, &state.audio);
+        draw_playback_panel(frame, area, &state.audio, &state.cover);
     }
 
     fn handle_input(
@@ -73,6 +80,12 @@ impl RouteHandler for PlaybackRoute {
                 let next_mode = state.next_loop_mode();
                 handle.cmd_tx.send(AudioCommand::SetLoopMode(next_mode))?;
             }
+            KeyCode::Char('i') => {
+                if let Some(metadata) = &state.audio.metadata {
+                    let info = TrackInfoRoute::new(metadata.clone(), self.name());
+                    return Ok(RouteAction::Push(Box::new(info)));
+                }
+            }
             _ => {}
         }
         Ok(RouteAction::None)
@@ -84,7 +97,12 @@ impl RouteHandler for PlaybackRoute {
 }
 
 /// Draw the playback panel
-pub fn draw_playback_panel(f: &mut Frame, area: Rect, audio_state: &AudioState) {
+pub fn draw_playback_panel(
+    f: &mut Frame,
+    area: Rect,
+    audio_state: &AudioState,
+    cover: &CoverState,
+) {
     // Panel is active when rendered (router-based system)
     let is_active = true;
 
@@ -94,12 +112,119 @@ pub fn draw_playback_panel(f: &mut Frame, area: Rect, audio_state: &AudioState)
             Constraint::Length(6), // Now playing info
             Constraint::Length(3), // Progress bar
             Constraint::Length(3), // Controls info
-            Constraint::Min(0),    // Status/spacer
+            Constraint::Min(0),    // Cover art
         ])
         .split(area);
 
     draw_now_playing(f, chunks[0], audio_state, is_active);
     draw_progress(f, chunks[1], audio_state);
+
+    // Cover and lyrics share what's left, side by side when there are both
+    let lyrics = audio_state
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.lyric.as_deref());
+    match (cover.art.is_some(), lyrics) {
+        (true, Some(lyrics)) => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(chunks[3]);
+            draw_cover(f, columns[0], cover);
+            draw_lyrics(f, columns[1], audio_state, lyrics);
+        }
+        (false, Some(lyrics)) => draw_lyrics(f, chunks[3], audio_state, lyrics),
+        _ => draw_cover(f, chunks[3], cover),
+    }
+}
+
+/// Draw the lyrics, following playback: synced lyrics highlight the current line
+/// (and its sung words with word timing) and keep it in the upper third, plain
+/// lyrics scroll along with the progress
+fn draw_lyrics(f: &mut Frame, area: Rect, audio_state: &AudioState, lyrics: &Lyrics) {
+    let block = Block::default()
+        .title(if lyrics.synced {
+            " 🎤 Lyrics "
+        } else {
+            " 🎤 Lyrics (unsynced) "
+        })
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let height = inner.height as usize;
+    if height == 0 {
+        return;
+    }
+
+    let position = audio_state.position;
+    let current = lyrics.line_at(position);
+    let scrollable = lyrics.lines.len().saturating_sub(height);
+    let top = if lyrics.synced {
+        current
+            .unwrap_or(0)
+            .saturating_sub(height / 3)
+            .min(scrollable)
+    } else {
+        (audio_state.progress() * scrollable as f32) as usize
+    };
+
+    let lines: Vec<Line> = lyrics
+        .lines
+        .iter()
+        .enumerate()
+        .skip(top)
+        .take(height)
+        .map(|(i, line)| match current {
+            Some(current) if i == current => current_lyric_line(line, position),
+            Some(current) if i < current => {
+                Line::styled(line.text.as_str(), Style::default().fg(Color::DarkGray))
+            }
+            _ => Line::styled(line.text.as_str(), Style::default().fg(Color::Gray)),
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), inner);
+}
+
+/// The line being sung, with the words already sung highlighted when it has
+/// word timing
+fn current_lyric_line(line: &LyricLine, position: f32) -> Line<'_> {
+    let sung = Style::default().fg(Color::Yellow).bold();
+    if line.text.is_empty() {
+        return Line::styled("♪", sung);
+    }
+    if line.words.is_empty() {
+        return Line::styled(line.text.as_str(), sung);
+    }
+    let upcoming = Style::default().fg(Color::White).bold();
+    let sung_count = line.words_sung(position);
+    Line::from(
+        line.words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let style = if i < sung_count { sung } else { upcoming };
+                Span::styled(word.text.as_str(), style)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Draw the cover art of the loaded file in whatever space is left
+fn draw_cover(f: &mut Frame, area: Rect, cover: &CoverState) {
+    let Some(art) = &cover.art else {
+        return;
+    };
+    let block = Block::default()
+        .title(" 🖼 Cover ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let inner = block.inner(area);
+    if inner.height < 2 || inner.width < 4 {
+        return;
+    }
+    f.render_widget(block, area);
+    graphics::render_cover(f, inner, art, cover);
 }
 
 /// Draw the now playing section
@@ -135,6 +260,10 @@ fn draw_now_playing(f: &mut Frame, area: Rect, audio_state: &AudioState, is_acti
                 album,
                 Style::default().fg(Color::DarkGray),
             )]),
+            Line::from(vec![Span::styled(
+                Analysis::from(metadata).to_string(),
+                Style::default().fg(Color::DarkGray),
+            )]),
         ];
 
         let paragraph = Paragraph::new(text);
@@ -149,7 +278,10 @@ fn draw_now_playing(f: &mut Frame, area: Rect, audio_state: &AudioState, is_acti
 fn draw_progress(f: &mut Frame, area: Rect, audio_state: &AudioState) {
     let progress_pct = (audio_state.progress() * 100.0) as u16;
     let position_str = AudioState::format_time(audio_state.position);
-    let duration_str = AudioState::format_time(audio_state.duration);
+    let duration_str = match audio_state.duration {
+        Some(duration) => AudioState::format_time(duration),
+        None => "LIVE".to_string(),
+    };
 
     let label = format!("{} / {}", position_str, duration_str);
 
@@ -161,29 +293,3 @@ fn draw_progress(f: &mut Frame, area: Rect, audio_state: &AudioState) {
 
     f.render_widget(gauge, area);
 }
-
-// ==================================================================
-// Metadata Route Implementation
-// ==================================================================
-
-// #[derive(Debug, Clone)]
-// pub struct SongMetadataRoute;
-
-// impl RouteHandler for SongMetadataRoute {
-//     fn render(&self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect, state: &crate::state::AppState) {
-//         todo!()
-//     }
-
-//     fn handle_input(
-//         &mut self,
-//         key: ratatui::crossterm::event::KeyCode,
-//         state: &mut crate::state::AppState,
-//         handle: &audido_core::engine::AudioEngineHandle,
-//     ) -> anyhow::Result<crate::router::RouteAction> {
-//         todo!()
-//     }
-
-//     fn name(&self) -> &str {
-//         todo!()
-//     }
-// }
@@ -1,16 +1,27 @@
-use audido_core::engine::AudioEngineHandle;
+use audido_core::{
+    commands::AudioCommand,
+    engine::AudioEngineHandle,
+    harmonic::{Compatibility, Transition},
+    queue::QueueItem,
+};
 use ratatui::{
     Frame,
     crossterm::event::KeyCode,
     layout::Rect,
     style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph},
 };
 
 use crate::{
-    router::{RouteAction, RouteHandler},
+    router::{InterceptKeyResult, RouteAction, RouteHandler},
+    routes::{
+        info::TrackInfoRoute,
+        tags::{TagEditorOrigin, TagEditorRoute},
+        url::UrlInputRoute,
+    },
     state::AppState,
-    states::QueueState,
+    states::{QueueState, SearchInput},
 };
 
 /// Queue route
@@ -28,15 +39,117 @@ impl RouteHandler for QueueRoute {
         state: &mut AppState,
         handle: &AudioEngineHandle,
     ) -> anyhow::Result<RouteAction> {
+        match state.queue.search.handle_key(key) {
+            SearchInput::Changed => {
+                state.queue.apply_search();
+                return Ok(RouteAction::None);
+            }
+            SearchInput::Handled => return Ok(RouteAction::None),
+            SearchInput::Ignored => {}
+        }
+
         match key {
+            KeyCode::Char('/') => state.queue.search.start(),
+            KeyCode::Char('k' | 'j' | 'n') if state.queue.search.is_filtering() => {
+                // Neighbours are hidden while filtering, so moves would be guesswork
+                state.audio.status_message = "Clear the search to reorder the queue".to_string();
+            }
             KeyCode::Up => state.queue_prev(),
             KeyCode::Down => state.queue_next(),
             KeyCode::Enter => {
                 if let Some(idx) = state.queue_selected() {
-                    handle
-                        .cmd_tx
-                        .send(audido_core::commands::AudioCommand::PlayQueueIndex(idx))?;
+                    handle.cmd_tx.send(AudioCommand::PlayQueueIndex(idx))?;
+                }
+            }
+            KeyCode::Char('k') => move_selected(state, handle, -1)?,
+            KeyCode::Char('j') => move_selected(state, handle, 1)?,
+            KeyCode::Char('n') => {
+                // Reorder the selected track to play right after the current one
+                if let (Some(selected), Some(current)) =
+                    (state.queue_selected(), state.queue.current_queue_index)
+                    && selected != current
+                {
+                    let id = state.queue.queue[selected].id;
+                    let to = if selected < current {
+                        current
+                    } else {
+                        current + 1
+                    };
+                    handle.cmd_tx.send(AudioCommand::MoveQueueItem { id, to })?;
+                    state.queue.select_index(to);
+                }
+            }
+            KeyCode::Char('m') => {
+                if let Some(id) = state.queue.selected_item().map(|item| item.id) {
+                    state.queue.toggle_mark(id);
+                    state.queue_next();
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                // Marked tracks if any, otherwise the one under the cursor
+                if !state.queue.marked.is_empty() {
+                    let ids: Vec<usize> = state.queue.marked.drain().collect();
+                    handle.cmd_tx.send(AudioCommand::RemoveMany(ids))?;
+                } else if let Some(id) = state.queue.selected_item().map(|item| item.id) {
+                    handle.cmd_tx.send(AudioCommand::RemoveFromQueue(id))?;
+                }
+            }
+            KeyCode::Char('D') => {
+                handle.cmd_tx.send(AudioCommand::Dedupe)?;
+                state.audio.status_message = "Removed duplicate tracks".to_string();
+            }
+            KeyCode::Char('s') if !state.queue.is_empty() => {
+                let field = state.queue.take_sort_field();
+                handle.cmd_tx.send(AudioCommand::SortBy(field))?;
+                state.audio.status_message = format!("Queue sorted by {}", field);
+            }
+            KeyCode::Char('h') if !state.queue.is_empty() => {
+                handle.cmd_tx.send(AudioCommand::SortQueueHarmonic)?;
+                state.audio.status_message = "Queue ordered for harmonic mixing".to_string();
+            }
+            KeyCode::Char('g') => {
+                // Jump to the current track
+                if let Some(current) = state.queue.current_queue_index {
+                    state.queue.select_index(current);
+                }
+            }
+            KeyCode::Char('e') => {
+                let files = state.queue.tag_targets();
+                if files.is_empty() {
+                    state.audio.status_message =
+                        "CUE tracks and streams can't be tagged".to_string();
+                    return Ok(RouteAction::None);
+                }
+                match TagEditorRoute::open(files, TagEditorOrigin::Queue) {
+                    Ok(editor) => return Ok(RouteAction::Push(Box::new(editor))),
+                    Err(e) => state.audio.status_message = format!("Cannot read tags: {}", e),
+                }
+            }
+            KeyCode::Char('i') => match state.queue.selected_item() {
+                Some(QueueItem {
+                    metadata: Some(metadata),
+                    ..
+                }) => {
+                    let info = TrackInfoRoute::new(metadata.clone(), self.name());
+                    return Ok(RouteAction::Push(Box::new(info)));
                 }
+                Some(_) => state.audio.status_message = "Track details not read yet".to_string(),
+                None => {}
+            },
+            KeyCode::Char('u') => return Ok(RouteAction::Push(Box::new(UrlInputRoute::page()))),
+            KeyCode::Char('r') => {
+                return Ok(RouteAction::Push(Box::new(UrlInputRoute::stream())));
+            }
+            KeyCode::Char('w') if !state.queue.queue.is_empty() => {
+                // Save next to what the browser is showing, or the working directory
+                let file_name = format!(
+                    "audido-queue-{}.m3u8",
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                );
+                let path = state.browser.current_dir.join(file_name);
+                handle.cmd_tx.send(AudioCommand::SavePlaylist(
+                    path.to_string_lossy().to_string(),
+                ))?;
             }
             _ => {}
         }
@@ -46,6 +159,83 @@ impl RouteHandler for QueueRoute {
     fn name(&self) -> &str {
         "Queue"
     }
+
+    // While typing a search query, keys like 'q' and Tab are text
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        if !state.queue.search.editing {
+            return InterceptKeyResult::Ignored;
+        }
+        match state.queue.search.handle_key(key) {
+            SearchInput::Changed => {
+                state.queue.apply_search();
+                InterceptKeyResult::Handled
+            }
+            SearchInput::Handled => InterceptKeyResult::Handled,
+            SearchInput::Ignored => InterceptKeyResult::Ignored,
+        }
+    }
+}
+
+/// Move the selected item by `offset` positions, keeping it selected
+fn move_selected(
+    state: &mut AppState,
+    handle: &AudioEngineHandle,
+    offset: isize,
+) -> anyhow::Result<()> {
+    let Some(selected) = state.queue_selected() else {
+        return Ok(());
+    };
+    let Some(to) = selected
+        .checked_add_signed(offset)
+        .filter(|to| *to < state.queue.queue.len())
+    else {
+        return Ok(());
+    };
+    let id = state.queue.queue[selected].id;
+    handle.cmd_tx.send(AudioCommand::MoveQueueItem { id, to })?;
+    state.queue.select_index(to);
+    Ok(())
+}
+
+/// Key, tempo and how well the track mixes in from the one before it, for
+/// tracks that have a key or tempo
+fn mix_info(queue_state: &QueueState, index: usize) -> Vec<Span<'static>> {
+    let Some(metadata) = queue_state.queue[index].metadata.as_ref() else {
+        return Vec::new();
+    };
+    let details: Vec<String> = [
+        metadata.key.map(|key| key.camelot_code()),
+        metadata.bpm.map(|bpm| format!("{:.0} BPM", bpm)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if details.is_empty() {
+        return Vec::new();
+    }
+
+    let mut spans = vec![Span::styled(
+        format!("  {}", details.join(" · ")),
+        Style::default().fg(Color::DarkGray),
+    )];
+    let previous = index
+        .checked_sub(1)
+        .and_then(|i| queue_state.queue[i].metadata.as_ref());
+    if let Some(previous) = previous {
+        let (symbol, color) = match Transition::between(previous, metadata).compatibility() {
+            Compatibility::Harmonic => (" ✓", Color::Green),
+            Compatibility::Compatible => (" ~", Color::Yellow),
+            Compatibility::Clash => (" ✗", Color::Red),
+            Compatibility::Unknown => ("", Color::DarkGray),
+        };
+        spans.push(Span::styled(symbol, Style::default().fg(color)));
+    }
+    spans
 }
 
 /// Draw the queue panel
@@ -53,8 +243,16 @@ pub fn draw_queue_panel(f: &mut Frame, area: Rect, queue_state: &QueueState) {
     // Panel is active when rendered (router-based system)
     let is_active = true;
 
-    let title = format!(" Queue ({} tracks) ", queue_state.queue.len());
-    let block = Block::default()
+    let title = if queue_state.marked.is_empty() {
+        format!(" Queue ({} tracks) ", queue_state.queue.len())
+    } else {
+        format!(
+            " Queue ({} tracks, {} marked) ",
+            queue_state.queue.len(),
+            queue_state.marked.len()
+        )
+    };
+    let mut block = Block::default()
         .title(title)
         .borders(Borders::ALL)
         .border_style(if is_active {
@@ -62,14 +260,23 @@ pub fn draw_queue_panel(f: &mut Frame, area: Rect, queue_state: &QueueState) {
         } else {
             Style::default()
         });
+    if let Some(search) = queue_state.search.title() {
+        block = block.title_bottom(search);
+    }
 
     let items: Vec<ListItem> = queue_state
-        .queue
-        .iter()
-        .enumerate()
-        .map(|(i, item)| {
+        .search
+        .visible(queue_state.queue.len())
+        .into_iter()
+        .map(|i| {
+            let item = &queue_state.queue[i];
             let is_current = queue_state.current_queue_index == Some(i);
             let prefix = if is_current { "▶ " } else { "  " };
+            let mark = if queue_state.marked.contains(&item.id) {
+                "● "
+            } else {
+                ""
+            };
             let name = item
                 .metadata
                 .as_ref()
@@ -87,12 +294,19 @@ pub fn draw_queue_panel(f: &mut Frame, area: Rect, queue_state: &QueueState) {
             } else {
                 Style::default().fg(Color::White)
             };
-            ListItem::new(format!("{}{}", prefix, name)).style(style)
+            let mut spans = vec![Span::styled(format!("{}{}{}", prefix, mark, name), style)];
+            spans.extend(mix_info(queue_state, i));
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
     if items.is_empty() {
-        let empty_msg = Paragraph::new("Queue is empty. Add files from Browser.")
+        let message = if queue_state.search.is_filtering() {
+            "No tracks match the search."
+        } else {
+            "Queue is empty. Add files from Browser."
+        };
+        let empty_msg = Paragraph::new(message)
             .style(Style::default().fg(Color::DarkGray))
             .block(block);
         f.render_widget(empty_msg, area);
@@ -0,0 +1,84 @@
+use audido_core::{commands::AudioCommand, engine::AudioEngineHandle, session::Session};
+use ratatui::{Frame, crossterm::event::KeyCode, layout::Rect};
+
+use crate::{
+    router::{InterceptKeyResult, RouteAction, RouteHandler},
+    routes::playback::PlaybackRoute,
+    state::AppState,
+    ui::{DialogProperties, draw_generic_dialog},
+};
+
+/// Startup prompt offering to resume the previous session, shown over the playback panel
+#[derive(Debug, Clone)]
+pub struct SessionRestoreRoute {
+    session: Session,
+    /// 0 = Restore, 1 = Start Fresh
+    selected: usize,
+}
+
+impl SessionRestoreRoute {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            selected: 0,
+        }
+    }
+}
+
+impl RouteHandler for SessionRestoreRoute {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
+        PlaybackRoute.render(frame, area, state);
+
+        let title = format!("Resume session? ({} tracks)", self.session.queue.len());
+        let restore = match self.session.current_track_name() {
+            Some(name) => format!("Restore: {}", name),
+            None => "Restore".to_string(),
+        };
+        let props = DialogProperties {
+            title: &title,
+            options: vec![&restore, "Start Fresh"],
+            selected_index: self.selected,
+        };
+        draw_generic_dialog(frame, area, props);
+    }
+
+    fn handle_input(
+        &mut self,
+        _key: KeyCode,
+        _state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> anyhow::Result<RouteAction> {
+        Ok(RouteAction::None)
+    }
+
+    fn name(&self) -> &str {
+        "Restore Session"
+    }
+
+    // The prompt is modal: only quitting gets through
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        match key {
+            KeyCode::Up | KeyCode::Down => {
+                self.selected = 1 - self.selected;
+                InterceptKeyResult::Handled
+            }
+            KeyCode::Enter => {
+                if self.selected == 0 {
+                    let _ = handle
+                        .cmd_tx
+                        .send(AudioCommand::RestoreSession(self.session.clone()));
+                    state.audio.status_message = "Restoring session...".to_string();
+                }
+                InterceptKeyResult::HandledAndNavigate(RouteAction::Pop)
+            }
+            KeyCode::Esc => InterceptKeyResult::HandledAndNavigate(RouteAction::Pop),
+            KeyCode::Char('q') => InterceptKeyResult::Ignored,
+            _ => InterceptKeyResult::Handled,
+        }
+    }
+}
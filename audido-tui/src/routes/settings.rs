@@ -2,7 +2,7 @@
 // Concrete Route Implementations
 // ============================================================================
 
-use audido_core::engine::AudioEngineHandle;
+use audido_core::{analysis::TagWriteMode, commands::AudioCommand, engine::AudioEngineHandle};
 use ratatui::{
     Frame,
     crossterm::event::KeyCode,
@@ -32,11 +32,22 @@ impl RouteHandler for SettingsRoute {
         &mut self,
         key: KeyCode,
         state: &mut AppState,
-        _handle: &AudioEngineHandle,
+        handle: &AudioEngineHandle,
     ) -> anyhow::Result<RouteAction> {
         match key {
             KeyCode::Up => state.settings.prev_item(),
             KeyCode::Down => state.settings.next_item(),
+            KeyCode::Enter if state.settings.selected() == Some(SettingsOption::AnalysisTags) => {
+                let mode = match state.settings.analysis_tags {
+                    TagWriteMode::Never => TagWriteMode::Ask,
+                    TagWriteMode::Ask => TagWriteMode::Always,
+                    TagWriteMode::Always => TagWriteMode::Never,
+                };
+                handle
+                    .cmd_tx
+                    .send(AudioCommand::SetAnalysisTagWrite(mode))?;
+                state.settings.analysis_tags = mode;
+            }
             KeyCode::Enter => {
                 // Navigate to EQ panel
                 return Ok(RouteAction::Push(Box::new(EqualizerRoute::default())));
@@ -60,7 +71,14 @@ pub fn draw_settings_panel(
 ) {
     // Panel is active when rendered (router-based system)
     let is_active = true;
-    draw_settings_list(f, area, settings_state, eq_state, normalizer_state, is_active);
+    draw_settings_list(
+        f,
+        area,
+        settings_state,
+        eq_state,
+        normalizer_state,
+        is_active,
+    );
 }
 
 fn draw_settings_list(
@@ -90,6 +108,7 @@ fn draw_settings_list(
         .map(|(i, setting)| {
             let is_selected = settings_state.selected_index == i && !settings_state.is_dialog_open;
 
+            let analysis_tags = settings_state.analysis_tags.to_string();
             let value_str = match setting {
                 SettingsOption::Equalizer => {
                     if eq_state.eq_enabled {
@@ -104,7 +123,8 @@ fn draw_settings_list(
                     } else {
                         "Off"
                     }
-                },
+                }
+                SettingsOption::AnalysisTags => analysis_tags.as_str(),
             };
 
             let prefix = if is_selected { "▶ " } else { "  " };
@@ -0,0 +1,360 @@
+use std::path::PathBuf;
+
+use audido_core::{
+    commands::AudioCommand,
+    engine::AudioEngineHandle,
+    tags::{self, TagChange, TagField, TagValues},
+};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    router::{InterceptKeyResult, RouteAction, RouteHandler},
+    routes::{browser::BrowserRoute, queue::QueueRoute},
+    state::AppState,
+};
+
+/// Tab the editor was opened from, drawn behind it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagEditorOrigin {
+    Browser,
+    Queue,
+}
+
+/// A field a change alters in one file: the field, its old and its new value
+type FieldDiff<'a> = (TagField, Option<&'a str>, Option<String>);
+
+/// One field in the editor
+#[derive(Debug, Clone)]
+struct FieldEdit {
+    field: TagField,
+    value: String,
+    /// The files hold different values, and the field wasn't touched yet
+    mixed: bool,
+    changed: bool,
+    /// Per-track field while editing several files, left alone
+    locked: bool,
+}
+
+/// Tag editor for one or more files, shown over the tab it was opened from.
+/// Changes are previewed file by file before they are written.
+#[derive(Debug, Clone)]
+pub struct TagEditorRoute {
+    origin: TagEditorOrigin,
+    files: Vec<PathBuf>,
+    /// Tags of each file when the editor opened
+    original: Vec<TagValues>,
+    fields: Vec<FieldEdit>,
+    selected: usize,
+    /// Showing the changes for confirmation, scrolled by this many lines
+    preview: Option<usize>,
+    /// Shown when the edits can't be previewed
+    error: Option<String>,
+}
+
+impl TagEditorRoute {
+    /// Read the tags of the files to edit
+    pub fn open(files: Vec<PathBuf>, origin: TagEditorOrigin) -> anyhow::Result<Self> {
+        let original = files
+            .iter()
+            .map(|file| tags::read_tag_values(file))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let batch = files.len() > 1;
+        let fields: Vec<FieldEdit> = TagField::iter()
+            .map(|field| {
+                let first = original.first().and_then(|values| values.get(field));
+                let mixed = original.iter().any(|values| values.get(field) != first);
+                FieldEdit {
+                    field,
+                    value: if mixed {
+                        String::new()
+                    } else {
+                        first.unwrap_or_default().to_string()
+                    },
+                    mixed,
+                    changed: false,
+                    locked: batch && field.is_per_track(),
+                }
+            })
+            .collect();
+        let selected = fields.iter().position(|edit| !edit.locked).unwrap_or(0);
+
+        Ok(Self {
+            origin,
+            files,
+            original,
+            fields,
+            selected,
+            preview: None,
+            error: None,
+        })
+    }
+
+    /// Changes to write, one per edited field
+    fn changes(&self) -> Vec<TagChange> {
+        self.fields
+            .iter()
+            .filter(|edit| edit.changed)
+            .map(|edit| TagChange::new(edit.field, &edit.value))
+            .collect()
+    }
+
+    /// Per file, the fields the changes alter
+    fn diff(&self) -> Vec<(&PathBuf, Vec<FieldDiff<'_>>)> {
+        let changes = self.changes();
+        self.files
+            .iter()
+            .zip(&self.original)
+            .map(|(file, values)| {
+                let altered = changes
+                    .iter()
+                    .filter(|change| values.get(change.field) != change.value.as_deref())
+                    .map(|change| (change.field, values.get(change.field), change.value.clone()))
+                    .collect();
+                (file, altered)
+            })
+            .collect()
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let len = self.fields.len() as isize;
+        let mut index = self.selected as isize;
+        for _ in 0..len {
+            index = (index + offset).rem_euclid(len);
+            if !self.fields[index as usize].locked {
+                self.selected = index as usize;
+                return;
+            }
+        }
+    }
+
+    fn edit_selected(&mut self, edit: impl FnOnce(&mut String)) {
+        if let Some(field) = self.fields.get_mut(self.selected)
+            && !field.locked
+        {
+            edit(&mut field.value);
+            field.mixed = false;
+            field.changed = true;
+            self.error = None;
+        }
+    }
+
+    /// Check the edits and show the preview
+    fn start_preview(&mut self) {
+        let changes = self.changes();
+        if let Some(e) = changes.iter().find_map(|change| change.validate().err()) {
+            self.error = Some(e.to_string());
+        } else if self.diff().iter().all(|(_, altered)| altered.is_empty()) {
+            self.error = Some("Nothing to change".to_string());
+        } else {
+            self.preview = Some(0);
+        }
+    }
+
+    /// Send the changes for the files they alter
+    fn confirm(&self, state: &mut AppState, handle: &AudioEngineHandle) {
+        let paths: Vec<String> = self
+            .diff()
+            .into_iter()
+            .filter(|(_, altered)| !altered.is_empty())
+            .map(|(file, _)| file.to_string_lossy().to_string())
+            .collect();
+        let count = paths.len();
+        let command = AudioCommand::WriteTags {
+            paths,
+            changes: self.changes(),
+        };
+        if handle.cmd_tx.send(command).is_ok() {
+            state.audio.status_message = format!("Writing tags to {} files...", count);
+            match self.origin {
+                TagEditorOrigin::Browser => state.browser.marked.clear(),
+                TagEditorOrigin::Queue => state.queue.marked.clear(),
+            }
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.files.as_slice() {
+            [file] => format!(
+                " Edit Tags: {} ",
+                file.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            ),
+            files => format!(" Edit Tags: {} files ", files.len()),
+        }
+    }
+
+    fn field_lines(&self) -> Vec<Line<'_>> {
+        let mut lines: Vec<Line> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, edit)| {
+                let is_selected = i == self.selected;
+                let label_style = if is_selected {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let dim = Style::default().fg(Color::DarkGray);
+                let value = if edit.locked {
+                    Span::styled("(differs per track)", dim)
+                } else if edit.mixed {
+                    Span::styled("<various>", dim)
+                } else if edit.changed {
+                    Span::styled(edit.value.as_str(), Style::default().fg(Color::Yellow))
+                } else {
+                    Span::raw(edit.value.as_str())
+                };
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{} {:<8} ", if is_selected { ">" } else { " " }, edit.field),
+                        label_style,
+                    ),
+                    value,
+                ];
+                if is_selected {
+                    spans.push(Span::raw("▏"));
+                }
+                Line::from(spans)
+            })
+            .collect();
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(match &self.error {
+            Some(error) => Span::styled(error.as_str(), Style::default().fg(Color::Red)),
+            None => Span::styled(
+                "Type to edit · Del clear · Enter preview · Esc cancel",
+                Style::default().fg(Color::DarkGray),
+            ),
+        }));
+        lines
+    }
+
+    fn preview_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for (file, altered) in self.diff() {
+            if altered.is_empty() {
+                continue;
+            }
+            let name = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            lines.push(Line::styled(name, Style::default().fg(Color::White).bold()));
+            for (field, old, new) in altered {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<8} ", field), Style::default().fg(Color::Gray)),
+                    Span::styled(
+                        old.unwrap_or("—").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(" → "),
+                    Span::styled(
+                        new.unwrap_or_else(|| "(removed)".to_string()),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                ]));
+            }
+        }
+        lines
+    }
+}
+
+impl RouteHandler for TagEditorRoute {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
+        match self.origin {
+            TagEditorOrigin::Browser => BrowserRoute.render(frame, area, state),
+            TagEditorOrigin::Queue => QueueRoute.render(frame, area, state),
+        }
+
+        let width = area.width.saturating_sub(10).min(80);
+        let height = match self.preview {
+            Some(_) => area.height.saturating_sub(4),
+            None => (self.fields.len() as u16 + 4).min(area.height),
+        };
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let dialog_area = Rect::new(x, y, width, height);
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+        let paragraph = match self.preview {
+            Some(scroll) => Paragraph::new(self.preview_lines())
+                .block(
+                    block
+                        .title(" Write These Changes? ")
+                        .title_bottom(" Enter write · Esc back "),
+                )
+                .scroll((scroll as u16, 0)),
+            None => Paragraph::new(self.field_lines()).block(block.title(self.title())),
+        };
+        frame.render_widget(paragraph, dialog_area);
+    }
+
+    fn handle_input(
+        &mut self,
+        _key: KeyCode,
+        _state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> anyhow::Result<RouteAction> {
+        Ok(RouteAction::None)
+    }
+
+    fn name(&self) -> &str {
+        "Edit Tags"
+    }
+
+    // Every key is text for the fields, so nothing reaches the global handler
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        if let Some(scroll) = &mut self.preview {
+            match key {
+                KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                KeyCode::Down => *scroll += 1,
+                KeyCode::Enter | KeyCode::Char('y') => {
+                    self.confirm(state, handle);
+                    return InterceptKeyResult::HandledAndNavigate(RouteAction::Pop);
+                }
+                KeyCode::Esc | KeyCode::Char('n') => self.preview = None,
+                _ => {}
+            }
+            return InterceptKeyResult::Handled;
+        }
+
+        match key {
+            KeyCode::Up | KeyCode::BackTab => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_selection(1),
+            KeyCode::Char(c) => self.edit_selected(|value| value.push(c)),
+            // Backspace on "<various>" would wipe the field on every file
+            KeyCode::Backspace if !self.fields[self.selected].mixed => {
+                self.edit_selected(|value| {
+                    value.pop();
+                })
+            }
+            KeyCode::Delete => self.edit_selected(String::clear),
+            KeyCode::Enter => self.start_preview(),
+            KeyCode::Esc => return InterceptKeyResult::HandledAndNavigate(RouteAction::Pop),
+            _ => {}
+        }
+        InterceptKeyResult::Handled
+    }
+}
@@ -0,0 +1,145 @@
+use audido_core::{commands::AudioCommand, engine::AudioEngineHandle, remote};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    router::{InterceptKeyResult, RouteAction, RouteHandler},
+    routes::queue::QueueRoute,
+    state::AppState,
+};
+
+/// What the entered URL is added as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UrlKind {
+    /// A page resolved and downloaded with yt-dlp
+    Page,
+    /// An internet radio stream played as it arrives
+    Stream,
+}
+
+/// Prompt for a URL to add to the queue, shown over the queue
+#[derive(Debug, Clone)]
+pub struct UrlInputRoute {
+    kind: UrlKind,
+    url: String,
+    /// Shown when Enter is pressed on something that isn't a URL
+    error: Option<String>,
+}
+
+impl UrlInputRoute {
+    /// Prompt for a web page or media URL
+    pub fn page() -> Self {
+        Self::new(UrlKind::Page)
+    }
+
+    /// Prompt for an internet radio stream
+    pub fn stream() -> Self {
+        Self::new(UrlKind::Stream)
+    }
+
+    fn new(kind: UrlKind) -> Self {
+        Self {
+            kind,
+            url: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl RouteHandler for UrlInputRoute {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &AppState) {
+        QueueRoute.render(frame, area, state);
+
+        let width = area.width.saturating_sub(10).min(70);
+        let height = 5;
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let y = area.y + area.height.saturating_sub(height) / 2;
+        let dialog_area = Rect::new(x, y, width, height);
+        frame.render_widget(Clear, dialog_area);
+
+        let (title, description) = match self.kind {
+            UrlKind::Page => (" Add URL ", "Resolved with yt-dlp and cached"),
+            UrlKind::Stream => (" Add Stream ", "Icecast/Shoutcast stream, http:// only"),
+        };
+        let block = Block::default()
+            .title(title)
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+        let hint = match &self.error {
+            Some(error) => Span::styled(error.clone(), Style::default().fg(Color::Red)),
+            None => Span::styled(description, Style::default().fg(Color::DarkGray)),
+        };
+        let text = vec![
+            Line::from(vec![Span::raw(self.url.clone()), Span::raw("▏")]),
+            Line::from(""),
+            Line::from(hint),
+        ];
+        frame.render_widget(Paragraph::new(text).block(block), dialog_area);
+    }
+
+    fn handle_input(
+        &mut self,
+        _key: KeyCode,
+        _state: &mut AppState,
+        _handle: &AudioEngineHandle,
+    ) -> anyhow::Result<RouteAction> {
+        Ok(RouteAction::None)
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            UrlKind::Page => "Add URL",
+            UrlKind::Stream => "Add Stream",
+        }
+    }
+
+    // Every key is text for the URL, so nothing reaches the global handler
+    fn intercept_global_key(
+        &mut self,
+        key: KeyCode,
+        state: &mut AppState,
+        handle: &AudioEngineHandle,
+    ) -> InterceptKeyResult {
+        match key {
+            KeyCode::Char(c) => {
+                self.url.push(c);
+                self.error = None;
+            }
+            KeyCode::Backspace => {
+                self.url.pop();
+                self.error = None;
+            }
+            KeyCode::Enter => {
+                let url = self.url.trim();
+                if !remote::is_url(url) {
+                    self.error = Some("Enter an http(s) URL".to_string());
+                    return InterceptKeyResult::Handled;
+                }
+                let (command, status) = match self.kind {
+                    UrlKind::Page => (
+                        AudioCommand::AddUrl(url.to_string()),
+                        format!("Resolving {}...", url),
+                    ),
+                    UrlKind::Stream => (
+                        AudioCommand::AddStream(url.to_string()),
+                        format!("Added stream {}", url),
+                    ),
+                };
+                if handle.cmd_tx.send(command).is_ok() {
+                    state.audio.status_message = status;
+                }
+                return InterceptKeyResult::HandledAndNavigate(RouteAction::Pop);
+            }
+            KeyCode::Esc => return InterceptKeyResult::HandledAndNavigate(RouteAction::Pop),
+            _ => {}
+        }
+        InterceptKeyResult::Handled
+    }
+}
@@ -1,7 +1,12 @@
-use audido_core::{commands::AudioResponse, queue::LoopMode};
+use std::path::PathBuf;
+
+use audido_core::{commands::AudioResponse, queue::LoopMode, stream::StreamState};
 use strum::IntoEnumIterator;
 
-use crate::states::{AudioState, BrowserState, EqState, QueueState, SettingsState, normalizer::NormalizerState};
+use crate::states::{
+    AudioState, BrowserState, CoverState, EqState, LibraryState, QueueState, SettingsState,
+    normalizer::NormalizerState,
+};
 
 /// Application state for the TUI
 pub struct AppState {
@@ -11,12 +16,16 @@ pub struct AppState {
     pub browser: BrowserState,
     /// Queue state
     pub queue: QueueState,
+    /// Library state
+    pub library: LibraryState,
     /// EQ State
     pub eq: EqState,
     /// Settings State
     pub settings: SettingsState,
     /// Normalizer State
     pub normalizer: NormalizerState,
+    /// Cover art of the loaded file
+    pub cover: CoverState,
 }
 
 impl AppState {
@@ -25,10 +34,11 @@ impl AppState {
             audio: AudioState::new(),
             browser: BrowserState::new(),
             queue: QueueState::new(),
+            library: LibraryState::new(),
             eq: EqState::new(),
             settings: SettingsState::new(),
             normalizer: NormalizerState::new(),
-            
+            cover: CoverState::new(),
         }
     }
 
@@ -51,18 +61,49 @@ impl AppState {
                 self.audio.status_message = "Stopped".to_string();
             }
             AudioResponse::Loaded(metadata) => {
-                self.audio.duration = metadata.duration;
+                // Streams report no length, position updates say for sure
+                self.audio.duration = (metadata.duration > 0.0).then_some(metadata.duration);
                 self.audio.status_message = format!(
                     "Loaded: {} - {}",
                     metadata.title.as_deref().unwrap_or("Unknown"),
                     metadata.author.as_deref().unwrap_or("Unknown")
                 );
+                self.cover.keep_for(&metadata.full_file_path);
                 self.audio.metadata = Some(metadata);
             }
+            AudioResponse::CoverArt { path, art } => {
+                // Art of a file that's no longer loaded arrives late sometimes
+                let current = self.audio.metadata.as_ref().map(|m| &m.full_file_path);
+                if current == Some(&path) {
+                    self.cover.path = Some(path);
+                    self.cover.art = art;
+                }
+            }
             AudioResponse::Position { current, total } => {
                 self.audio.position = current;
                 self.audio.duration = total;
             }
+            AudioResponse::StreamTitle(metadata) => {
+                self.audio.status_message = format!(
+                    "Now playing: {}",
+                    match (&metadata.author, &metadata.title) {
+                        (Some(author), Some(title)) => format!("{} - {}", author, title),
+                        (None, Some(title)) => title.clone(),
+                        _ => "Unknown".to_string(),
+                    }
+                );
+                self.audio.metadata = Some(metadata);
+            }
+            AudioResponse::StreamState(stream_state) => {
+                self.audio.status_message = match stream_state {
+                    StreamState::Buffering => "Buffering stream...".to_string(),
+                    StreamState::Playing => "Streaming".to_string(),
+                    StreamState::Reconnecting { attempt } => {
+                        format!("Connection lost, reconnecting (attempt {})...", attempt)
+                    }
+                    StreamState::Failed(e) => format!("Stream failed: {}", e),
+                };
+            }
             AudioResponse::Error(msg) => {
                 self.audio.error_message = Some(msg.clone());
                 self.audio.status_message = format!("Error: {}", msg);
@@ -70,18 +111,88 @@ impl AppState {
             AudioResponse::Shutdown => {
                 self.audio.status_message = "Engine shutdown".to_string();
             }
-            AudioResponse::QueueUpdated(queue_items) => {
-                self.queue.queue = queue_items;
-                if !self.queue.queue.is_empty() && self.queue.queue_state.selected().is_none() {
-                    self.queue.queue_state.select(Some(0));
+            AudioResponse::QueueUpdated {
+                items,
+                current_index,
+            } => {
+                self.queue.queue = items;
+                self.queue.current_queue_index = current_index;
+                self.queue.sync_after_update();
+            }
+            AudioResponse::PlaylistLoaded { path, count } => {
+                self.audio.status_message = format!("Loaded {} tracks from {}", count, path);
+            }
+            AudioResponse::UrlAdded { url, count } => {
+                self.audio.status_message = format!("Added {} tracks from {}", count, url);
+            }
+            AudioResponse::TagsWritten { count, errors } => {
+                self.audio.status_message = match errors.first() {
+                    None => format!("Tags written to {} files", count),
+                    Some(error) => format!(
+                        "Tags written to {} files, {} failed: {}",
+                        count,
+                        errors.len(),
+                        error
+                    ),
+                };
+            }
+            AudioResponse::Analyzed {
+                metadata,
+                analysis,
+                ask,
+            } => {
+                let path = metadata.full_file_path.clone();
+                if self.audio.metadata.as_ref().map(|m| &m.full_file_path) == Some(&path) {
+                    self.audio.metadata = Some(metadata);
+                }
+                if !analysis.is_empty() {
+                    self.audio.status_message = format!("Analysed: {}", analysis);
                 }
+                self.library
+                    .record_analysis(PathBuf::from(&path), analysis.clone());
+                if ask {
+                    self.audio.pending_analysis = Some((path, analysis));
+                }
+            }
+            AudioResponse::AnalysisWritten(path) => {
+                self.audio.status_message = format!("Analysis saved to the tags of {}", path);
+            }
+            AudioResponse::PlaylistSaved { path, count } => {
+                self.audio.status_message = format!("Saved {} tracks to {}", count, path);
             }
             AudioResponse::LoopModeChanged(mode) => {
                 self.queue.loop_mode = mode;
             }
+            AudioResponse::PresetRuleApplied {
+                rule,
+                eq_enabled,
+                eq_preset,
+                filters,
+                normalizer_enabled,
+            } => {
+                self.eq.eq_enabled = eq_enabled;
+                self.eq.local_preset = eq_preset;
+                self.eq.local_filters = filters;
+                self.normalizer.enabled = normalizer_enabled;
+                self.audio.active_rule = rule;
+            }
+            AudioResponse::SessionRestored(session) => {
+                self.audio.volume = session.volume;
+                self.audio.active_rule = None;
+                self.eq.eq_enabled = session.eq.enabled;
+                self.eq.local_preset = session.eq.preset;
+                self.eq.local_filters = session.eq.filters;
+                self.eq.local_master_gain = session.eq.master_gain_db;
+                self.eq.solo_band = None;
+                self.normalizer.enabled = session.normalizer.enabled;
+                self.audio.status_message = "Session restored".to_string();
+            }
+            AudioResponse::EqDynamicGains(gains) => {
+                self.eq.dynamic_gains = gains;
+            }
             AudioResponse::TrackChanged { index, metadata } => {
                 self.queue.current_queue_index = Some(index);
-                self.queue.queue_state.select(Some(index));
+                self.queue.select_index(index);
                 self.audio.metadata = Some(metadata);
                 self.audio.status_message =
                     format!("Track {}/{}", index + 1, self.queue.queue.len());
@@ -94,12 +205,13 @@ impl AppState {
     // ==============================================
 
     pub fn queue_next(&mut self) {
-        if self.queue.queue.is_empty() {
+        let len = self.queue.visible_len();
+        if len == 0 {
             return;
         }
         let i = match self.queue.queue_state.selected() {
             Some(i) => {
-                if i >= self.queue.queue.len() - 1 {
+                if i >= len - 1 {
                     0
                 } else {
                     i + 1
@@ -111,13 +223,14 @@ impl AppState {
     }
 
     pub fn queue_prev(&mut self) {
-        if self.queue.is_empty() {
+        let len = self.queue.visible_len();
+        if len == 0 {
             return;
         }
         let i = match self.queue.queue_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.queue.queue.len() - 1
+                    len - 1
                 } else {
                     i - 1
                 }
@@ -129,7 +242,7 @@ impl AppState {
 
     /// Get currently selected queue index
     pub fn queue_selected(&self) -> Option<usize> {
-        self.queue.queue_state.selected()
+        self.queue.selected_index()
     }
 
     // ==============================================
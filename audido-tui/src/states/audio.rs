@@ -1,4 +1,4 @@
-use audido_core::metadata::AudioMetadata;
+use audido_core::{analysis::Analysis, metadata::AudioMetadata};
 
 /// Audio-related state (playback status, position, volume, metadata, messages)
 #[derive(Debug, Clone)]
@@ -7,8 +7,8 @@ pub struct AudioState {
     pub is_playing: bool,
     /// Current playback position in seconds
     pub position: f32,
-    /// Total duration in seconds
-    pub duration: f32,
+    /// Total duration in seconds, `None` for live streams
+    pub duration: Option<f32>,
     /// Current volume (0.0 to 1.0)
     pub volume: f32,
     /// Currently loaded audio metadata
@@ -17,6 +17,11 @@ pub struct AudioState {
     pub status_message: String,
     /// Error message if any
     pub error_message: Option<String>,
+    /// Name of the preset rule that set the current DSP settings, if any
+    pub active_rule: Option<String>,
+    /// Analysis of this file waiting for the user to decide whether it goes
+    /// into the tags
+    pub pending_analysis: Option<(String, Analysis)>,
 }
 
 impl AudioState {
@@ -24,20 +29,21 @@ impl AudioState {
         Self {
             is_playing: false,
             position: 0.0,
-            duration: 0.0,
+            duration: None,
             volume: 1.0,
             metadata: None,
             status_message: "No audio loaded. Pass a file path as argument.".to_string(),
             error_message: None,
+            active_rule: None,
+            pending_analysis: None,
         }
     }
 
     /// Get the progress percentage (0.0 to 1.0)
     pub fn progress(&self) -> f32 {
-        if self.duration > 0.0 {
-            (self.position / self.duration).clamp(0.0, 1.0)
-        } else {
-            0.0
+        match self.duration {
+            Some(duration) if duration > 0.0 => (self.position / duration).clamp(0.0, 1.0),
+            _ => 0.0,
         }
     }
 
@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::{collections::HashSet, path::PathBuf};
 
-use audido_core::browser::{self, FileEntry};
+use audido_core::{
+    browser::{self, FileEntry},
+    formats::FormatConfig,
+    playlist,
+};
 use ratatui::widgets::ListState;
 
+use super::SearchFilter;
+
 /// Dialog shown when selecting a file in browser
 #[derive(Debug, Clone, Default)]
 pub enum BrowserFileDialog {
@@ -19,12 +25,21 @@ pub struct BrowserState {
     pub items: Vec<FileEntry>,
     pub list_state: ListState,
     pub dialog: BrowserFileDialog,
+    pub search: SearchFilter,
+    /// Audio files marked for tag editing, kept while moving between folders
+    pub marked: HashSet<PathBuf>,
+    /// Which files are listed as audio
+    pub formats: FormatConfig,
 }
 
 impl BrowserState {
     pub fn new() -> Self {
         let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let items = browser::get_directory_content(&current_dir).unwrap_or_default();
+        let formats = FormatConfig::load().unwrap_or_else(|e| {
+            log::warn!("Using default format settings: {}", e);
+            FormatConfig::default()
+        });
+        let items = browser::get_directory_content(&current_dir, &formats).unwrap_or_default();
         let mut list_state = ListState::default();
         if !items.is_empty() {
             list_state.select(Some(0));
@@ -35,13 +50,20 @@ impl BrowserState {
             items,
             list_state,
             dialog: BrowserFileDialog::None,
+            search: SearchFilter::default(),
+            marked: HashSet::new(),
+            formats,
         }
     }
 
     pub fn next(&mut self) {
+        let len = self.search.len(self.items.len());
+        if len == 0 {
+            return;
+        }
         let i = match self.list_state.selected() {
             Some(i) => {
-                if i >= self.items.len() - 1 {
+                if i >= len - 1 {
                     0
                 } else {
                     i + 1
@@ -53,10 +75,14 @@ impl BrowserState {
     }
 
     pub fn prev(&mut self) {
+        let len = self.search.len(self.items.len());
+        if len == 0 {
+            return;
+        }
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.items.len() - 1
+                    len - 1
                 } else {
                     i - 1
                 }
@@ -68,13 +94,14 @@ impl BrowserState {
 
     /// Enter selected directory or return PathBuf if it's a file
     pub fn enter(&mut self) -> Option<PathBuf> {
-        let i = self.list_state.selected()?;
+        let i = self.search.item_index(self.list_state.selected()?)?;
         let item = &self.items.get(i)?;
         if item.is_dir {
             let new_path = item.path.clone();
-            if let Ok(new_items) = browser::get_directory_content(&new_path) {
+            if let Ok(new_items) = browser::get_directory_content(&new_path, &self.formats) {
                 self.current_dir = new_path;
                 self.items = new_items;
+                self.search.clear();
                 self.list_state.select(Some(0));
             }
             None
@@ -83,15 +110,69 @@ impl BrowserState {
         }
     }
 
+    /// Entry under the cursor
+    pub fn selected_item(&self) -> Option<&FileEntry> {
+        let i = self.search.item_index(self.list_state.selected()?)?;
+        self.items.get(i)
+    }
+
+    /// Mark or unmark the audio file under the cursor
+    pub fn toggle_mark(&mut self) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        if item.is_dir || playlist::is_playlist(&item.path) {
+            return;
+        }
+        let path = item.path.clone();
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+    }
+
+    /// Files to edit the tags of: the marked ones, else the audio file under
+    /// the cursor, or every audio file in the folder under the cursor (an album)
+    pub fn tag_targets(&self) -> Vec<PathBuf> {
+        if !self.marked.is_empty() {
+            let mut files: Vec<PathBuf> = self.marked.iter().cloned().collect();
+            files.sort();
+            return files;
+        }
+        match self.selected_item() {
+            Some(item) if item.is_dir && item.name != ".." => {
+                browser::get_directory_content(&item.path, &self.formats)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|entry| !entry.is_dir && !playlist::is_playlist(&entry.path))
+                    .map(|entry| entry.path)
+                    .collect()
+            }
+            Some(item) if !item.is_dir && self.formats.is_audio(&item.path) => {
+                vec![item.path.clone()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Filter the listing by the search query
+    pub fn apply_search(&mut self) {
+        self.search
+            .apply(&self.items, &mut self.list_state, |item| {
+                [item.name.clone()]
+            });
+    }
+
     /// Open the browser file dialog for a given path
     pub fn open_dialog(&mut self, path: PathBuf) {
         self.dialog = BrowserFileDialog::Open { path, selected: 0 };
     }
 
-    /// Navigate dialog selection
-    pub fn dialog_toggle(&mut self) {
-        if let BrowserFileDialog::Open { selected, .. } = &mut self.dialog {
-            *selected = if *selected == 0 { 1 } else { 0 };
+    /// Move the dialog selection by `offset`, wrapping around `option_count` options
+    pub fn dialog_move(&mut self, offset: isize, option_count: usize) {
+        if let BrowserFileDialog::Open { selected, .. } = &mut self.dialog
+            && option_count > 0
+        {
+            *selected = (*selected as isize + offset).rem_euclid(option_count as isize) as usize;
         }
     }
 
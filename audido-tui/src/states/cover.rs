@@ -0,0 +1,39 @@
+use std::{cell::Cell, sync::Arc};
+
+use audido_core::cover::CoverArt;
+use ratatui::layout::{Rect, Size};
+
+use crate::graphics::GraphicsProtocol;
+
+/// Cover art of the loaded file and where it is on screen
+#[derive(Debug)]
+pub struct CoverState {
+    /// File the art belongs to
+    pub path: Option<String>,
+    pub art: Option<Arc<CoverArt>>,
+    pub protocol: GraphicsProtocol,
+    /// Where the last frame put a kitty/sixel image, set while rendering
+    pub placement: Cell<Option<Rect>>,
+    /// The image on the terminal: its place, which art and the terminal size
+    pub shown: Option<(Rect, usize, Size)>,
+}
+
+impl CoverState {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            art: None,
+            protocol: GraphicsProtocol::detect(),
+            placement: Cell::new(None),
+            shown: None,
+        }
+    }
+
+    /// Forget the art unless it belongs to `path` (tracks of a CUE sheet share it)
+    pub fn keep_for(&mut self, path: &str) {
+        if self.path.as_deref() != Some(path) {
+            self.path = None;
+            self.art = None;
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use audido_core::dsp::eq::{EqPreset, FilterNode};
+use audido_core::dsp::eq::{EqPreset, Equalizer, FilterNode};
+
+/// Maximum number of undo steps kept
+const EQ_HISTORY_LIMIT: usize = 64;
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum EqMode {
@@ -6,6 +9,40 @@ pub enum EqMode {
     Advanced,
 }
 
+/// Everything the user can edit on the EQ, as stored in the undo history and A/B slots
+#[derive(Debug, Clone, PartialEq)]
+pub struct EqSnapshot {
+    pub filters: Vec<FilterNode>,
+    pub preset: EqPreset,
+    pub master_gain: f32,
+}
+
+/// What an edit touched. Consecutive edits of the same kind share one undo step,
+/// so holding an arrow key doesn't flood the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqEdit {
+    MasterGain,
+    BandParameter {
+        band: usize,
+        param: usize,
+    },
+    /// Structural edits (add, delete, bypass, reset); never merged
+    Bands,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+pub enum AbSlot {
+    A,
+    B,
+}
+
+/// A/B comparison: the live EQ is the active slot, `other` holds the inactive one
+#[derive(Debug, Clone)]
+pub struct AbCompare {
+    pub active: AbSlot,
+    pub other: EqSnapshot,
+}
+
 #[derive(Debug, Clone)]
 pub struct EqState {
     pub eq_enabled: bool,
@@ -15,6 +52,15 @@ pub struct EqState {
     pub local_preset: EqPreset,
     pub local_master_gain: f32,
     pub local_num_channels: u16,
+    /// Live gain of each band in dB as reported by the engine (`None` for static bands)
+    pub dynamic_gains: Vec<Option<f32>>,
+    /// Band currently soloed, mirrored from what was sent to the engine
+    pub solo_band: Option<usize>,
+    undo_stack: Vec<EqSnapshot>,
+    redo_stack: Vec<EqSnapshot>,
+    last_edit: Option<EqEdit>,
+    /// A/B comparison, `None` when not comparing
+    pub ab: Option<AbCompare>,
 }
 
 impl EqState {
@@ -27,7 +73,113 @@ impl EqState {
             local_preset: EqPreset::default(),
             local_master_gain: 0.0,
             local_num_channels: 2, // Default to stereo
+            dynamic_gains: Vec::new(),
+            solo_band: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            ab: None,
+        }
+    }
+
+    pub fn snapshot(&self) -> EqSnapshot {
+        EqSnapshot {
+            filters: self.local_filters.clone(),
+            preset: self.local_preset,
+            master_gain: self.local_master_gain,
+        }
+    }
+
+    fn restore(&mut self, snapshot: EqSnapshot) {
+        // Band indices may not line up anymore
+        if snapshot.filters.len() != self.local_filters.len() {
+            self.solo_band = None;
+        }
+        self.local_filters = snapshot.filters;
+        self.local_preset = snapshot.preset;
+        self.local_master_gain = snapshot.master_gain;
+    }
+
+    /// Record the current state before an edit so it can be undone
+    pub fn checkpoint(&mut self, edit: EqEdit) {
+        if edit != EqEdit::Bands && self.last_edit == Some(edit) {
+            return;
+        }
+        self.last_edit = Some(edit);
+
+        let snapshot = self.snapshot();
+        if self.undo_stack.last() != Some(&snapshot) {
+            self.undo_stack.push(snapshot);
+            if self.undo_stack.len() > EQ_HISTORY_LIMIT {
+                self.undo_stack.remove(0);
+            }
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Go back one step. Returns `false` when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop() else {
+            return false;
+        };
+        self.redo_stack.push(self.snapshot());
+        self.restore(snapshot);
+        self.last_edit = None;
+        true
+    }
+
+    /// Re-apply an undone step. Returns `false` when there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return false;
+        };
+        self.undo_stack.push(self.snapshot());
+        self.restore(snapshot);
+        self.last_edit = None;
+        true
+    }
+
+    /// Swap to the other A/B slot. The first toggle starts the comparison with
+    /// B as a copy of the current curve.
+    pub fn toggle_ab(&mut self) {
+        let current = self.snapshot();
+        match self.ab.take() {
+            None => {
+                self.ab = Some(AbCompare {
+                    active: AbSlot::A,
+                    other: current,
+                });
+            }
+            Some(ab) => {
+                let active = match ab.active {
+                    AbSlot::A => AbSlot::B,
+                    AbSlot::B => AbSlot::A,
+                };
+                self.restore(ab.other);
+                self.ab = Some(AbCompare {
+                    active,
+                    other: current,
+                });
+                self.last_edit = None;
+            }
+        }
+    }
+
+    /// Stop comparing and keep the active slot
+    pub fn end_ab(&mut self) {
+        self.ab = None;
+    }
+
+    /// Master gain to send to the engine. While comparing A/B the curve's average
+    /// gain is compensated so both slots play at the same loudness.
+    pub fn effective_master_gain(&self, sample_rate: u32) -> f32 {
+        if self.ab.is_none() {
+            return self.local_master_gain;
         }
+        let mut eq = Equalizer::new(sample_rate, self.local_num_channels);
+        eq.filters = self.local_filters.clone();
+        eq.parameters_changed();
+        self.local_master_gain - eq.average_gain_db()
     }
 
     /// Toggle EQ enabled state
@@ -35,6 +187,46 @@ impl EqState {
         self.eq_enabled = !self.eq_enabled;
     }
 
+    /// Live gain of a dynamic band, if the engine has reported one
+    pub fn dynamic_gain(&self, band: usize) -> Option<f32> {
+        self.dynamic_gains.get(band).copied().flatten()
+    }
+
+    /// Whether a band is currently heard (not bypassed and not muted by a solo)
+    pub fn is_band_audible(&self, band: usize) -> bool {
+        let bypassed = self
+            .local_filters
+            .get(band)
+            .is_some_and(|filter| filter.bypass);
+        !bypassed && self.solo_band.is_none_or(|solo| solo == band)
+    }
+
+    /// Id for a new band that doesn't clash with any existing one
+    pub fn next_filter_id(&self) -> i16 {
+        self.local_filters
+            .iter()
+            .map(|filter| filter.id)
+            .max()
+            .map_or(0, |id| id + 1)
+    }
+
+    /// Remove a band locally, keeping the solo pointed at the same band
+    pub fn remove_filter(&mut self, band: usize) -> Option<FilterNode> {
+        if band >= self.local_filters.len() {
+            return None;
+        }
+        self.solo_band = match self.solo_band {
+            Some(solo) if solo == band => None,
+            Some(solo) if solo > band => Some(solo - 1),
+            solo => solo,
+        };
+        // Keep the live gains on their bands until the engine reports again
+        if band < self.dynamic_gains.len() {
+            self.dynamic_gains.remove(band);
+        }
+        Some(self.local_filters.remove(band))
+    }
+
     /// Toggle between Casual and Advanced mode
     pub fn toggle_mode(&mut self) {
         self.eq_mode = match self.eq_mode {
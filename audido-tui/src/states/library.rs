@@ -0,0 +1,676 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use audido_core::{
+    analysis::Analysis,
+    library::{Library, LibraryQuery, LibrarySort, LibraryTrack, ScanStats, SmartPlaylists},
+    stats::{PlayStats, TrackStats},
+};
+use ratatui::widgets::ListState;
+
+use super::SearchFilter;
+
+/// Top-level grouping of the library tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LibraryView {
+    #[default]
+    Artists,
+    Genres,
+    Playlists,
+    Stats,
+}
+
+/// Longest most and recently played lists
+const STATS_LIST_LIMIT: usize = 100;
+
+/// Lists built from the play statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsList {
+    Most,
+    Recent,
+    /// Library tracks never played to at least half
+    Never,
+}
+
+impl StatsList {
+    pub const ALL: [StatsList; 3] = [StatsList::Most, StatsList::Recent, StatsList::Never];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsList::Most => "Most played",
+            StatsList::Recent => "Recently played",
+            StatsList::Never => "Never played",
+        }
+    }
+}
+
+/// Where the library tab currently is in the drill-down
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryLevel {
+    Artists,
+    Albums { artist: String },
+    Tracks { artist: String, album: String },
+    Genres,
+    GenreTracks { genre: String },
+    Playlists,
+    PlaylistTracks { name: String },
+    Stats,
+    StatsTracks { list: StatsList },
+}
+
+/// One row of the library list
+#[derive(Debug, Clone)]
+pub enum LibraryEntry {
+    Artist {
+        name: String,
+        album_count: usize,
+        track_count: usize,
+        duration: f32,
+    },
+    Album {
+        artist: String,
+        album: String,
+        track_count: usize,
+        duration: f32,
+    },
+    Genre {
+        name: String,
+        track_count: usize,
+    },
+    /// A smart playlist with its current totals
+    Playlist {
+        name: String,
+        track_count: usize,
+        duration: f32,
+    },
+    StatsList {
+        list: StatsList,
+        track_count: usize,
+    },
+    Track {
+        path: PathBuf,
+        title: String,
+        artist: Option<String>,
+        album: Option<String>,
+        duration: f32,
+        /// Extra column, e.g. the play count in the statistics lists
+        detail: Option<String>,
+    },
+}
+
+impl LibraryEntry {
+    /// Fields the library search matches against
+    fn search_fields(&self) -> Vec<String> {
+        match self {
+            LibraryEntry::Artist { name, .. }
+            | LibraryEntry::Genre { name, .. }
+            | LibraryEntry::Playlist { name, .. } => vec![name.clone()],
+            LibraryEntry::Album { artist, album, .. } => vec![album.clone(), artist.clone()],
+            LibraryEntry::StatsList { list, .. } => vec![list.label().to_string()],
+            LibraryEntry::Track {
+                path,
+                title,
+                artist,
+                album,
+                ..
+            } => {
+                let mut fields = vec![
+                    title.clone(),
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                ];
+                fields.extend([artist, album].into_iter().flatten().cloned());
+                fields
+            }
+        }
+    }
+}
+
+/// Messages from the background scan thread
+enum ScanEvent {
+    /// The stored index was read, shown while the rescan runs
+    Opened(Library),
+    Scanned(Library, ScanStats),
+    /// Smart playlist definitions, re-read on every scan
+    Playlists(SmartPlaylists),
+    Failed(String),
+}
+
+/// Library tab state: the index, the drill-down position and a running scan
+pub struct LibraryState {
+    pub library: Option<Library>,
+    pub smart_playlists: SmartPlaylists,
+    /// Re-read from disk whenever a statistics list is shown
+    pub play_stats: PlayStats,
+    pub view: LibraryView,
+    pub level: LibraryLevel,
+    /// Levels above the current one with their selection, for going back
+    pub history: Vec<(LibraryLevel, Option<usize>)>,
+    pub entries: Vec<LibraryEntry>,
+    pub list_state: ListState,
+    pub status: Option<String>,
+    pub search: SearchFilter,
+    scan_rx: Option<Receiver<ScanEvent>>,
+    /// Analysis results waiting for a scan to hand over the library
+    pending_analysis: Vec<(PathBuf, Analysis)>,
+}
+
+impl LibraryState {
+    pub fn new() -> Self {
+        Self {
+            library: None,
+            smart_playlists: SmartPlaylists::default(),
+            play_stats: PlayStats::default(),
+            view: LibraryView::Artists,
+            level: LibraryLevel::Artists,
+            history: Vec::new(),
+            entries: Vec::new(),
+            list_state: ListState::default(),
+            status: None,
+            search: SearchFilter::default(),
+            scan_rx: None,
+            pending_analysis: Vec::new(),
+        }
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.scan_rx.is_some()
+    }
+
+    /// Rescan the library on a background thread. The first scan also opens
+    /// the library from the config and data directories.
+    pub fn start_scan(&mut self) {
+        if self.is_scanning() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let current = self.library.clone();
+        thread::spawn(move || {
+            match SmartPlaylists::load() {
+                Ok(playlists) => {
+                    let _ = tx.send(ScanEvent::Playlists(playlists));
+                }
+                // A broken playlists file shouldn't keep the library from loading
+                Err(e) => log::error!("Cannot load smart playlists: {}", e),
+            }
+            let mut library = match current {
+                Some(library) => library,
+                None => match Library::open() {
+                    Ok(library) => {
+                        let _ = tx.send(ScanEvent::Opened(library.clone()));
+                        library
+                    }
+                    Err(e) => {
+                        let _ = tx.send(ScanEvent::Failed(e.to_string()));
+                        return;
+                    }
+                },
+            };
+            match library.rescan() {
+                Ok(stats) => {
+                    let _ = tx.send(ScanEvent::Scanned(library, stats));
+                }
+                Err(e) => {
+                    let _ = tx.send(ScanEvent::Failed(e.to_string()));
+                }
+            }
+        });
+        self.scan_rx = Some(rx);
+        self.status = Some("Scanning library...".to_string());
+    }
+
+    /// Pick up results of a running scan
+    pub fn poll_scan(&mut self) {
+        let Some(rx) = self.scan_rx.take() else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(ScanEvent::Opened(library)) => self.set_library(library),
+                Ok(ScanEvent::Playlists(playlists)) => {
+                    self.smart_playlists = playlists;
+                    self.refresh_entries();
+                }
+                Ok(ScanEvent::Scanned(library, stats)) => {
+                    self.status = if library.roots.is_empty() {
+                        Some("No library folders configured, add them to library.toml".to_string())
+                    } else {
+                        Some(format!(
+                            "{} tracks ({} new, {} changed, {} removed)",
+                            library.index.len(),
+                            stats.added,
+                            stats.updated,
+                            stats.removed
+                        ))
+                    };
+                    self.set_library(library);
+                    self.apply_pending_analysis();
+                    return;
+                }
+                Ok(ScanEvent::Failed(e)) => {
+                    log::error!("Library scan failed: {}", e);
+                    self.status = Some(format!("Library scan failed: {}", e));
+                    return;
+                }
+                Err(TryRecvError::Empty) => {
+                    // Still running, keep listening
+                    self.scan_rx = Some(rx);
+                    return;
+                }
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+    }
+
+    /// Keep the key and tempo of an analysed track in the library index, so
+    /// smart playlists can match on them. Until the library is loaded (or while
+    /// a scan works on its own copy) the results wait.
+    pub fn record_analysis(&mut self, path: PathBuf, analysis: Analysis) {
+        if analysis.key.is_none() && analysis.bpm.is_none() {
+            return;
+        }
+        self.pending_analysis.push((path, analysis));
+        if self.library.is_some() && !self.is_scanning() {
+            self.apply_pending_analysis();
+        }
+    }
+
+    fn apply_pending_analysis(&mut self) {
+        let Some(library) = &mut self.library else {
+            return;
+        };
+        let mut recorded = false;
+        for (path, analysis) in self.pending_analysis.drain(..) {
+            recorded |= library
+                .index
+                .record_analysis(&path, analysis.bpm, analysis.key);
+        }
+        if recorded {
+            if let Err(e) = library.save() {
+                log::error!("Cannot save the library index: {}", e);
+            }
+            self.refresh_entries();
+        }
+    }
+
+    fn set_library(&mut self, library: Library) {
+        self.library = Some(library);
+        self.refresh_entries();
+    }
+
+    /// Cycle through the artist, genre, smart playlist and statistics groupings,
+    /// starting at the top
+    pub fn toggle_view(&mut self) {
+        self.view = match self.view {
+            LibraryView::Artists => LibraryView::Genres,
+            LibraryView::Genres => LibraryView::Playlists,
+            LibraryView::Playlists => LibraryView::Stats,
+            LibraryView::Stats => LibraryView::Artists,
+        };
+        self.level = match self.view {
+            LibraryView::Artists => LibraryLevel::Artists,
+            LibraryView::Genres => LibraryLevel::Genres,
+            LibraryView::Playlists => LibraryLevel::Playlists,
+            LibraryView::Stats => LibraryLevel::Stats,
+        };
+        self.history.clear();
+        self.search.clear();
+        self.list_state.select(None);
+        self.refresh_entries();
+    }
+
+    /// Open the selected artist, album or genre
+    pub fn drill_down(&mut self) {
+        let next = match self.selected_entry() {
+            Some(LibraryEntry::Artist { name, .. }) => LibraryLevel::Albums {
+                artist: name.clone(),
+            },
+            Some(LibraryEntry::Album { artist, album, .. }) => LibraryLevel::Tracks {
+                artist: artist.clone(),
+                album: album.clone(),
+            },
+            Some(LibraryEntry::Genre { name, .. }) => LibraryLevel::GenreTracks {
+                genre: name.clone(),
+            },
+            Some(LibraryEntry::Playlist { name, .. }) => {
+                LibraryLevel::PlaylistTracks { name: name.clone() }
+            }
+            Some(LibraryEntry::StatsList { list, .. }) => LibraryLevel::StatsTracks { list: *list },
+            _ => return,
+        };
+        let previous = std::mem::replace(&mut self.level, next);
+        self.history.push((previous, self.selected_index()));
+        self.search.clear();
+        self.list_state.select(None);
+        self.refresh_entries();
+    }
+
+    /// Go back up one level, restoring its selection
+    pub fn go_back(&mut self) {
+        if let Some((level, selected)) = self.history.pop() {
+            self.level = level;
+            self.search.clear();
+            self.list_state.select(selected);
+            self.refresh_entries();
+        }
+    }
+
+    /// Rebuild the rows of the current level from the index
+    pub fn refresh_entries(&mut self) {
+        // The engine saves the statistics periodically, pick up the latest
+        if matches!(
+            self.level,
+            LibraryLevel::Stats | LibraryLevel::StatsTracks { .. }
+        ) {
+            match PlayStats::load() {
+                Ok(stats) => self.play_stats = stats,
+                Err(e) => log::error!("Cannot load play statistics: {}", e),
+            }
+        }
+        self.entries = match &self.library {
+            Some(library) => build_entries(
+                library,
+                &self.smart_playlists,
+                &self.play_stats,
+                &self.level,
+            ),
+            None => Vec::new(),
+        };
+        if self.search.is_filtering() {
+            self.apply_search();
+            return;
+        }
+        match self.list_state.selected() {
+            _ if self.entries.is_empty() => self.list_state.select(None),
+            Some(i) if i < self.entries.len() => {}
+            Some(_) => self.list_state.select(Some(self.entries.len() - 1)),
+            None => self.list_state.select(Some(0)),
+        }
+    }
+
+    /// Filter the rows of the current level by the search query
+    pub fn apply_search(&mut self) {
+        self.search.apply(
+            &self.entries,
+            &mut self.list_state,
+            LibraryEntry::search_fields,
+        );
+    }
+
+    /// Rows shown, in display order
+    pub fn visible_entries(&self) -> impl Iterator<Item = &LibraryEntry> {
+        self.search
+            .visible(self.entries.len())
+            .into_iter()
+            .map(|i| &self.entries[i])
+    }
+
+    pub fn next(&mut self) {
+        let len = self.search.len(self.entries.len());
+        if len == 0 {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(i) if i + 1 < len => i + 1,
+            _ => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    pub fn prev(&mut self) {
+        let len = self.search.len(self.entries.len());
+        if len == 0 {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    /// Index into `entries` of the row under the cursor
+    fn selected_index(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|position| self.search.item_index(position))
+    }
+
+    pub fn selected_entry(&self) -> Option<&LibraryEntry> {
+        self.selected_index().and_then(|i| self.entries.get(i))
+    }
+
+    /// Files of an entry in play order: a whole artist, album, genre or smart
+    /// playlist, or a single track
+    pub fn entry_paths(&self, entry: &LibraryEntry) -> Vec<String> {
+        let Some(library) = &self.library else {
+            return Vec::new();
+        };
+        let query = match entry {
+            LibraryEntry::Artist { name, .. } => LibraryQuery::new().artist(name),
+            LibraryEntry::Album { artist, album, .. } => {
+                LibraryQuery::new().artist(artist).album(album)
+            }
+            LibraryEntry::Genre { name, .. } => LibraryQuery::new().genre(name),
+            LibraryEntry::Playlist { name, .. } => {
+                return self
+                    .smart_playlists
+                    .get(name)
+                    .map(|playlist| playlist.tracks(&library.index))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|track| track.path.to_string_lossy().to_string())
+                    .collect();
+            }
+            LibraryEntry::StatsList { list, .. } => {
+                return stats_entries(library, &self.play_stats, *list)
+                    .iter()
+                    .flat_map(|entry| self.entry_paths(entry))
+                    .collect();
+            }
+            LibraryEntry::Track { path, .. } => {
+                return vec![path.to_string_lossy().to_string()];
+            }
+        };
+        library
+            .index
+            .query(&query)
+            .into_iter()
+            .map(|track| track.path.to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// Title of the current level, e.g. "Artists › Radiohead"
+    pub fn breadcrumb(&self) -> String {
+        match &self.level {
+            LibraryLevel::Artists => "Artists".to_string(),
+            LibraryLevel::Albums { artist } => format!("Artists › {}", artist),
+            LibraryLevel::Tracks { artist, album } => format!("Artists › {} › {}", artist, album),
+            LibraryLevel::Genres => "Genres".to_string(),
+            LibraryLevel::GenreTracks { genre } => format!("Genres › {}", genre),
+            LibraryLevel::Playlists => "Smart Playlists".to_string(),
+            LibraryLevel::PlaylistTracks { name } => format!("Smart Playlists › {}", name),
+            LibraryLevel::Stats => "Statistics".to_string(),
+            LibraryLevel::StatsTracks { list } => format!("Statistics › {}", list.label()),
+        }
+    }
+}
+
+fn build_entries(
+    library: &Library,
+    smart_playlists: &SmartPlaylists,
+    play_stats: &PlayStats,
+    level: &LibraryLevel,
+) -> Vec<LibraryEntry> {
+    let index = &library.index;
+    match level {
+        LibraryLevel::Artists => {
+            let mut entries: Vec<LibraryEntry> = Vec::new();
+            // Albums come sorted by artist, so each artist's albums are adjacent
+            for album in index.albums(None) {
+                match entries.last_mut() {
+                    Some(LibraryEntry::Artist {
+                        name,
+                        album_count,
+                        track_count,
+                        duration,
+                    }) if name.eq_ignore_ascii_case(&album.artist) => {
+                        *album_count += 1;
+                        *track_count += album.track_count;
+                        *duration += album.duration;
+                    }
+                    _ => entries.push(LibraryEntry::Artist {
+                        name: album.artist,
+                        album_count: 1,
+                        track_count: album.track_count,
+                        duration: album.duration,
+                    }),
+                }
+            }
+            entries
+        }
+        LibraryLevel::Albums { artist } => index
+            .albums(Some(artist))
+            .into_iter()
+            .map(|album| LibraryEntry::Album {
+                artist: album.artist,
+                album: album.album,
+                track_count: album.track_count,
+                duration: album.duration,
+            })
+            .collect(),
+        LibraryLevel::Genres => index
+            .genres()
+            .into_iter()
+            .map(|genre| LibraryEntry::Genre {
+                track_count: index.query(&LibraryQuery::new().genre(&genre)).len(),
+                name: genre,
+            })
+            .collect(),
+        LibraryLevel::Playlists => smart_playlists
+            .playlists
+            .iter()
+            .map(|playlist| {
+                let tracks = playlist.tracks(index);
+                LibraryEntry::Playlist {
+                    name: playlist.name.clone(),
+                    track_count: tracks.len(),
+                    duration: tracks.iter().map(|t| t.duration).sum(),
+                }
+            })
+            .collect(),
+        LibraryLevel::Tracks { artist, album } => {
+            track_entries(index.query(&LibraryQuery::new().artist(artist).album(album)))
+        }
+        LibraryLevel::GenreTracks { genre } => {
+            track_entries(index.query(&LibraryQuery::new().genre(genre).sort(LibrarySort::Artist)))
+        }
+        LibraryLevel::PlaylistTracks { name } => smart_playlists
+            .get(name)
+            .map(|playlist| track_entries(playlist.tracks(index)))
+            .unwrap_or_default(),
+        LibraryLevel::Stats => StatsList::ALL
+            .into_iter()
+            .map(|list| LibraryEntry::StatsList {
+                list,
+                track_count: stats_entries(library, play_stats, list).len(),
+            })
+            .collect(),
+        LibraryLevel::StatsTracks { list } => stats_entries(library, play_stats, *list),
+    }
+}
+
+/// Rows of a statistics list. Played files outside the library are listed too,
+/// with what the file name tells.
+fn stats_entries(library: &Library, play_stats: &PlayStats, list: StatsList) -> Vec<LibraryEntry> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let played = match list {
+        StatsList::Most => play_stats.most_played(STATS_LIST_LIMIT),
+        StatsList::Recent => play_stats.recently_played(STATS_LIST_LIMIT),
+        StatsList::Never => {
+            let tracks = library.index.tracks().map(|track| track.path.as_path());
+            return play_stats
+                .never_played(tracks)
+                .into_iter()
+                .map(|path| {
+                    let skips = play_stats.get(path).map_or(0, |stats| stats.skip_count);
+                    stats_track_entry(
+                        library,
+                        path,
+                        (skips > 0).then(|| format!("skipped {}×", skips)),
+                    )
+                })
+                .collect();
+        }
+    };
+    played
+        .into_iter()
+        .map(|(path, stats)| {
+            let detail = match list {
+                StatsList::Recent => format_ago(now, stats),
+                _ => format!("{} plays", stats.play_count),
+            };
+            stats_track_entry(library, path, Some(detail))
+        })
+        .collect()
+}
+
+fn stats_track_entry(library: &Library, path: &Path, detail: Option<String>) -> LibraryEntry {
+    match library.index.get(path) {
+        Some(track) => LibraryEntry::Track {
+            path: track.path.clone(),
+            title: track.display_title(),
+            artist: track.artist.clone(),
+            album: track.album.clone(),
+            duration: track.duration,
+            detail,
+        },
+        None => LibraryEntry::Track {
+            path: path.to_path_buf(),
+            title: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            artist: None,
+            album: None,
+            duration: 0.0,
+            detail,
+        },
+    }
+}
+
+/// "played 5 min ago" style age of the last listen
+fn format_ago(now: u64, stats: &TrackStats) -> String {
+    let Some(last) = stats.last_played else {
+        return "never played".to_string();
+    };
+    let secs = now.saturating_sub(last);
+    match secs {
+        0..60 => "played just now".to_string(),
+        60..3600 => format!("played {} min ago", secs / 60),
+        3600..86400 => format!("played {} h ago", secs / 3600),
+        _ => format!("played {} days ago", secs / 86400),
+    }
+}
+
+fn track_entries(tracks: Vec<&LibraryTrack>) -> Vec<LibraryEntry> {
+    tracks
+        .into_iter()
+        .map(|track| LibraryEntry::Track {
+            path: track.path.clone(),
+            title: track.display_title(),
+            artist: track.artist.clone(),
+            album: track.album.clone(),
+            duration: track.duration,
+            detail: None,
+        })
+        .collect()
+}
@@ -1,12 +1,18 @@
 pub mod audio;
 pub mod browser;
+pub mod cover;
 pub mod eq;
+pub mod library;
+pub mod normalizer;
 pub mod queue;
+pub mod search;
 pub mod settings;
-pub mod normalizer;
 
 pub use audio::AudioState;
 pub use browser::{BrowserFileDialog, BrowserState};
-pub use eq::{EqMode, EqState};
+pub use cover::CoverState;
+pub use eq::{EqEdit, EqMode, EqState};
+pub use library::{LibraryEntry, LibraryState};
 pub use queue::QueueState;
+pub use search::{SearchFilter, SearchInput};
 pub use settings::{SettingsOption, SettingsState};
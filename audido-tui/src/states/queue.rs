@@ -1,5 +1,13 @@
-use audido_core::queue::{LoopMode, QueueItem};
+use std::{collections::HashSet, path::PathBuf};
+
+use audido_core::{
+    queue::{LoopMode, QueueItem, QueueSortField},
+    stream,
+};
 use ratatui::widgets::ListState;
+use strum::IntoEnumIterator;
+
+use super::SearchFilter;
 
 /// Queue-related state (track list, selection, loop mode)
 #[derive(Debug, Clone)]
@@ -8,6 +16,11 @@ pub struct QueueState {
     pub current_queue_index: Option<usize>,
     pub loop_mode: LoopMode,
     pub queue_state: ListState,
+    /// Item ids marked for batch removal and tag editing
+    pub marked: HashSet<usize>,
+    /// Field the next sort uses
+    pub sort_field: QueueSortField,
+    pub search: SearchFilter,
 }
 
 impl QueueState {
@@ -17,10 +30,115 @@ impl QueueState {
             current_queue_index: None,
             loop_mode: LoopMode::Off,
             queue_state: ListState::default(),
+            marked: HashSet::new(),
+            sort_field: QueueSortField::default(),
+            search: SearchFilter::default(),
         }
     }
 
     pub fn is_empty(&self) -> bool {
         self.queue.is_empty()
     }
+
+    /// Queue index of the item under the cursor
+    pub fn selected_index(&self) -> Option<usize> {
+        self.queue_state
+            .selected()
+            .and_then(|position| self.search.item_index(position))
+    }
+
+    /// Item under the cursor
+    pub fn selected_item(&self) -> Option<&QueueItem> {
+        self.selected_index().and_then(|i| self.queue.get(i))
+    }
+
+    /// Put the cursor on a queue index, unless the search hides it
+    pub fn select_index(&mut self, index: usize) {
+        if let Some(position) = self.search.position_of(index) {
+            self.queue_state.select(Some(position));
+        }
+    }
+
+    /// Number of items shown
+    pub fn visible_len(&self) -> usize {
+        self.search.len(self.queue.len())
+    }
+
+    /// Filter the queue by the search query over file name and tags
+    pub fn apply_search(&mut self) {
+        self.search
+            .apply(&self.queue, &mut self.queue_state, search_fields);
+    }
+
+    pub fn toggle_mark(&mut self, id: usize) {
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
+        }
+    }
+
+    /// Files to edit the tags of: those of the marked tracks, else of the one
+    /// under the cursor. CUE tracks (part of an album file) and streams can't
+    /// be tagged and are left out.
+    pub fn tag_targets(&self) -> Vec<PathBuf> {
+        let items: Vec<&QueueItem> = if self.marked.is_empty() {
+            self.selected_item().into_iter().collect()
+        } else {
+            self.queue
+                .iter()
+                .filter(|item| self.marked.contains(&item.id))
+                .collect()
+        };
+        let mut files: Vec<PathBuf> = Vec::new();
+        for item in items {
+            if item.range.is_none() && !stream::is_stream(&item.path) && !files.contains(&item.path)
+            {
+                files.push(item.path.clone());
+            }
+        }
+        files
+    }
+
+    /// Return the current sort field and advance to the next one
+    pub fn take_sort_field(&mut self) -> QueueSortField {
+        let field = self.sort_field;
+        let fields: Vec<QueueSortField> = QueueSortField::iter().collect();
+        let pos = fields.iter().position(|f| *f == field).unwrap_or(0);
+        self.sort_field = fields[(pos + 1) % fields.len()];
+        field
+    }
+
+    /// Keep the selection and marks valid after the engine sent a new queue
+    pub fn sync_after_update(&mut self) {
+        self.marked
+            .retain(|id| self.queue.iter().any(|item| item.id == *id));
+        if self.search.is_filtering() {
+            self.apply_search();
+            return;
+        }
+        match self.queue_state.selected() {
+            _ if self.queue.is_empty() => self.queue_state.select(None),
+            None => self.queue_state.select(Some(0)),
+            Some(i) if i >= self.queue.len() => self.queue_state.select(Some(self.queue.len() - 1)),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Fields the queue search matches against
+fn search_fields(item: &QueueItem) -> Vec<String> {
+    let mut fields = vec![
+        item.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    ];
+    if let Some(metadata) = &item.metadata {
+        fields.extend(
+            [&metadata.title, &metadata.author, &metadata.album]
+                .into_iter()
+                .flatten()
+                .cloned(),
+        );
+    }
+    fields
 }
@@ -0,0 +1,240 @@
+use audido_core::fuzzy::FuzzyMatcher;
+use ratatui::{crossterm::event::KeyCode, widgets::ListState};
+
+/// What a key did to the search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchInput {
+    /// The query changed, the list needs filtering again
+    Changed,
+    /// The key was consumed without changing the query
+    Handled,
+    /// Not a search key, the route should handle it
+    Ignored,
+}
+
+/// `/` search over a list: the query being typed and the items it matched.
+///
+/// While filtering, the list shows only matching items (best first) and the
+/// `ListState` selection is a position in that filtered list; `item_index`
+/// maps it back to the full list.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    pub query: String,
+    /// Keys go to the query while typing
+    pub editing: bool,
+    /// Indices of matching items, `None` when not filtering
+    matches: Option<Vec<usize>>,
+}
+
+impl SearchFilter {
+    /// Start typing a query, keeping the current one
+    pub fn start(&mut self) {
+        self.editing = true;
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.matches.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.editing = false;
+        self.matches = None;
+    }
+
+    /// Handle a key: while typing it edits the query, Enter keeps the filter
+    /// and Esc drops it
+    pub fn handle_key(&mut self, key: KeyCode) -> SearchInput {
+        if !self.editing {
+            if key == KeyCode::Esc && self.is_filtering() {
+                self.clear();
+                return SearchInput::Changed;
+            }
+            return SearchInput::Ignored;
+        }
+        match key {
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                SearchInput::Changed
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                SearchInput::Changed
+            }
+            KeyCode::Enter => {
+                self.editing = false;
+                if self.query.trim().is_empty() {
+                    self.clear();
+                }
+                SearchInput::Handled
+            }
+            KeyCode::Esc => {
+                self.clear();
+                SearchInput::Changed
+            }
+            _ => SearchInput::Ignored,
+        }
+    }
+
+    /// Filter `items` by the query over the fields `fields` returns, keeping
+    /// the same item selected if it still matches
+    pub fn apply<T, F, I>(&mut self, items: &[T], list_state: &mut ListState, fields: F)
+    where
+        F: Fn(&T) -> I,
+        I: IntoIterator<Item = String>,
+    {
+        let selected = list_state.selected().and_then(|i| self.item_index(i));
+        let matcher = FuzzyMatcher::new(&self.query);
+        self.matches = if matcher.is_empty() {
+            None
+        } else {
+            Some(matcher.rank(items, fields))
+        };
+
+        let len = self.len(items.len());
+        let position = selected.and_then(|item| self.position_of(item));
+        list_state.select(match position {
+            Some(position) => Some(position),
+            None if len > 0 => Some(0),
+            None => None,
+        });
+    }
+
+    /// Number of items shown
+    pub fn len(&self, total: usize) -> usize {
+        self.matches.as_ref().map_or(total, Vec::len)
+    }
+
+    /// Index into the full list of the item shown at `position`
+    pub fn item_index(&self, position: usize) -> Option<usize> {
+        match &self.matches {
+            Some(matches) => matches.get(position).copied(),
+            None => Some(position),
+        }
+    }
+
+    /// Position in the shown list of the item at `index`, `None` when filtered out
+    pub fn position_of(&self, index: usize) -> Option<usize> {
+        match &self.matches {
+            Some(matches) => matches.iter().position(|i| *i == index),
+            None => Some(index),
+        }
+    }
+
+    /// Indices of the shown items, in display order
+    pub fn visible(&self, total: usize) -> Vec<usize> {
+        match &self.matches {
+            Some(matches) => matches.clone(),
+            None => (0..total).collect(),
+        }
+    }
+
+    /// Query line for a panel title, e.g. " /creep▏ "
+    pub fn title(&self) -> Option<String> {
+        if self.editing {
+            Some(format!(" /{}▏ ", self.query))
+        } else if self.is_filtering() {
+            Some(format!(" /{} (Esc to clear) ", self.query))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: [&str; 5] = [
+        "Creep",
+        "Karma Police",
+        "No Surprises",
+        "Airbag",
+        "Let Down",
+    ];
+
+    fn filter(query: &str, list_state: &mut ListState) -> SearchFilter {
+        let mut search = SearchFilter::default();
+        search.start();
+        for c in query.chars() {
+            assert_eq!(search.handle_key(KeyCode::Char(c)), SearchInput::Changed);
+        }
+        search.apply(&ITEMS, list_state, |item| [item.to_string()]);
+        search
+    }
+
+    #[test]
+    fn item_index_and_position_of_round_trip_while_filtering() {
+        let mut list_state = ListState::default();
+        let search = filter("r", &mut list_state);
+        assert!(search.is_filtering());
+
+        let visible = search.visible(ITEMS.len());
+        assert_eq!(search.len(ITEMS.len()), visible.len());
+        for (position, &index) in visible.iter().enumerate() {
+            assert_eq!(search.item_index(position), Some(index));
+            assert_eq!(search.position_of(index), Some(position));
+        }
+        assert_eq!(search.item_index(visible.len()), None);
+        // "Let Down" has no r
+        assert_eq!(search.position_of(4), None);
+    }
+
+    #[test]
+    fn positions_are_indices_when_not_filtering() {
+        let search = SearchFilter::default();
+        assert_eq!(search.item_index(3), Some(3));
+        assert_eq!(search.position_of(3), Some(3));
+        assert_eq!(search.visible(3), [0, 1, 2]);
+    }
+
+    #[test]
+    fn selection_follows_its_item_through_filtering() {
+        let mut list_state = ListState::default();
+        list_state.select(Some(3));
+        let search = filter("a", &mut list_state);
+        let selected = list_state.selected().and_then(|p| search.item_index(p));
+        assert_eq!(selected, Some(3));
+    }
+
+    #[test]
+    fn selection_moves_to_the_best_match_when_its_item_is_filtered_out() {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+        let search = filter("police", &mut list_state);
+        assert_eq!(list_state.selected(), Some(0));
+        assert_eq!(search.item_index(0), Some(1));
+    }
+
+    #[test]
+    fn nothing_is_selected_when_nothing_matches() {
+        let mut list_state = ListState::default();
+        list_state.select(Some(2));
+        let search = filter("zzz", &mut list_state);
+        assert_eq!(search.len(ITEMS.len()), 0);
+        assert_eq!(list_state.selected(), None);
+    }
+
+    #[test]
+    fn enter_keeps_the_filter_and_esc_clears_it() {
+        let mut list_state = ListState::default();
+        let mut search = filter("creep", &mut list_state);
+        assert_eq!(search.handle_key(KeyCode::Enter), SearchInput::Handled);
+        assert!(!search.editing);
+        assert!(search.is_filtering());
+        assert_eq!(search.handle_key(KeyCode::Char('j')), SearchInput::Ignored);
+
+        assert_eq!(search.handle_key(KeyCode::Esc), SearchInput::Changed);
+        assert!(!search.is_filtering());
+        assert!(search.query.is_empty());
+    }
+
+    #[test]
+    fn enter_on_an_empty_query_stops_searching() {
+        let mut search = SearchFilter::default();
+        search.start();
+        assert_eq!(search.handle_key(KeyCode::Enter), SearchInput::Handled);
+        assert!(!search.editing);
+        assert_eq!(search.title(), None);
+    }
+}
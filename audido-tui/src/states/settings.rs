@@ -1,7 +1,10 @@
+use audido_core::analysis::{AnalysisConfig, TagWriteMode};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SettingsOption {
     Equalizer,
     Normalize,
+    AnalysisTags,
 }
 
 impl SettingsOption {
@@ -9,6 +12,7 @@ impl SettingsOption {
         match self {
             SettingsOption::Equalizer => "Equalizer",
             SettingsOption::Normalize => "Normalize Audio",
+            SettingsOption::AnalysisTags => "Save Analysis to Tags",
         }
     }
 }
@@ -21,18 +25,33 @@ pub struct SettingsState {
     pub is_dialog_open: bool,
     /// Selection index inside the dialog (e.g., 0=On, 1=Off)
     pub dialog_selection_index: usize,
+    /// Whether analysis results are written to tags, as the engine has it
+    pub analysis_tags: TagWriteMode,
 }
 
 impl SettingsState {
     pub fn new() -> Self {
         Self {
-            items: vec![SettingsOption::Equalizer, SettingsOption::Normalize],
+            items: vec![
+                SettingsOption::Equalizer,
+                SettingsOption::Normalize,
+                SettingsOption::AnalysisTags,
+            ],
             selected_index: 0,
             is_dialog_open: false,
             dialog_selection_index: 0,
+            // The engine logs why the settings can't be read
+            analysis_tags: AnalysisConfig::load()
+                .map(|config| config.write_tags)
+                .unwrap_or_default(),
         }
     }
 
+    /// The selected option
+    pub fn selected(&self) -> Option<SettingsOption> {
+        self.items.get(self.selected_index).copied()
+    }
+
     pub fn next_item(&mut self) {
         if !self.items.is_empty() {
             self.selected_index = (self.selected_index + 1) % self.items.len();
@@ -96,6 +96,8 @@ fn draw_controls(f: &mut Frame, area: Rect, _state: &AppState, router: &crate::r
                 Span::raw(" Loop  "),
                 Span::styled("[←/→]", Style::default().fg(Color::Yellow)),
                 Span::raw(" Seek  "),
+                Span::styled("[I]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Info  "),
                 Span::styled("[Tab]", Style::default().fg(Color::Magenta)),
                 Span::raw(" Switch Tab  "),
                 Span::styled("[Q]", Style::default().fg(Color::Red)),
@@ -108,16 +110,84 @@ fn draw_controls(f: &mut Frame, area: Rect, _state: &AppState, router: &crate::r
                 Span::raw(" Navigate  "),
                 Span::styled("[Enter]", Style::default().fg(Color::Yellow)),
                 Span::raw(" Play  "),
-                Span::styled("[N/P]", Style::default().fg(Color::Yellow)),
-                Span::raw(" Next/Prev  "),
-                Span::styled("[L]", Style::default().fg(Color::Yellow)),
-                Span::raw(" Loop  "),
+                Span::styled("[/]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Search  "),
+                Span::styled("[K/J]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Move  "),
+                Span::styled("[N]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Play Next  "),
+                Span::styled("[M]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Mark  "),
+                Span::styled("[E]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Edit Tags  "),
+                Span::styled("[I]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Info  "),
+                Span::styled("[D]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Remove  "),
+                Span::styled("[Shift+D]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Dedupe  "),
+                Span::styled("[S]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Sort  "),
+                Span::styled("[H]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Harmonic Mix  "),
+                Span::styled("[G]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Go to Current  "),
+                Span::styled("[W]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Save Playlist  "),
+                Span::styled("[U]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Add URL  "),
+                Span::styled("[R]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Add Stream  "),
+                Span::styled("[Tab]", Style::default().fg(Color::Magenta)),
+                Span::raw(" Switch Tab  "),
+                Span::styled("[Q]", Style::default().fg(Color::Red)),
+                Span::raw(" Quit"),
+            ]
+        }
+        "Library" => {
+            vec![
+                Span::styled("[↑/↓]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Navigate  "),
+                Span::styled("[Enter/←]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Open/Back  "),
+                Span::styled("[/]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Search  "),
+                Span::styled("[P]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Play  "),
+                Span::styled("[A]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Enqueue  "),
+                Span::styled("[N]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Play Next  "),
+                Span::styled("[V]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Change View  "),
+                Span::styled("[R]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Rescan  "),
                 Span::styled("[Tab]", Style::default().fg(Color::Magenta)),
                 Span::raw(" Switch Tab  "),
                 Span::styled("[Q]", Style::default().fg(Color::Red)),
                 Span::raw(" Quit"),
             ]
         }
+        "Restore Session" | "Save Analysis" => {
+            vec![
+                Span::styled("[↑/↓]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Select  "),
+                Span::styled("[Enter]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Confirm  "),
+                Span::styled("[Esc]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Start Fresh  "),
+                Span::styled("[Q]", Style::default().fg(Color::Red)),
+                Span::raw(" Quit"),
+            ]
+        }
+        "Add URL" | "Add Stream" => {
+            vec![
+                Span::styled("[Enter]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Add to Queue  "),
+                Span::styled("[Esc]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Cancel"),
+            ]
+        }
         "Log" => {
             vec![
                 Span::styled("[↑/↓]", Style::default().fg(Color::Yellow)),
@@ -134,12 +204,38 @@ fn draw_controls(f: &mut Frame, area: Rect, _state: &AppState, router: &crate::r
                 Span::raw(" Nav  "),
                 Span::styled("[Enter]", Style::default().fg(Color::Yellow)),
                 Span::raw(" Select  "),
+                Span::styled("[/]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Search  "),
+                Span::styled("[M]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Mark  "),
+                Span::styled("[E]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Edit Tags  "),
                 Span::styled("[Tab]", Style::default().fg(Color::Magenta)),
                 Span::raw(" Switch Tab  "),
                 Span::styled("[Q]", Style::default().fg(Color::Red)),
                 Span::raw(" Quit"),
             ]
         }
+        "Edit Tags" => {
+            vec![
+                Span::styled("[↑/↓]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Field  "),
+                Span::styled("[Del]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Clear  "),
+                Span::styled("[Enter]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Preview/Write  "),
+                Span::styled("[Esc]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Back"),
+            ]
+        }
+        "Track Info" => {
+            vec![
+                Span::styled("[↑/↓]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Scroll  "),
+                Span::styled("[Esc]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Close"),
+            ]
+        }
         "Settings" => {
             vec![
                 Span::styled("[↑/↓]", Style::default().fg(Color::Yellow)),
@@ -162,6 +258,16 @@ fn draw_controls(f: &mut Frame, area: Rect, _state: &AppState, router: &crate::r
                 Span::raw(" Mode  "),
                 Span::styled("[A]", Style::default().fg(Color::Yellow)),
                 Span::raw(" Add  "),
+                Span::styled("[D]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Delete  "),
+                Span::styled("[S]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Solo  "),
+                Span::styled("[B]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Bypass  "),
+                Span::styled("[U/R]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Undo/Redo  "),
+                Span::styled("[X]", Style::default().fg(Color::Yellow)),
+                Span::raw(" A/B  "),
                 Span::styled("[Esc]", Style::default().fg(Color::Yellow)),
                 Span::raw(" Back  "),
                 Span::styled("[Q]", Style::default().fg(Color::Red)),
@@ -198,10 +304,13 @@ fn draw_status(f: &mut Frame, area: Rect, audio: &AudioState, queue: &QueueState
 
     let volume_bar = format!("Vol: {:3.0}%", audio.volume * 100.0);
     let queue_info = format!("Queue: {}", queue.queue.len());
-    let status_text = format!(
+    let mut status_text = format!(
         "{}  |  {}  |  {}  |  {}",
         audio.status_message, volume_bar, queue_info, loop_icon
     );
+    if let Some(rule) = &audio.active_rule {
+        status_text.push_str(&format!("  |  Rule: {}", rule));
+    }
 
     let paragraph = Paragraph::new(status_text)
         .style(status_style)